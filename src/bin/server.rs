@@ -0,0 +1,117 @@
+//! Headless dedicated server: `cargo run --release --bin server --no-default-features`.
+//!
+//! Settings come from the `network` section of the config file (`--config`, default
+//! server_config.json, written with defaults on first start). The world lives in
+//! saves/world_<seed>; saves/server_level.json remembers which seed that is and the game clock,
+//! so a restarted server picks up the same world. `--seed` only matters for a brand new world.
+//!
+//! Console commands: `list`, `save`, `stop`.
+
+use std::fs;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{unbounded, Receiver};
+use serde::{Deserialize, Serialize};
+
+use minecraft_clone::config_system::ConfigManager;
+use minecraft_clone::logger;
+use minecraft_clone::server::Server;
+use minecraft_clone::tick::TICKS_PER_SECOND;
+use minecraft_clone::world::World;
+
+const LEVEL_FILE: &str = "saves/server_level.json";
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize)]
+struct Level {
+    seed: u32,
+    tick: u64,
+}
+
+fn arg(args: &[String], name: &str) -> Option<String> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).cloned()
+}
+
+fn save(server: &mut Server) {
+    let saved = server.save();
+    let level = Level { seed: server.world.seed, tick: server.world.ticks.game_tick() };
+    let written = fs::create_dir_all("saves").and_then(|_| fs::write(LEVEL_FILE, serde_json::to_string_pretty(&level).unwrap_or_default()));
+    match written {
+        Ok(()) => log::info!("[SERVER] Saved {} chunks at tick {}", saved, level.tick),
+        Err(e) => log::error!("[SERVER] Failed to write {}: {}", LEVEL_FILE, e),
+    }
+}
+
+/// Lines typed into the server console.
+fn console() -> Receiver<String> {
+    let (tx, rx) = unbounded();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line.trim().to_string()).is_err() { break; }
+        }
+    });
+    rx
+}
+
+fn main() {
+    logger::init_logger();
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = arg(&args, "--config").unwrap_or_else(|| "server_config.json".to_string());
+    let config = ConfigManager::new(config_path).get_config().network.clone();
+
+    let level = match fs::read_to_string(LEVEL_FILE).ok().and_then(|s| serde_json::from_str::<Level>(&s).ok()) {
+        Some(level) => level,
+        None => {
+            let seed = arg(&args, "--seed").and_then(|s| s.parse().ok())
+                .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u32);
+            log::info!("[SERVER] Creating a new world with seed {}", seed);
+            Level { seed, tick: 0 }
+        }
+    };
+    let mut world = World::new_persistent(level.seed);
+    world.ticks.set_game_tick(level.tick);
+
+    let mut server = match Server::bind(world, config.clone()) {
+        Ok(server) => server,
+        Err(e) => {
+            log::error!("[SERVER] Cannot listen on port {}: {}", config.server_port, e);
+            std::process::exit(1);
+        }
+    };
+    log::info!("[SERVER] {} - \"{}\"", config.server_name, config.motd);
+    log::info!("[SERVER] Listening on {} (seed {}, up to {} players, view distance {})", server.local_addr().map(|a| a.to_string()).unwrap_or_default(), level.seed, config.max_players, server.world.view_distance);
+
+    let commands = console();
+    let step = Duration::from_secs_f32(1.0 / TICKS_PER_SECOND);
+    let mut last_save = Instant::now();
+    loop {
+        let started = Instant::now();
+        server.tick(step.as_secs_f32());
+
+        while let Ok(command) = commands.try_recv() {
+            match command.as_str() {
+                "stop" => {
+                    log::info!("[SERVER] Stopping");
+                    server.shutdown();
+                    save(&mut server);
+                    return;
+                }
+                "save" => save(&mut server),
+                "list" => {
+                    let players = server.players();
+                    log::info!("[SERVER] {}/{} players online", players.len(), config.max_players);
+                    for (id, addr) in players { log::info!("[SERVER]   player {} from {}", id, addr); }
+                }
+                "" => {}
+                other => log::warn!("[SERVER] Unknown command '{}' (try list, save or stop)", other),
+            }
+        }
+        if last_save.elapsed() >= AUTOSAVE_INTERVAL {
+            save(&mut server);
+            last_save = Instant::now();
+        }
+
+        if let Some(rest) = step.checked_sub(started.elapsed()) { std::thread::sleep(rest); }
+    }
+}
@@ -0,0 +1,92 @@
+//! Biome registry
+//!
+//! Every biome is a `Biome` ID plus one static `BiomeDef` row: the blocks its terrain is made of, the
+//! trees and plants the decoration pass puts on it, the grass/foliage tint the mesher bakes into
+//! vertices, what falls from the sky there, and the climate box it is picked from. Terrain shape
+//! decides the broad class (ocean, badlands, plains, peaks); temperature and humidity decide the rest.
+
+use crate::decoration::TreeKind;
+use crate::world::BlockType;
+
+/// Columns above this height are peaks whatever the climate.
+pub const PEAK_Y: i32 = 102;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[repr(u8)]
+pub enum Biome {
+    Ocean, IceOcean, Plains, #[default] Forest, Taiga, IcePlains, Desert, Jungle, Swamp, Badlands, Peaks,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Precipitation { Rain, Snow, Dry }
+
+pub struct BiomeDef {
+    pub name: &'static str,
+    pub surface: BlockType, // Top block of exposed ground above sea level
+    pub filler: BlockType,  // The few blocks under the surface
+    pub trees: &'static [TreeKind], // Picked uniformly; repeat an entry to weight it
+    pub tree_density: f32, // Trees attempted per column (fractional part is a chance)
+    pub ground_cover: Option<BlockType>, // Plant filling flower patches
+    pub flowers: &'static [BlockType], // One is picked per patch and mixed into the cover
+    pub flower_patches: u32, // Per column
+    pub grass_tint: [u8; 3], // Multiplies the grass-top texture (255 = as painted)
+    pub foliage_tint: [u8; 3], // Multiplies oak leaves
+    pub fog_color: [f32; 3], // Sky and distance fog while the camera is in this biome
+    #[allow(dead_code)] // Read by the weather system, which only the library exposes so far
+    pub precipitation: Precipitation,
+    pub temperature: (f32, f32), // [min, max) of the temperature noise
+    pub humidity: (f32, f32),    // [min, max) of the humidity noise
+}
+
+const ANY: (f32, f32) = (f32::NEG_INFINITY, f32::INFINITY);
+const CLEAR_SKY: [f32; 3] = [0.5, 0.8, 0.9];
+const GRASS_FLOWERS: &[BlockType] = &[BlockType::Rose, BlockType::Dandelion];
+
+// Indexed by `Biome as usize`
+static BIOMES: [BiomeDef; 11] = [
+    BiomeDef { name: "ocean", surface: BlockType::Sand, filler: BlockType::Dirt, trees: &[], tree_density: 0.0, ground_cover: None, flowers: &[], flower_patches: 0,
+        grass_tint: [255, 255, 255], foliage_tint: [255, 255, 255], fog_color: CLEAR_SKY, precipitation: Precipitation::Rain, temperature: ANY, humidity: ANY },
+    BiomeDef { name: "ice_ocean", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[], tree_density: 0.0, ground_cover: None, flowers: &[], flower_patches: 0,
+        grass_tint: [200, 225, 235], foliage_tint: [200, 225, 235], fog_color: CLEAR_SKY, precipitation: Precipitation::Snow, temperature: (f32::NEG_INFINITY, -0.4), humidity: ANY },
+    BiomeDef { name: "plains", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[TreeKind::Oak], tree_density: 0.33, ground_cover: Some(BlockType::TallGrass), flowers: GRASS_FLOWERS, flower_patches: 2,
+        grass_tint: [255, 255, 235], foliage_tint: [255, 255, 235], fog_color: CLEAR_SKY, precipitation: Precipitation::Rain, temperature: ANY, humidity: ANY },
+    BiomeDef { name: "forest", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[TreeKind::Oak, TreeKind::Oak, TreeKind::Birch], tree_density: 4.0, ground_cover: Some(BlockType::TallGrass), flowers: GRASS_FLOWERS, flower_patches: 1,
+        grass_tint: [235, 255, 235], foliage_tint: [230, 250, 230], fog_color: CLEAR_SKY, precipitation: Precipitation::Rain, temperature: ANY, humidity: ANY },
+    BiomeDef { name: "taiga", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[TreeKind::Spruce], tree_density: 3.0, ground_cover: None, flowers: &[], flower_patches: 0,
+        grass_tint: [200, 230, 215], foliage_tint: [190, 225, 210], fog_color: CLEAR_SKY, precipitation: Precipitation::Snow, temperature: (f32::NEG_INFINITY, -0.2), humidity: (0.0, f32::INFINITY) },
+    BiomeDef { name: "ice_plains", surface: BlockType::Snow, filler: BlockType::Dirt, trees: &[], tree_density: 0.0, ground_cover: None, flowers: &[], flower_patches: 0,
+        grass_tint: [210, 230, 235], foliage_tint: [210, 230, 235], fog_color: [0.9, 0.9, 1.0], precipitation: Precipitation::Snow, temperature: (f32::NEG_INFINITY, -0.2), humidity: (f32::NEG_INFINITY, 0.0) },
+    BiomeDef { name: "desert", surface: BlockType::Sand, filler: BlockType::Sand, trees: &[], tree_density: 0.0, ground_cover: Some(BlockType::DeadBush), flowers: &[], flower_patches: 1,
+        grass_tint: [255, 240, 170], foliage_tint: [255, 240, 170], fog_color: [0.8, 0.7, 0.5], precipitation: Precipitation::Dry, temperature: (0.3, f32::INFINITY), humidity: (f32::NEG_INFINITY, 0.0) },
+    BiomeDef { name: "jungle", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[TreeKind::TallOak, TreeKind::Oak], tree_density: 5.0, ground_cover: Some(BlockType::TallGrass), flowers: GRASS_FLOWERS, flower_patches: 1,
+        grass_tint: [215, 255, 190], foliage_tint: [200, 255, 170], fog_color: CLEAR_SKY, precipitation: Precipitation::Rain, temperature: (0.3, f32::INFINITY), humidity: (0.0, f32::INFINITY) },
+    BiomeDef { name: "swamp", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[TreeKind::Oak], tree_density: 0.33, ground_cover: Some(BlockType::TallGrass), flowers: &[], flower_patches: 1,
+        grass_tint: [170, 185, 130], foliage_tint: [160, 180, 120], fog_color: [0.3, 0.4, 0.2], precipitation: Precipitation::Rain, temperature: ANY, humidity: (0.45, f32::INFINITY) },
+    BiomeDef { name: "badlands", surface: BlockType::Sand, filler: BlockType::Sandstone, trees: &[], tree_density: 0.0, ground_cover: Some(BlockType::DeadBush), flowers: &[], flower_patches: 1,
+        grass_tint: [240, 220, 160], foliage_tint: [240, 220, 160], fog_color: CLEAR_SKY, precipitation: Precipitation::Dry, temperature: ANY, humidity: ANY },
+    BiomeDef { name: "peaks", surface: BlockType::Grass, filler: BlockType::Dirt, trees: &[], tree_density: 0.0, ground_cover: None, flowers: &[], flower_patches: 0,
+        grass_tint: [215, 235, 225], foliage_tint: [215, 235, 225], fog_color: CLEAR_SKY, precipitation: Precipitation::Snow, temperature: ANY, humidity: ANY },
+];
+
+impl Biome {
+    pub fn def(self) -> &'static BiomeDef { &BIOMES[self as usize] }
+
+    pub fn name(self) -> &'static str { self.def().name }
+
+    /// Picks the biome for a point from the terrain-shape and climate noise at its column.
+    pub fn select(cont: f32, eros: f32, temp: f32, humid: f32, y: i32) -> Biome {
+        if y > PEAK_Y { return Biome::Peaks; }
+        let candidates: &[Biome] = if cont < -0.25 { &[Biome::IceOcean, Biome::Ocean] }
+            else if eros < -0.5 { &[Biome::Badlands] }
+            else if eros > 0.4 { &[Biome::Plains] }
+            else { &[Biome::Taiga, Biome::IcePlains, Biome::Desert, Biome::Jungle, Biome::Swamp, Biome::Forest] };
+        // Rows are checked in order and the last one covers everything, so the climate boxes may overlap
+        candidates.iter().copied().find(|b| b.def().has_climate(temp, humid)).unwrap_or(candidates[candidates.len() - 1])
+    }
+}
+
+impl BiomeDef {
+    pub fn has_climate(&self, temp: f32, humid: f32) -> bool {
+        temp >= self.temperature.0 && temp < self.temperature.1 && humid >= self.humidity.0 && humid < self.humidity.1
+    }
+}
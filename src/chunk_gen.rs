@@ -0,0 +1,155 @@
+//! Background chunk generation service
+//!
+//! Chunk coordinates go into a shared priority queue ordered by (column) distance to the player.
+//! A pool of worker threads, each with its own `NoiseGenerator`, pops the closest request, loads it
+//! from the region store or runs `World::generate_single_chunk`, and sends the finished `Chunk`
+//! back over a crossbeam channel. The main thread drains that channel once per frame.
+//! The same workers light decorated columns (`lighting::light_sections`), ahead of any generation.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::lighting::light_sections;
+use crate::noise_gen::NoiseGenerator;
+use crate::region::RegionStore;
+use crate::world::{Chunk, World};
+
+type ChunkKey = (i32, i32, i32);
+type Column = ((i32, i32), Vec<Chunk>); // A column's sections, bottom to top
+
+struct GenQueue {
+    heap: BinaryHeap<Reverse<(i32, ChunkKey)>>,
+    lighting: VecDeque<Column>,
+    center: (i32, i32),
+    shutdown: bool,
+}
+
+enum Job { Generate(ChunkKey), Light(Column) }
+
+struct GenShared {
+    queue: Mutex<GenQueue>,
+    ready: Condvar,
+}
+
+pub struct ChunkGenPool {
+    shared: Arc<GenShared>,
+    rx: Receiver<(ChunkKey, Chunk)>,
+    lit_rx: Receiver<Column>,
+    in_flight: Mutex<HashSet<ChunkKey>>, // Queued or being generated, not yet handed back
+}
+
+fn column_dist(center: (i32, i32), key: ChunkKey) -> i32 {
+    let dx = key.0 - center.0;
+    let dz = key.2 - center.1;
+    dx * dx + dz * dz
+}
+
+impl ChunkGenPool {
+    pub fn new(seed: u32, storage: Option<Arc<RegionStore>>) -> Self {
+        let shared = Arc::new(GenShared {
+            queue: Mutex::new(GenQueue { heap: BinaryHeap::new(), lighting: VecDeque::new(), center: (0, 0), shutdown: false }),
+            ready: Condvar::new(),
+        });
+        let (tx, rx) = unbounded();
+        let (lit_tx, lit_rx) = unbounded();
+        // Leave one core for the main thread; the mesh workers cap themselves at 8 as well
+        let threads = num_cpus::get().saturating_sub(1).clamp(1, 8);
+        for i in 0..threads {
+            let shared = shared.clone();
+            let tx = tx.clone();
+            let lit_tx = lit_tx.clone();
+            let storage = storage.clone();
+            let _ = std::thread::Builder::new()
+                .name(format!("chunk-gen-{}", i))
+                .spawn(move || Self::worker(shared, tx, lit_tx, seed, storage));
+        }
+        log::info!("[WORLDGEN] Started {} generation workers", threads);
+        Self { shared, rx, lit_rx, in_flight: Mutex::new(HashSet::new()) }
+    }
+
+    fn worker(shared: Arc<GenShared>, tx: Sender<(ChunkKey, Chunk)>, lit_tx: Sender<Column>, seed: u32, storage: Option<Arc<RegionStore>>) {
+        let noise_gen = NoiseGenerator::new(seed);
+        loop {
+            let job = {
+                let mut queue = shared.queue.lock().unwrap();
+                loop {
+                    if queue.shutdown { return; }
+                    // Columns waiting for light are otherwise finished, so they go first
+                    if let Some(column) = queue.lighting.pop_front() { break Job::Light(column); }
+                    if let Some(Reverse((_, key))) = queue.heap.pop() { break Job::Generate(key); }
+                    queue = shared.ready.wait(queue).unwrap();
+                }
+            };
+            let key = match job {
+                Job::Generate(key) => key,
+                Job::Light(((cx, cz), sections)) => {
+                    if lit_tx.send(((cx, cz), light_sections(cx, cz, sections))).is_err() { return; }
+                    continue;
+                }
+            };
+            let (cx, cy, cz) = key;
+            let chunk = match storage.as_ref().and_then(|s| s.load_chunk(cx, cy, cz)) {
+                Some(mut chunk) => { chunk.fill_biomes(cx, cy, cz, &noise_gen); chunk }
+                None => World::generate_single_chunk(cx, cy, cz, &noise_gen),
+            };
+            if tx.send((key, chunk)).is_err() { return; } // World was dropped
+        }
+    }
+
+    /// Queues `key` unless it is already queued or in progress. Returns true if it was added.
+    pub fn request(&self, key: ChunkKey) -> bool {
+        if !self.in_flight.lock().unwrap().insert(key) { return false; }
+        let mut queue = self.shared.queue.lock().unwrap();
+        let dist = column_dist(queue.center, key);
+        queue.heap.push(Reverse((dist, key)));
+        drop(queue);
+        self.shared.ready.notify_one();
+        true
+    }
+
+    /// Lights a decorated column's `sections` (bottom to top) in the background; the result comes
+    /// back through `drain_lit`.
+    pub fn light(&self, column: (i32, i32), sections: Vec<Chunk>) {
+        self.shared.queue.lock().unwrap().lighting.push_back((column, sections));
+        self.shared.ready.notify_one();
+    }
+
+    /// Re-sorts the queue around a new player column and drops requests further than `keep_radius`.
+    pub fn reprioritize(&self, center: (i32, i32), keep_radius: i32) {
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.center == center { return; }
+        queue.center = center;
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let old = std::mem::take(&mut queue.heap);
+        for Reverse((_, key)) in old {
+            let dist = column_dist(center, key);
+            if dist > keep_radius * keep_radius { in_flight.remove(&key); continue; }
+            queue.heap.push(Reverse((dist, key)));
+        }
+    }
+
+    /// Finished chunks, non-blocking.
+    pub fn drain(&self) -> Vec<(ChunkKey, Chunk)> {
+        let done: Vec<_> = self.rx.try_iter().collect();
+        if !done.is_empty() {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            for (key, _) in &done { in_flight.remove(key); }
+        }
+        done
+    }
+
+    /// Columns lit since the last call, non-blocking.
+    pub fn drain_lit(&self) -> Vec<Column> { self.lit_rx.try_iter().collect() }
+
+    pub fn pending(&self) -> usize { self.in_flight.lock().unwrap().len() }
+}
+
+impl Drop for ChunkGenPool {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().shutdown = true;
+        self.shared.ready.notify_all();
+    }
+}
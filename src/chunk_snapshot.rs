@@ -0,0 +1,61 @@
+//! Immutable meshing input
+//!
+//! A mesh worker only ever looks at one section and the cells touching it, so instead of sharing
+//! the whole `World` it gets a `ChunkSnapshot`: the section's blocks and light plus a one-block
+//! border copied from its 26 neighbours, and the section's biome columns. Capturing one costs the
+//! same whatever the size of the world, so remeshing scales with the number of dirty sections.
+
+use crate::biome::Biome;
+use crate::world::{BlockPos, BlockType, World, CHUNK_SIZE_X, CHUNK_SIZE_Y, CHUNK_SIZE_Z};
+
+const PX: usize = CHUNK_SIZE_X + 2;
+const PY: usize = CHUNK_SIZE_Y + 2;
+const PZ: usize = CHUNK_SIZE_Z + 2;
+
+pub struct ChunkSnapshot {
+    pub key: (i32, i32, i32),
+    pub is_empty: bool,
+    blocks: Box<[BlockType]>, // PX * PY * PZ, local coordinates shifted by one
+    light: Box<[u8]>,
+    biomes: [Biome; CHUNK_SIZE_X * CHUNK_SIZE_Z],
+}
+
+#[inline]
+fn index(x: i32, y: i32, z: i32) -> usize { ((y + 1) as usize * PZ + (z + 1) as usize) * PX + (x + 1) as usize }
+
+impl ChunkSnapshot {
+    /// Copies section `key` and its border out of `world`. Missing and all-air sections come back
+    /// empty without copying anything. Border cells read exactly what `World::get_block` /
+    /// `get_light_world` would return.
+    pub fn capture(world: &World, key: (i32, i32, i32)) -> Self {
+        let Some(chunk) = world.chunks.get(&key).filter(|c| !c.is_empty) else {
+            return Self { key, is_empty: true, blocks: Box::default(), light: Box::default(), biomes: [Biome::default(); CHUNK_SIZE_X * CHUNK_SIZE_Z] };
+        };
+        let (cx, cy, cz) = key;
+        let mut blocks = vec![BlockType::Air; PX * PY * PZ].into_boxed_slice();
+        let mut light = vec![0u8; PX * PY * PZ].into_boxed_slice();
+        for y in -1..=CHUNK_SIZE_Y as i32 { for z in -1..=CHUNK_SIZE_Z as i32 { for x in -1..=CHUNK_SIZE_X as i32 {
+            let inside = (0..CHUNK_SIZE_X as i32).contains(&x) && (0..CHUNK_SIZE_Y as i32).contains(&y) && (0..CHUNK_SIZE_Z as i32).contains(&z);
+            let i = index(x, y, z);
+            if inside {
+                blocks[i] = chunk.get_block(x as usize, y as usize, z as usize);
+                light[i] = chunk.get_light(x as usize, y as usize, z as usize);
+            } else {
+                let pos = BlockPos { x: cx * 16 + x, y: cy * 16 + y, z: cz * 16 + z };
+                blocks[i] = world.get_block(pos);
+                light[i] = world.get_light_world(pos);
+            }
+        }}}
+        Self { key, is_empty: false, blocks, light, biomes: chunk.biomes }
+    }
+
+    /// Block at section-local coordinates, each in -1..=16.
+    #[inline]
+    pub fn block(&self, x: i32, y: i32, z: i32) -> BlockType { self.blocks[index(x, y, z)] }
+
+    /// Combined sky/block light at section-local coordinates, each in -1..=16.
+    #[inline]
+    pub fn light(&self, x: i32, y: i32, z: i32) -> u8 { self.light[index(x, y, z)] }
+
+    pub fn biome(&self, x: usize, z: usize) -> Biome { self.biomes[z * CHUNK_SIZE_X + x] }
+}
@@ -0,0 +1,219 @@
+//! Weather-driven cloud layer
+//!
+//! The clouds drawn are the ones `weather_system` simulates: every `Cloud` drifts with the
+//! `WindSystem`, and here its footprint is cut into a grid of cells with ragged, deterministic
+//! edges. In fancy mode each cell is a box whose sides are only kept where the neighbouring cell is
+//! empty, so the translucent boxes never show inner walls; in fast mode it's a flat sheet. How
+//! thick, dense and dark the clouds look follows the weather, blended through weather changes.
+//!
+//! Cells are instances of one unit cube (`vs_cloud` in shader.wgsl scales and places them and
+//! drops the masked faces), so only the small instance list is uploaded each frame.
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "client")]
+use wgpu::util::DeviceExt;
+#[cfg(feature = "client")]
+use wgpu::*;
+
+use crate::weather_system::{WeatherSystem, WeatherType};
+
+/// Width of a cloud cell in blocks.
+pub const CELL: f32 = 8.0;
+/// Fancy clouds are this share of their `Cloud::size.y` thick in clear weather.
+const THICKNESS: f32 = 0.3;
+#[cfg(feature = "client")]
+const INITIAL_CELLS: u64 = 1024;
+
+const TOP: u32 = 1 << 0;
+const BOTTOM: u32 = 1 << 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CloudMode {
+    Off,
+    Fast,
+    #[default]
+    Fancy,
+}
+
+/// How the current weather dresses the clouds: thickness multiplier, colour multiplier, extra
+/// share of cells filled, and opacity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CloudLook { pub thickness: f32, pub shade: f32, pub coverage: f32, pub opacity: f32 }
+
+impl CloudLook {
+    pub fn of(weather: WeatherType) -> Self {
+        let (thickness, shade, coverage, opacity) = match weather {
+            WeatherType::Clear => (1.0, 1.0, 0.0, 0.8),
+            WeatherType::Cloudy => (1.3, 0.85, 0.1, 0.85),
+            WeatherType::Rain => (1.6, 0.7, 0.2, 0.9),
+            WeatherType::HeavyRain => (1.9, 0.55, 0.25, 0.92),
+            WeatherType::Thunderstorm => (2.2, 0.4, 0.3, 0.95),
+            WeatherType::Snow => (1.5, 0.9, 0.2, 0.9),
+            WeatherType::Blizzard => (2.0, 0.75, 0.3, 0.95),
+            WeatherType::Fog => (1.2, 0.85, 0.1, 0.7),
+            WeatherType::Sandstorm => (1.4, 0.8, 0.15, 0.85),
+            WeatherType::MagicalStorm => (1.8, 0.6, 0.25, 0.9),
+        };
+        Self { thickness, shade, coverage, opacity }
+    }
+
+    /// The look `transition_progress` of the way from the current weather to the one it's turning into.
+    pub fn blended(weather: &WeatherSystem) -> Self {
+        let (a, b, t) = (Self::of(weather.current_weather), Self::of(weather.target_weather), weather.transition_progress);
+        let mix = |x: f32, y: f32| x + (y - x) * t;
+        Self { thickness: mix(a.thickness, b.thickness), shade: mix(a.shade, b.shade), coverage: mix(a.coverage, b.coverage), opacity: mix(a.opacity, b.opacity) }
+    }
+}
+
+#[cfg(feature = "client")]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CloudVertex { pub corner: [f32; 3], pub face: u32, pub shade: f32 }
+
+/// One cell: a box from `min` spanning `size`, drawing the faces whose bits are set in `faces`
+/// (bit order as `CUBE_FACES`: top, bottom, -X, +X, +Z, -Z).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CloudInstance { pub min: [f32; 3], pub size: [f32; 3], pub color: [f32; 4], pub faces: u32 }
+
+#[cfg(feature = "client")]
+impl CloudVertex {
+    const ATTRIBUTES: [VertexAttribute; 3] = vertex_attr_array![0 => Float32x3, 1 => Uint32, 2 => Float32];
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout { array_stride: std::mem::size_of::<CloudVertex>() as BufferAddress, step_mode: VertexStepMode::Vertex, attributes: &Self::ATTRIBUTES }
+    }
+}
+
+#[cfg(feature = "client")]
+impl CloudInstance {
+    const ATTRIBUTES: [VertexAttribute; 4] = vertex_attr_array![3 => Float32x3, 4 => Float32x3, 5 => Float32x4, 6 => Uint32];
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout { array_stride: std::mem::size_of::<CloudInstance>() as BufferAddress, step_mode: VertexStepMode::Instance, attributes: &Self::ATTRIBUTES }
+    }
+}
+
+/// Unit-cube faces, wound counter-clockwise seen from outside, with the classic cloud shading.
+#[cfg(feature = "client")]
+const CUBE_FACES: [([[f32; 3]; 4], f32); 6] = [
+    ([[0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]], 1.0),
+    ([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]], 0.7),
+    ([[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]], 0.9),
+    ([[1.0, 0.0, 1.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0]], 0.9),
+    ([[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]], 0.8),
+    ([[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]], 0.8),
+];
+
+/// Stable pseudo-random value in [0, 1) for cell (`i`, `j`) of the cloud seeded `seed`.
+fn cell_hash(i: i32, j: i32, seed: u32) -> f32 {
+    let mut h = (i as u32).wrapping_mul(0x9E37_79B9) ^ (j as u32).wrapping_mul(0x85EB_CA6B) ^ seed;
+    h ^= h >> 16; h = h.wrapping_mul(0x7FEB_352D);
+    h ^= h >> 15; h = h.wrapping_mul(0x846C_A68B);
+    h ^= h >> 16;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// The cells of every cloud in `weather`, as drawn in `mode` (nothing when clouds are off).
+pub fn cloud_cells(weather: &WeatherSystem, mode: CloudMode) -> Vec<CloudInstance> {
+    let mut cells = Vec::new();
+    if mode == CloudMode::Off { return cells; }
+    let look = CloudLook::blended(weather);
+    for cloud in &weather.clouds {
+        // A cloud's size never changes, so it gives the cloud a stable shape as it drifts
+        let seed = cloud.size.x.to_bits() ^ cloud.size.z.to_bits().rotate_left(16);
+        let (nx, nz) = ((cloud.size.x / CELL).ceil() as i32, (cloud.size.z / CELL).ceil() as i32);
+        let coverage = (0.45 + 0.5 * cloud.density + look.coverage).min(1.0);
+        let filled = |i: i32, j: i32| {
+            if i < 0 || j < 0 || i >= nx || j >= nz { return false; }
+            let (u, v) = ((i as f32 + 0.5) / nx as f32 * 2.0 - 1.0, (j as f32 + 0.5) / nz as f32 * 2.0 - 1.0);
+            let edge = 1.0 - (u * u + v * v);
+            edge > 0.0 && cell_hash(i, j, seed) < coverage * (0.6 + edge)
+        };
+
+        let tint = Vec3::ONE.lerp(Vec3::new(cloud.color[0], cloud.color[1], cloud.color[2]), 0.5) * look.shade;
+        let color = [tint.x, tint.y, tint.z, look.opacity];
+        let height = if mode == CloudMode::Fancy { cloud.size.y * THICKNESS * look.thickness } else { 0.0 };
+        let origin = cloud.position - Vec3::new(nx as f32, 0.0, nz as f32) * (CELL * 0.5);
+        for i in 0..nx {
+            for j in 0..nz {
+                if !filled(i, j) { continue; }
+                let mut faces = TOP | BOTTOM;
+                if mode == CloudMode::Fancy {
+                    for (bit, (di, dj)) in [(-1, 0), (1, 0), (0, 1), (0, -1)].into_iter().enumerate() {
+                        if !filled(i + di, j + dj) { faces |= 1 << (bit + 2); }
+                    }
+                }
+                let min = origin + Vec3::new(i as f32 * CELL, 0.0, j as f32 * CELL);
+                cells.push(CloudInstance { min: min.to_array(), size: [CELL, height, CELL], color, faces });
+            }
+        }
+    }
+    cells
+}
+
+/// GPU side: the unit cube, the per-frame cell instances and the blended pipeline.
+#[cfg(feature = "client")]
+pub struct CloudRenderer {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    instance_buffer: Buffer,
+    instance_count: u32,
+    pub mode: CloudMode,
+}
+
+#[cfg(feature = "client")]
+fn instance_buffer(device: &Device, capacity: u64) -> Buffer {
+    device.create_buffer(&BufferDescriptor { label: Some("Cloud Instances"), size: capacity * std::mem::size_of::<CloudInstance>() as u64, usage: BufferUsages::VERTEX | BufferUsages::COPY_DST, mapped_at_creation: false })
+}
+
+#[cfg(feature = "client")]
+impl CloudRenderer {
+    /// `layout` is the terrain pipeline layout, for the camera and the sky colour the clouds fade into.
+    pub fn new(device: &Device, shader: &ShaderModule, layout: &PipelineLayout, format: TextureFormat) -> Self {
+        let mut vertices = Vec::new(); let mut indices: Vec<u16> = Vec::new();
+        for (face, (corners, shade)) in CUBE_FACES.iter().enumerate() {
+            let base = vertices.len() as u16;
+            vertices.extend(corners.iter().map(|&corner| CloudVertex { corner, face: face as u32, shade: *shade }));
+            indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        let vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor { label: Some("Cloud VB"), contents: bytemuck::cast_slice(&vertices), usage: BufferUsages::VERTEX });
+        let index_buffer = device.create_buffer_init(&util::BufferInitDescriptor { label: Some("Cloud IB"), contents: bytemuck::cast_slice(&indices), usage: BufferUsages::INDEX });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Cloud Pipeline"),
+            layout: Some(layout),
+            vertex: VertexState { module: shader, entry_point: "vs_cloud", buffers: &[CloudVertex::desc(), CloudInstance::desc()] },
+            fragment: Some(FragmentState { module: shader, entry_point: "fs_cloud", targets: &[Some(ColorTargetState { format, blend: Some(BlendState::ALPHA_BLENDING), write_mask: ColorWrites::ALL })] }),
+            primitive: PrimitiveState { cull_mode: Some(Face::Back), ..Default::default() },
+            depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled: true, depth_compare: CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState::default() }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        Self { pipeline, vertex_buffer, index_buffer, instance_buffer: instance_buffer(device, INITIAL_CELLS), instance_count: 0, mode: CloudMode::default() }
+    }
+
+    /// Rebuilds the cells from the weather's clouds, farthest from `eye` first so the blending
+    /// layers correctly where clouds overlap.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, weather: &WeatherSystem, eye: Vec3) {
+        let mut cells = cloud_cells(weather, self.mode);
+        let centre = |c: &CloudInstance| Vec3::from(c.min) + Vec3::from(c.size) * 0.5;
+        cells.sort_by(|a, b| centre(b).distance_squared(eye).total_cmp(&centre(a).distance_squared(eye)));
+        self.instance_count = cells.len() as u32;
+        if cells.is_empty() { return; }
+        let needed = (cells.len() * std::mem::size_of::<CloudInstance>()) as u64;
+        if needed > self.instance_buffer.size() {
+            self.instance_buffer = instance_buffer(device, (cells.len() as u64).next_power_of_two());
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&cells));
+    }
+
+    /// Expects the terrain bind groups to be set already.
+    pub fn draw<'a>(&'a self, pass: &mut RenderPass<'a>) {
+        if self.instance_count == 0 || self.mode == CloudMode::Off { return; }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
+        pass.draw_indexed(0..36, 0, 0..self.instance_count);
+    }
+}
@@ -10,6 +10,10 @@
 
 use glam::Vec3;
 use crate::world::World;
+#[cfg(feature = "client")]
+use crate::world::BlockPos;
+#[cfg(feature = "client")]
+use crate::entity_models::{self, Gait, ModelInstance, ModelKind};
 use crate::player::Player;
 use std::collections::HashMap;
 
@@ -392,7 +396,46 @@ pub enum AnimationState {
     Dodging,
 }
 
+#[cfg(feature = "client")]
+impl AnimationState {
+    /// Leg animation the entity renderer plays for this state.
+    pub fn gait(self) -> Gait {
+        match self {
+            AnimationState::Walking => Gait::Walking,
+            AnimationState::Running => Gait::Running,
+            AnimationState::Jumping | AnimationState::Falling => Gait::Falling,
+            _ => Gait::Idle,
+        }
+    }
+}
+
 impl Mob {
+    /// How the renderer draws this mob (see `Renderer::submit_models`): its model, skin and size,
+    /// lit by the block it stands in and animated by its `animation_state`.
+    #[cfg(feature = "client")]
+    pub fn model_instance(&self, world: &World) -> ModelInstance {
+        use std::f32::consts::FRAC_PI_2;
+        let (kind, skin, scale, arm_pitch) = match self.mob_type {
+            MobType::Zombie => (ModelKind::Humanoid, entity_models::SKIN_ZOMBIE, 0.95, FRAC_PI_2),
+            MobType::Skeleton => (ModelKind::Skeleton, entity_models::SKIN_SKELETON, 0.95, 0.0),
+            MobType::Creeper => (ModelKind::Creeper, entity_models::SKIN_CREEPER, 1.0, 0.0),
+            MobType::Spider => (ModelKind::Quadruped, entity_models::SKIN_SPIDER, 1.3, 0.0),
+            MobType::Wolf => (ModelKind::Quadruped, entity_models::SKIN_WOLF, 0.8, 0.0),
+            MobType::Cat => (ModelKind::Quadruped, entity_models::SKIN_WOLF, 0.6, 0.0),
+            MobType::Horse => (ModelKind::Quadruped, entity_models::SKIN_VILLAGER, 1.8, 0.0),
+            MobType::Enderman => (ModelKind::Skeleton, entity_models::SKIN_SPIDER, 1.45, 0.0),
+            MobType::IronGolem => (ModelKind::Humanoid, entity_models::SKIN_SKELETON, 1.35, 0.0),
+            MobType::SnowGolem => (ModelKind::Humanoid, entity_models::SKIN_WOLF, 0.9, 0.0),
+            MobType::Villager | MobType::Witch => (ModelKind::Humanoid, entity_models::SKIN_VILLAGER, 0.95, 0.0),
+            _ => (ModelKind::Humanoid, entity_models::SKIN_PLAYER, 0.95, 0.0),
+        };
+        // Mobs face atan2(vx, vz); models use the player's convention, forward = (cos yaw, sin yaw)
+        let yaw = FRAC_PI_2 - self.rotation.y;
+        let p = self.position;
+        let light = world.get_light_world(BlockPos { x: p.x.floor() as i32, y: (p.y + 0.5).floor() as i32, z: p.z.floor() as i32 }) as f32;
+        ModelInstance { kind, id: self.id, position: p, yaw, look_yaw: yaw, look_pitch: self.rotation.x, gait: Some(self.animation_state.gait()), arm_pitch, skin, scale, light }
+    }
+
     pub fn new(mob_type: MobType, position: Vec3) -> Self {
         let (health, max_health, armor, speed, damage, attack_range, detection_range) = match &mob_type {
             MobType::Zombie => (20.0, 20.0, 2.0, 1.0, 3.0, 2.0, 16.0),
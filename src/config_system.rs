@@ -14,6 +14,10 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use crate::clouds::CloudMode;
+#[cfg(feature = "client")]
+use crate::post::PostSettings;
+
 /// DIABOLICAL Game Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
@@ -37,6 +41,8 @@ pub struct GraphicsConfig {
     pub render_distance: u32,
     pub max_fps: u32,
     pub shadow_quality: ShadowQuality,
+    #[serde(default)]
+    pub clouds: CloudMode,
     pub texture_quality: TextureQuality,
     pub particle_quality: ParticleQuality,
     pub anti_aliasing: AntiAliasing,
@@ -64,6 +70,19 @@ pub enum ShadowQuality {
     Epic,
 }
 
+impl ShadowQuality {
+    /// Shadow cascade count and the size of each cascade's map; no cascades when shadows are off.
+    pub fn cascades(&self) -> (u32, u32) {
+        match self {
+            ShadowQuality::Off => (0, 0),
+            ShadowQuality::Low => (2, 1024),
+            ShadowQuality::Medium => (3, 2048),
+            ShadowQuality::High => (4, 2048),
+            ShadowQuality::Ultra | ShadowQuality::Epic => (4, 4096),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TextureQuality {
     Low,
@@ -90,6 +109,21 @@ pub enum AntiAliasing {
     TAA,
 }
 
+impl AntiAliasing {
+    /// Whether the post chain's FXAA pass runs. FXAA is the only anti-aliasing the renderer has;
+    /// the MSAA and TAA settings are logged as unsupported and leave edges as they are.
+    pub fn fxaa(&self) -> bool {
+        match self {
+            AntiAliasing::Off => false,
+            AntiAliasing::FXAA => true,
+            other => {
+                log::warn!("[CONFIG] {:?} anti-aliasing is not supported, only FXAA is; rendering without it", other);
+                false
+            }
+        }
+    }
+}
+
 /// DIABOLICAL Audio Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioConfig {
@@ -338,8 +372,9 @@ impl GameConfig {
             return Err(ConfigError::InvalidValue("FOV must be between 30 and 120".to_string()));
         }
         
-        if self.graphics.render_distance < 2 || self.graphics.render_distance > 32 {
-            return Err(ConfigError::InvalidValue("Render distance must be between 2 and 32".to_string()));
+        // Past the first couple of LOD rings chunks are meshed coarsely, so this can go well beyond full detail
+        if self.graphics.render_distance < 2 || self.graphics.render_distance > 48 {
+            return Err(ConfigError::InvalidValue("Render distance must be between 2 and 48".to_string()));
         }
 
         // Validate audio settings
@@ -355,12 +390,23 @@ impl GameConfig {
         Ok(())
     }
 
+    #[cfg(feature = "client")]
     pub fn apply_graphics_settings(&self, renderer: &mut crate::renderer::Renderer) {
         // Apply graphics settings to renderer
         // This would need to be implemented with the actual renderer
         renderer.set_render_distance(self.graphics.render_distance);
         renderer.set_max_fps(self.graphics.max_fps);
         renderer.set_fov(self.graphics.fov);
+        let (cascades, map_size) = self.graphics.shadow_quality.cascades();
+        renderer.set_shadows(cascades, map_size);
+        renderer.set_cloud_mode(self.graphics.clouds);
+        renderer.set_post_processing(PostSettings {
+            fxaa: self.graphics.anti_aliasing.fxaa(),
+            bloom: self.graphics.bloom,
+            tonemap: true,
+            exposure: self.graphics.brightness,
+            gamma: self.graphics.gamma,
+        });
     }
 
     pub fn apply_audio_settings(&self, audio_system: &mut crate::AudioSystem) {
@@ -382,6 +428,7 @@ impl Default for GraphicsConfig {
             render_distance: 12,
             max_fps: 60,
             shadow_quality: ShadowQuality::Medium,
+            clouds: CloudMode::Fancy,
             texture_quality: TextureQuality::High,
             particle_quality: ParticleQuality::Medium,
             anti_aliasing: AntiAliasing::FXAA,
@@ -0,0 +1,63 @@
+// --- DIABOLICAL COMPUTE CULLER ---
+// One invocation per chunk mesh: tests its bounding sphere against the camera frustum and writes
+// its draw command in place, with an instance count of 0 when it's off screen.
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct ChunkCullData {
+    pos_radius: vec4<f32>,
+    index_count: u32,
+    base_vertex: i32,
+    base_index: u32,
+    _pad: u32,
+};
+
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+struct CullParams {
+    chunk_count: u32,
+};
+
+@group(1) @binding(0) var<storage, read> chunks: array<ChunkCullData>;
+@group(1) @binding(1) var<storage, read_write> draw_commands: array<DrawIndexedIndirect>;
+@group(1) @binding(2) var<storage, read> params: CullParams;
+
+@compute @workgroup_size(64)
+fn compute_cull(@builtin(global_invocation_id) id: vec3<u32>) {
+    let idx = id.x;
+    if (idx >= params.chunk_count) { return; }
+
+    let center = chunks[idx].pos_radius.xyz;
+    let radius = chunks[idx].pos_radius.w;
+
+    // DIABOLICAL FRUSTUM EXTRACTION: Same planes as Player::get_frustum_planes, normalised so the
+    // sphere test measures real distances
+    let m = camera.view_proj;
+    let r0 = vec4<f32>(m[0][0], m[1][0], m[2][0], m[3][0]);
+    let r1 = vec4<f32>(m[0][1], m[1][1], m[2][1], m[3][1]);
+    let r2 = vec4<f32>(m[0][2], m[1][2], m[2][2], m[3][2]);
+    let r3 = vec4<f32>(m[0][3], m[1][3], m[2][3], m[3][3]);
+    var planes = array<vec4<f32>, 6>(r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2);
+
+    var visible = true;
+    for (var i = 0; i < 6; i++) {
+        let p = planes[i] / length(planes[i].xyz);
+        if (dot(p.xyz, center) + p.w < -radius) { visible = false; }
+    }
+
+    draw_commands[idx].index_count = chunks[idx].index_count;
+    draw_commands[idx].instance_count = select(0u, 1u, visible);
+    draw_commands[idx].first_index = chunks[idx].base_index;
+    draw_commands[idx].base_vertex = chunks[idx].base_vertex;
+    draw_commands[idx].first_instance = 0u;
+}
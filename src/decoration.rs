@@ -0,0 +1,224 @@
+//! Decoration pass: features on top of finished terrain
+//!
+//! Terrain generation only lays down stone, soil, surface blocks and water. Trees, ore veins,
+//! boulders and flower patches are features rooted in one column that may reach into the columns
+//! around it. A column is decorated once the 3x3 columns around it have terrain: the features of
+//! all nine are rebuilt from the seed, and only the blocks landing in the column being decorated
+//! are written, in a fixed order. Every feature therefore comes out whole across chunk and section
+//! borders, and the result doesn't depend on which neighbour happened to load first. A column's
+//! features are kept from the first time its own 3x3 is loaded until all nine are decorated, so
+//! they are built once rather than for each of the nine columns that need them.
+
+use std::collections::HashSet;
+
+use crate::biome::Biome;
+use crate::world::{chunk_rng_seed, BlockPos, BlockType, SimpleRng, World, CHUNK_SIZE_Y, WATER_LEVEL, WORLD_HEIGHT};
+
+const SECTIONS: i32 = WORLD_HEIGHT / CHUNK_SIZE_Y as i32;
+
+/// (ore, veins per column, lowest y, highest y, blocks per vein)
+const ORES: [(BlockType, u32, i32, i32, u32); 6] = [
+    (BlockType::CoalOre, 16, 5, 110, 10),
+    (BlockType::IronOre, 10, 5, 64, 7),
+    (BlockType::GoldOre, 3, 5, 32, 6),
+    (BlockType::RedstoneOre, 5, 5, 24, 6),
+    (BlockType::LapisOre, 2, 5, 32, 5),
+    (BlockType::DiamondOre, 1, 5, 16, 5),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TreeKind { Oak, Birch, Spruce, TallOak }
+
+/// What a placed block is allowed to overwrite.
+#[derive(Clone, Copy)]
+pub(crate) enum Replace { Air, Foliage, Stone }
+
+impl Replace {
+    fn allows(self, current: BlockType) -> bool {
+        match self {
+            Replace::Air => current == BlockType::Air,
+            Replace::Foliage => current == BlockType::Air || current.is_cross_model() || is_leaves(current),
+            Replace::Stone => current == BlockType::Stone,
+        }
+    }
+}
+
+fn is_leaves(block: BlockType) -> bool { matches!(block, BlockType::Leaves | BlockType::SpruceLeaves | BlockType::BirchLeaves) }
+
+pub(crate) type Placement = (BlockPos, BlockType, Replace);
+
+fn offset(pos: BlockPos, dx: i32, dy: i32, dz: i32) -> BlockPos { BlockPos { x: pos.x + dx, y: pos.y + dy, z: pos.z + dz } }
+
+fn below(rng: &mut SimpleRng, n: u32) -> i32 { (rng.next_u32() % n) as i32 }
+
+/// Blocks worldgen itself produces; decorations and player builds are ignored when finding the ground.
+fn is_terrain(block: BlockType) -> bool {
+    matches!(block, BlockType::Stone | BlockType::Dirt | BlockType::Grass | BlockType::Sand | BlockType::Snow | BlockType::Bedrock) || block.is_water()
+}
+
+/// Trunk and canopy of one tree standing on the block below `base`. Logs may replace foliage,
+/// leaves only fill air, so overlapping trees and trunks stay intact in any order.
+pub fn tree_blocks(kind: TreeKind, base: BlockPos, rng: &mut SimpleRng) -> Vec<(BlockPos, BlockType)> {
+    let (log, leaves) = match kind {
+        TreeKind::Oak | TreeKind::TallOak => (BlockType::Wood, BlockType::Leaves),
+        TreeKind::Birch => (BlockType::BirchWood, BlockType::BirchLeaves),
+        TreeKind::Spruce => (BlockType::SpruceWood, BlockType::SpruceLeaves),
+    };
+    let h = match kind { TreeKind::Oak | TreeKind::Birch => 5, TreeKind::TallOak => 7, TreeKind::Spruce => 6 } + below(rng, 3);
+    let mut out: Vec<_> = (0..h).map(|i| (offset(base, 0, i, 0), log)).collect();
+    if kind == TreeKind::Spruce {
+        // Cone: single tip, then alternating rings of radius 1 and 2 down to just above the ground
+        for dy in 3..=h {
+            let radius: i32 = if dy == h { 0 } else if dy >= h - 2 || (h - dy) % 2 == 1 { 1 } else { 2 };
+            for dx in -radius..=radius { for dz in -radius..=radius {
+                if radius == 2 && dx.abs() == 2 && dz.abs() == 2 { continue; }
+                out.push((offset(base, dx, dy, dz), leaves));
+            }}
+        }
+    } else {
+        // The classic blob worldgen always used: two wide layers, a narrow one, a cap
+        for dy in (h - 3)..=h {
+            let radius: i32 = if dy == h { 0 } else if dy == h - 1 { 1 } else { 2 };
+            for dx in -radius..=radius { for dz in -radius..=radius {
+                if dx.abs() + dz.abs() > radius + 1 { continue; }
+                out.push((offset(base, dx, dy, dz), leaves));
+            }}
+        }
+    }
+    out
+}
+
+impl World {
+    /// Highest worldgen block in a column, ignoring anything decorations or players put on top.
+    fn terrain_surface(&self, x: i32, z: i32) -> Option<(i32, BlockType)> {
+        (0..WORLD_HEIGHT).rev().map(|y| (y, self.get_block(BlockPos { x, y, z }))).find(|&(_, b)| is_terrain(b))
+    }
+
+    /// Whether the 3x3 columns around (cx, cz) all have terrain.
+    fn area_loaded(&self, cx: i32, cz: i32) -> bool {
+        (-1..=1).all(|dx| (-1..=1).all(|dz| (0..SECTIONS).all(|cy| self.chunks.contains_key(&(cx + dx, cy, cz + dz)))))
+    }
+
+    /// Every feature rooted in source column (sx, sz), in placement order.
+    fn column_features(&self, sx: i32, sz: i32) -> Vec<Placement> {
+        let mut rng = SimpleRng::new(chunk_rng_seed(self.seed, sx, -1, sz));
+        let mut out = Vec::new();
+
+        // 1. Ore veins: short random walks that only ever replace stone
+        for (ore, veins, min_y, max_y, size) in ORES {
+            for _ in 0..veins {
+                let mut p = BlockPos { x: sx * 16 + below(&mut rng, 16), y: min_y + below(&mut rng, (max_y - min_y) as u32), z: sz * 16 + below(&mut rng, 16) };
+                for _ in 0..size {
+                    out.push((p, ore, Replace::Stone));
+                    let step = if rng.next_u32().is_multiple_of(2) { 1 } else { -1 };
+                    match below(&mut rng, 3) { 0 => p.x += step, 1 => p.y += step, _ => p.z += step }
+                }
+            }
+        }
+
+        let (cx, cz) = (sx * 16 + 8, sz * 16 + 8);
+        let Some((center_y, _)) = self.terrain_surface(cx, cz) else { return out; };
+        let biome = self.get_biome(BlockPos { x: cx, y: center_y, z: cz }).unwrap_or_default();
+        let def = biome.def();
+
+        // 2. Boulders: a mossy cobblestone dome sitting on open ground
+        if matches!(biome, Biome::Plains | Biome::Taiga | Biome::Forest) && below(&mut rng, 8) == 0 {
+            let (x, z) = (sx * 16 + below(&mut rng, 16), sz * 16 + below(&mut rng, 16));
+            let radius = 1 + below(&mut rng, 2);
+            if let Some((y, _)) = self.terrain_surface(x, z).filter(|&(y, g)| !g.is_water() && y > WATER_LEVEL) {
+                for dx in -radius..=radius { for dy in -radius..=radius { for dz in -radius..=radius {
+                    if dx * dx + dy * dy + dz * dz > radius * radius + 1 { continue; }
+                    let block = if below(&mut rng, 3) == 0 { BlockType::Cobblestone } else { BlockType::MossyCobble };
+                    out.push((BlockPos { x: x + dx, y: y + 1 + dy, z: z + dz }, block, Replace::Foliage));
+                }}}
+            }
+        }
+
+        // 3. Trees
+        let mut attempts = def.tree_density as u32;
+        if !def.trees.is_empty() && rng.next_f32() < def.tree_density.fract() { attempts += 1; }
+        let mut trunks: Vec<(i32, i32)> = Vec::new();
+        for _ in 0..attempts {
+            let (x, z) = (sx * 16 + below(&mut rng, 16), sz * 16 + below(&mut rng, 16));
+            let kind = def.trees[below(&mut rng, def.trees.len() as u32) as usize];
+            let Some((y, ground)) = self.terrain_surface(x, z) else { continue; };
+            if !matches!(ground, BlockType::Grass | BlockType::Dirt | BlockType::Snow) || y <= WATER_LEVEL { continue; }
+            if trunks.iter().any(|&(tx, tz)| (tx - x).abs() <= 3 && (tz - z).abs() <= 3) { continue; }
+            trunks.push((x, z));
+            let base = BlockPos { x, y: y + 1, z };
+            for (p, b) in tree_blocks(kind, base, &mut rng) {
+                out.push((p, b, if is_leaves(b) { Replace::Air } else { Replace::Foliage }));
+            }
+        }
+
+        // 4. Flower patches: the biome's ground cover with one kind of flower mixed in
+        let Some(cover) = def.ground_cover else { return out; };
+        for _ in 0..def.flower_patches {
+            let (px, pz) = (sx * 16 + below(&mut rng, 16), sz * 16 + below(&mut rng, 16));
+            let flower = (!def.flowers.is_empty()).then(|| def.flowers[below(&mut rng, def.flowers.len() as u32) as usize]);
+            for _ in 0..10 {
+                let (x, z) = (px + below(&mut rng, 7) - 3, pz + below(&mut rng, 7) - 3);
+                // Drawn before looking at the ground, so what's loaded can't shift the rest of the stream
+                let plant = match flower { Some(f) if below(&mut rng, 3) == 0 => f, _ => cover };
+                let Some((y, ground)) = self.terrain_surface(x, z) else { continue; };
+                let soil = if plant == BlockType::DeadBush { BlockType::Sand } else { BlockType::Grass };
+                if ground == soil { out.push((BlockPos { x, y: y + 1, z }, plant, Replace::Air)); }
+            }
+        }
+        out
+    }
+
+    /// Writes the parts of all nearby features that fall inside column (cx, cz). Returns false
+    /// (and does nothing) while the column is already decorated or any of the 3x3 columns around
+    /// it is still missing terrain.
+    pub fn decorate_column(&mut self, cx: i32, cz: i32) -> bool {
+        if self.decorated_columns.contains(&(cx, cz)) { return false; }
+        if !self.area_loaded(cx, cz) { return false; }
+
+        // Sections off disk or from a server already have their features
+        let mut placements = Vec::new();
+        if !(0..SECTIONS).all(|cy| self.chunks.get(&(cx, cy, cz)).is_some_and(|c| c.decorated)) {
+            for sx in cx - 1..=cx + 1 {
+                for sz in cz - 1..=cz + 1 {
+                    if let Some(features) = self.features.get(&(sx, sz)) { placements.extend_from_slice(features); continue; }
+                    let features = self.column_features(sx, sz);
+                    placements.extend_from_slice(&features);
+                    // Features only read the ground of the 3x3 around their column; once that's
+                    // all there they come out the same every time
+                    if self.area_loaded(sx, sz) { self.features.insert((sx, sz), features); }
+                }
+            }
+        }
+
+        let mut touched = HashSet::new();
+        for (pos, block, rule) in placements {
+            if pos.x.div_euclid(16) != cx || pos.z.div_euclid(16) != cz || pos.y < 0 || pos.y >= WORLD_HEIGHT { continue; }
+            let key = (cx, pos.y.div_euclid(16), cz);
+            let Some(chunk) = self.chunks.get_mut(&key) else { continue; };
+            if chunk.decorated { continue; }
+            let (lx, ly, lz) = (pos.x.rem_euclid(16) as usize, pos.y.rem_euclid(16) as usize, pos.z.rem_euclid(16) as usize);
+            if !rule.allows(chunk.get_block(lx, ly, lz)) { continue; }
+            chunk.set_block(lx, ly, lz, block);
+            chunk.is_empty = false;
+            touched.insert(key);
+        }
+
+        for cy in 0..SECTIONS {
+            let Some(chunk) = self.chunks.get_mut(&(cx, cy, cz)) else { continue; };
+            chunk.decorated = true;
+            if touched.contains(&(cx, cy, cz)) {
+                chunk.blocks.compact();
+                chunk.mesh_dirty = true;
+                self.dirty_chunks.insert((cx, cy, cz));
+            }
+        }
+        self.decorated_columns.insert((cx, cz));
+        // Nobody needs the features of a column whose whole 3x3 is decorated any more
+        for sx in cx - 1..=cx + 1 {
+            for sz in cz - 1..=cz + 1 {
+                if (-1..=1).all(|dx| (-1..=1).all(|dz| self.decorated_columns.contains(&(sx + dx, sz + dz)))) { self.features.remove(&(sx, sz)); }
+            }
+        }
+        true
+    }
+}
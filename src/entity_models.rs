@@ -0,0 +1,307 @@
+//! Instanced entity models
+//!
+//! Players, mobs and dropped items are built from cuboid parts, each turning about its own pivot.
+//! Every model is baked once into one static vertex buffer whose vertices remember their part's
+//! pivot and limb. Per frame the renderer only uploads one `EntityInstance` per entity (placement,
+//! skin, light and four limb angles) and `vs_entity` (shader.wgsl) poses the parts on the GPU, so
+//! hundreds of animated entities cost no vertex rebuilding.
+//!
+//! Models face +X with their feet at the origin and are measured in sixteenths of a block, like
+//! the textures. A skin is three consecutive atlas tiles (skin, shirt, trousers) that parts pick
+//! from; a dropped item is a one-tile cube whose skin is the item's texture.
+
+use glam::{Mat4, Quat, Vec3};
+use std::collections::HashMap;
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+use wgpu::*;
+
+pub const SKIN_PLAYER: u32 = 260;
+// Mob skins are picked by combat_system, which only the library exposes so far
+#[allow(dead_code)] pub const SKIN_ZOMBIE: u32 = 263;
+#[allow(dead_code)] pub const SKIN_SKELETON: u32 = 266;
+#[allow(dead_code)] pub const SKIN_CREEPER: u32 = 269;
+#[allow(dead_code)] pub const SKIN_SPIDER: u32 = 272;
+#[allow(dead_code)] pub const SKIN_WOLF: u32 = 275;
+#[allow(dead_code)] pub const SKIN_VILLAGER: u32 = 278;
+
+/// Limb angle (radians) per block walked: one full stride every ~2.5 blocks.
+const STRIDE: f32 = 2.5;
+/// How quickly the swing amplitude follows the gait, per second.
+const GAIT_BLEND: f32 = 10.0;
+/// A tracked entity that stops sending new positions for this long is standing still.
+const STILL_AFTER: f32 = 0.25;
+/// Tracks for entities not drawn for this long are dropped.
+const FORGET_AFTER: f32 = 5.0;
+const INITIAL_INSTANCES: u64 = 256;
+
+/// Which angle of `EntityInstance::pose` turns a part. Arms swing against the legs on the same
+/// side, so a quadruped's front legs use the arm slots and it trots diagonally.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Limb { Body = 0, Head = 1, RightArm = 2, LeftArm = 3, RightLeg = 4, LeftLeg = 5 }
+
+/// One cuboid of a model, in sixteenths of a block; `tile` indexes the instance's skin.
+pub struct Part { pub limb: Limb, pub min: [f32; 3], pub size: [f32; 3], pub pivot: [f32; 3], pub tile: u32 }
+
+const fn part(limb: Limb, min: [f32; 3], size: [f32; 3], pivot: [f32; 3], tile: u32) -> Part { Part { limb, min, size, pivot, tile } }
+
+const HUMANOID: [Part; 6] = [
+    part(Limb::Head, [-4.0, 24.0, -4.0], [8.0, 8.0, 8.0], [0.0, 24.0, 0.0], 0),
+    part(Limb::Body, [-2.0, 12.0, -4.0], [4.0, 12.0, 8.0], [0.0, 12.0, 0.0], 1),
+    part(Limb::RightArm, [-2.0, 12.0, 4.0], [4.0, 12.0, 4.0], [0.0, 22.0, 6.0], 0),
+    part(Limb::LeftArm, [-2.0, 12.0, -8.0], [4.0, 12.0, 4.0], [0.0, 22.0, -6.0], 0),
+    part(Limb::RightLeg, [-2.0, 0.0, 0.0], [4.0, 12.0, 4.0], [0.0, 12.0, 2.0], 2),
+    part(Limb::LeftLeg, [-2.0, 0.0, -4.0], [4.0, 12.0, 4.0], [0.0, 12.0, -2.0], 2),
+];
+const SKELETON: [Part; 6] = [
+    part(Limb::Head, [-4.0, 24.0, -4.0], [8.0, 8.0, 8.0], [0.0, 24.0, 0.0], 0),
+    part(Limb::Body, [-2.0, 12.0, -4.0], [4.0, 12.0, 8.0], [0.0, 12.0, 0.0], 1),
+    part(Limb::RightArm, [-1.0, 12.0, 4.0], [2.0, 12.0, 2.0], [0.0, 22.0, 5.0], 0),
+    part(Limb::LeftArm, [-1.0, 12.0, -6.0], [2.0, 12.0, 2.0], [0.0, 22.0, -5.0], 0),
+    part(Limb::RightLeg, [-1.0, 0.0, 1.0], [2.0, 12.0, 2.0], [0.0, 12.0, 2.0], 2),
+    part(Limb::LeftLeg, [-1.0, 0.0, -3.0], [2.0, 12.0, 2.0], [0.0, 12.0, -2.0], 2),
+];
+const CREEPER: [Part; 6] = [
+    part(Limb::Head, [-4.0, 18.0, -4.0], [8.0, 8.0, 8.0], [0.0, 18.0, 0.0], 0),
+    part(Limb::Body, [-2.0, 6.0, -4.0], [4.0, 12.0, 8.0], [0.0, 6.0, 0.0], 1),
+    part(Limb::RightArm, [2.0, 0.0, 0.0], [4.0, 6.0, 4.0], [4.0, 6.0, 2.0], 2),
+    part(Limb::LeftArm, [2.0, 0.0, -4.0], [4.0, 6.0, 4.0], [4.0, 6.0, -2.0], 2),
+    part(Limb::RightLeg, [-6.0, 0.0, 0.0], [4.0, 6.0, 4.0], [-4.0, 6.0, 2.0], 2),
+    part(Limb::LeftLeg, [-6.0, 0.0, -4.0], [4.0, 6.0, 4.0], [-4.0, 6.0, -2.0], 2),
+];
+const QUADRUPED: [Part; 6] = [
+    part(Limb::Head, [7.0, 7.0, -3.0], [6.0, 6.0, 6.0], [7.0, 10.0, 0.0], 0),
+    part(Limb::Body, [-8.0, 6.0, -4.0], [16.0, 7.0, 8.0], [0.0, 6.0, 0.0], 1),
+    part(Limb::RightArm, [4.0, 0.0, 1.0], [3.0, 7.0, 3.0], [5.5, 7.0, 2.5], 2),
+    part(Limb::LeftArm, [4.0, 0.0, -4.0], [3.0, 7.0, 3.0], [5.5, 7.0, -2.5], 2),
+    part(Limb::RightLeg, [-7.0, 0.0, 1.0], [3.0, 7.0, 3.0], [-5.5, 7.0, 2.5], 2),
+    part(Limb::LeftLeg, [-7.0, 0.0, -4.0], [3.0, 7.0, 3.0], [-5.5, 7.0, -2.5], 2),
+];
+const ITEM: [Part; 1] = [part(Limb::Body, [-8.0, -8.0, -8.0], [16.0, 16.0, 16.0], [0.0, 0.0, 0.0], 0)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ModelKind { Humanoid, Skeleton, Creeper, Quadruped, Item }
+
+impl ModelKind {
+    pub const ALL: [ModelKind; 5] = [ModelKind::Humanoid, ModelKind::Skeleton, ModelKind::Creeper, ModelKind::Quadruped, ModelKind::Item];
+
+    pub fn parts(self) -> &'static [Part] {
+        match self {
+            ModelKind::Humanoid => &HUMANOID,
+            ModelKind::Skeleton => &SKELETON,
+            ModelKind::Creeper => &CREEPER,
+            ModelKind::Quadruped => &QUADRUPED,
+            ModelKind::Item => &ITEM,
+        }
+    }
+}
+
+/// What the legs are doing; picks the swing amplitude and whether the arms are thrown up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gait { Idle, Walking, Running, Falling }
+
+impl Gait {
+    /// Gait of something moving at `velocity` blocks per second.
+    pub fn from_velocity(velocity: Vec3) -> Gait {
+        let ground_speed = velocity.x.hypot(velocity.z);
+        if velocity.y < -6.0 { Gait::Falling }
+        else if ground_speed > 4.5 { Gait::Running }
+        else if ground_speed > 0.3 { Gait::Walking }
+        else { Gait::Idle }
+    }
+
+    fn swing(self) -> f32 {
+        match self { Gait::Idle => 0.0, Gait::Walking => 0.7, Gait::Running => 1.1, Gait::Falling => 0.2 }
+    }
+}
+
+/// One entity to draw this frame. `id` keys its walk cycle between frames and must be stable
+/// for the entity's lifetime (within its kind); `gait: None` derives it from how fast it moves.
+#[derive(Clone, Copy, Debug)]
+pub struct ModelInstance {
+    pub kind: ModelKind,
+    pub id: u32,
+    pub position: Vec3,
+    pub yaw: f32, // Body heading, with forward = (cos yaw, 0, sin yaw) like the player's camera
+    pub look_yaw: f32,
+    pub look_pitch: f32,
+    pub gait: Option<Gait>,
+    pub arm_pitch: f32, // Resting arm angle, e.g. a zombie's arms held forward
+    pub skin: u32,
+    pub scale: f32,
+    pub light: f32, // 0..=15, like terrain vertices
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelVertex { pub position: [f32; 3], pub pivot: [f32; 3], pub tex_coords: [f32; 2], pub tile: u32, pub limb: u32, pub shade: f32 }
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EntityInstance {
+    pub transform: [[f32; 4]; 4],
+    pub pose: [f32; 4], // Leg swing, arm pitch, head yaw, head pitch
+    pub skin: u32,
+    pub light: f32,
+    pub _pad: [u32; 2],
+}
+
+impl ModelVertex {
+    const ATTRIBUTES: [VertexAttribute; 6] = vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Uint32, 4 => Uint32, 5 => Float32];
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout { array_stride: std::mem::size_of::<ModelVertex>() as BufferAddress, step_mode: VertexStepMode::Vertex, attributes: &Self::ATTRIBUTES }
+    }
+}
+
+impl EntityInstance {
+    const ATTRIBUTES: [VertexAttribute; 7] = vertex_attr_array![6 => Float32x4, 7 => Float32x4, 8 => Float32x4, 9 => Float32x4, 10 => Float32x4, 11 => Uint32, 12 => Float32];
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout { array_stride: std::mem::size_of::<EntityInstance>() as BufferAddress, step_mode: VertexStepMode::Instance, attributes: &Self::ATTRIBUTES }
+    }
+}
+
+/// Unit-cube faces, each wound counter-clockwise seen from outside, with its directional shade.
+const CUBE_FACES: [([[f32; 3]; 4], f32); 6] = [
+    ([[0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]], 1.0),
+    ([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]], 0.5),
+    ([[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]], 0.8),
+    ([[1.0, 0.0, 1.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0]], 0.8),
+    ([[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]], 0.65),
+    ([[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]], 0.65),
+];
+
+/// Every model's cuboids in `ModelKind::ALL` order, in blocks, and each model's index range.
+pub fn bake_models() -> (Vec<ModelVertex>, Vec<u32>, Vec<Range<u32>>) {
+    let (mut vertices, mut indices, mut ranges) = (Vec::new(), Vec::new(), Vec::new());
+    for kind in ModelKind::ALL {
+        let first = indices.len() as u32;
+        for p in kind.parts() {
+            for (corners, shade) in CUBE_FACES {
+                let base = vertices.len() as u32;
+                for (c, uv) in corners.iter().zip([[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]) {
+                    let position = [0, 1, 2].map(|a| (p.min[a] + c[a] * p.size[a]) / 16.0);
+                    vertices.push(ModelVertex { position, pivot: p.pivot.map(|v| v / 16.0), tex_coords: uv, tile: p.tile, limb: p.limb as u32, shade });
+                }
+                indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+            }
+        }
+        ranges.push(first..indices.len() as u32);
+    }
+    (vertices, indices, ranges)
+}
+
+struct Track { position: Vec3, velocity: Vec3, moved_at: f32, seen: f32, phase: f32, swing: f32, raise: f32 }
+
+/// Walk cycles of the entities drawn recently. Positions can arrive in steps (remote players
+/// update 20 times a second), so velocity is only re-measured when an entity actually moves.
+#[derive(Default)]
+pub struct Animator { tracks: HashMap<(ModelKind, u32), Track> }
+
+impl Animator {
+    /// Places and poses `model` at animation time `time` (seconds), advancing its walk cycle.
+    pub fn pose(&mut self, model: &ModelInstance, time: f32) -> EntityInstance {
+        let rotation = Quat::from_rotation_y(-model.yaw);
+        let transform = Mat4::from_scale_rotation_translation(Vec3::splat(model.scale), rotation, model.position).to_cols_array_2d();
+        let light = model.light;
+        if model.kind == ModelKind::Item {
+            return EntityInstance { transform, pose: [0.0; 4], skin: model.skin, light, _pad: [0; 2] };
+        }
+
+        let track = self.tracks.entry((model.kind, model.id)).or_insert(Track { position: model.position, velocity: Vec3::ZERO, moved_at: time, seen: time, phase: 0.0, swing: 0.0, raise: 0.0 });
+        let dt = (time - track.seen).max(0.0);
+        let step = model.position - track.position;
+        if step != Vec3::ZERO {
+            track.velocity = step / (time - track.moved_at).max(1.0 / 120.0);
+            track.phase += step.x.hypot(step.z) * STRIDE / model.scale;
+            track.position = model.position;
+            track.moved_at = time;
+        } else if time - track.moved_at > STILL_AFTER {
+            track.velocity = Vec3::ZERO;
+        }
+        track.seen = time;
+
+        let gait = model.gait.unwrap_or_else(|| Gait::from_velocity(track.velocity));
+        let blend = 1.0 - (-dt * GAIT_BLEND).exp();
+        track.swing += (gait.swing() - track.swing) * blend;
+        track.raise += (if gait == Gait::Falling { 1.0 } else { 0.0 } - track.raise) * blend;
+
+        let swing = track.phase.sin() * track.swing;
+        let head_yaw = (model.look_yaw - model.yaw + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+        let pose = [swing, model.arm_pitch + track.raise * 2.6, head_yaw.clamp(-1.2, 1.2), model.look_pitch.clamp(-1.4, 1.4)];
+        EntityInstance { transform, pose, skin: model.skin, light, _pad: [0; 2] }
+    }
+
+    /// Forgets entities that haven't been drawn for a while.
+    pub fn prune(&mut self, time: f32) {
+        self.tracks.retain(|_, t| time - t.seen < FORGET_AFTER);
+    }
+}
+
+/// GPU side: the baked models, a growable instance buffer and the pipeline that draws them.
+pub struct EntityModels {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    ranges: Vec<Range<u32>>,
+    instance_buffer: Buffer,
+    batches: Vec<(ModelKind, Range<u32>)>,
+    pub animator: Animator,
+}
+
+fn instance_buffer(device: &Device, capacity: u64) -> Buffer {
+    device.create_buffer(&BufferDescriptor { label: Some("Entity Instances"), size: capacity * std::mem::size_of::<EntityInstance>() as u64, usage: BufferUsages::VERTEX | BufferUsages::COPY_DST, mapped_at_creation: false })
+}
+
+impl EntityModels {
+    /// `layout` is the terrain pipeline layout: entities are lit, fogged and shadowed the same way.
+    pub fn new(device: &Device, shader: &ShaderModule, layout: &PipelineLayout, format: TextureFormat) -> Self {
+        let (vertices, indices, ranges) = bake_models();
+        let vertex_buffer = device.create_buffer_init(&util::BufferInitDescriptor { label: Some("Model VB"), contents: bytemuck::cast_slice(&vertices), usage: BufferUsages::VERTEX });
+        let index_buffer = device.create_buffer_init(&util::BufferInitDescriptor { label: Some("Model IB"), contents: bytemuck::cast_slice(&indices), usage: BufferUsages::INDEX });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Entity Pipeline"),
+            layout: Some(layout),
+            vertex: VertexState { module: shader, entry_point: "vs_entity", buffers: &[ModelVertex::desc(), EntityInstance::desc()] },
+            fragment: Some(FragmentState { module: shader, entry_point: "fs_cutout", targets: &[Some(ColorTargetState { format, blend: None, write_mask: ColorWrites::ALL })] }),
+            primitive: PrimitiveState { cull_mode: Some(Face::Back), ..Default::default() },
+            depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled: true, depth_compare: CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState::default() }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+        });
+        Self { pipeline, vertex_buffer, index_buffer, ranges, instance_buffer: instance_buffer(device, INITIAL_INSTANCES), batches: Vec::new(), animator: Animator::default() }
+    }
+
+    /// Poses `models` and uploads them grouped by model, ready for `draw`.
+    pub fn prepare(&mut self, device: &Device, queue: &Queue, models: &[ModelInstance], time: f32) {
+        let mut order: Vec<&ModelInstance> = models.iter().collect();
+        order.sort_by_key(|m| m.kind as u32);
+        let instances: Vec<EntityInstance> = order.iter().map(|m| self.animator.pose(m, time)).collect();
+        self.animator.prune(time);
+
+        self.batches.clear();
+        for (i, m) in order.iter().enumerate() {
+            match self.batches.last_mut() {
+                Some((kind, range)) if *kind == m.kind => range.end = i as u32 + 1,
+                _ => self.batches.push((m.kind, i as u32..i as u32 + 1)),
+            }
+        }
+        if instances.is_empty() { return; }
+        let needed = (instances.len() * std::mem::size_of::<EntityInstance>()) as u64;
+        if needed > self.instance_buffer.size() {
+            self.instance_buffer = instance_buffer(device, (instances.len() as u64).next_power_of_two());
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+    }
+
+    /// One instanced draw per model kind. Expects the terrain bind groups to be set already.
+    pub fn draw<'a>(&'a self, pass: &mut RenderPass<'a>) {
+        if self.batches.is_empty() { return; }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint32);
+        for (kind, instances) in &self.batches {
+            pass.draw_indexed(self.ranges[*kind as usize].clone(), 0, instances.clone());
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Scheduled-tick fluid simulation
+//!
+//! Water and lava exist as a source block (level 8) and seven flowing levels. Any block change next
+//! to a fluid schedules that fluid on the world tick: water reacts after 5 ticks, lava after 30.
+//! A ticked cell first re-derives its level from whatever feeds it, so flows recede one level per
+//! update once their source is gone, and then spreads: straight down when it can, otherwise
+//! sideways toward the nearest drop, losing one level per block (two for lava).
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::tick::BlockBehaviour;
+use crate::world::{BlockPos, BlockType, SimpleRng, World};
+
+const HORIZONTAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const FALLING_LEVEL: u8 = 7; // Fluid poured from above stands almost full
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FluidKind { Water, Lava }
+
+impl FluidKind {
+    pub fn of(block: BlockType) -> Option<Self> {
+        if block.is_water() { Some(FluidKind::Water) } else if block.is_lava() { Some(FluidKind::Lava) } else { None }
+    }
+
+    /// Ticks between a change and the fluid reacting to it.
+    pub fn tick_delay(self) -> u64 { match self { FluidKind::Water => 5, FluidKind::Lava => 30 } }
+
+    /// Levels lost per block of horizontal spread.
+    fn level_drop(self) -> u8 { match self { FluidKind::Water => 1, FluidKind::Lava => 2 } }
+
+    /// How far sideways a flow looks for a drop to head toward.
+    fn search_radius(self) -> i32 { match self { FluidKind::Water => 4, FluidKind::Lava => 2 } }
+
+    /// Block for a given level: 8 is the source, 0 is nothing left.
+    pub fn block(self, level: u8) -> BlockType {
+        match (self, level) {
+            (_, 0) => BlockType::Air,
+            (FluidKind::Water, 1) => BlockType::WaterFlow1, (FluidKind::Water, 2) => BlockType::WaterFlow2,
+            (FluidKind::Water, 3) => BlockType::WaterFlow3, (FluidKind::Water, 4) => BlockType::WaterFlow4,
+            (FluidKind::Water, 5) => BlockType::WaterFlow5, (FluidKind::Water, 6) => BlockType::WaterFlow6,
+            (FluidKind::Water, 7) => BlockType::WaterFlow7, (FluidKind::Water, _) => BlockType::Water,
+            (FluidKind::Lava, 1) => BlockType::LavaFlow1, (FluidKind::Lava, 2) => BlockType::LavaFlow2,
+            (FluidKind::Lava, 3) => BlockType::LavaFlow3, (FluidKind::Lava, 4) => BlockType::LavaFlow4,
+            (FluidKind::Lava, 5) => BlockType::LavaFlow5, (FluidKind::Lava, 6) => BlockType::LavaFlow6,
+            (FluidKind::Lava, 7) => BlockType::LavaFlow7, (FluidKind::Lava, _) => BlockType::Lava,
+        }
+    }
+}
+
+fn offset(pos: BlockPos, dx: i32, dy: i32, dz: i32) -> BlockPos { BlockPos { x: pos.x + dx, y: pos.y + dy, z: pos.z + dz } }
+
+/// Whether a fluid of `kind` arriving at `level` may replace `block`.
+fn can_flow_into(block: BlockType, kind: FluidKind, level: u8) -> bool {
+    match FluidKind::of(block) {
+        Some(k) => k == kind && block.get_fluid_level() < level && block.get_fluid_level() < 8,
+        None => block == BlockType::Air || block == BlockType::Torch || block == BlockType::Fire || block.is_cross_model(),
+    }
+}
+
+/// Scheduled-tick hook for every water and lava block.
+pub struct FluidBehaviour;
+impl BlockBehaviour for FluidBehaviour {
+    fn scheduled_tick(&self, world: &mut World, pos: BlockPos, _rng: &mut SimpleRng) { world.update_fluid_at(pos); }
+    fn neighbour_changed(&self, world: &mut World, pos: BlockPos) {
+        if let Some(kind) = FluidKind::of(world.get_block(pos)) { world.schedule_tick(pos, kind.tick_delay()); }
+    }
+}
+
+impl World {
+    fn update_fluid_at(&mut self, pos: BlockPos) {
+        let block = self.get_block(pos);
+        let Some(kind) = FluidKind::of(block) else { return; };
+        let mut level = block.get_fluid_level();
+
+        // 1. Flowing cells follow whatever feeds them; with nothing left they dry up
+        if level < 8 {
+            let expected = self.expected_fluid_level(pos, kind);
+            if expected != level {
+                self.set_block_ticked(pos, kind.block(expected));
+                if expected == 0 { return; }
+                level = expected;
+            }
+        }
+
+        // 2. Lava meeting water
+        let affected = self.update_block_physics(pos);
+        self.dirty_chunks.extend(affected);
+        if self.get_block(pos) != kind.block(level) { return; }
+
+        // 3. Spread: downward first, sideways only from cells resting on something
+        let below = offset(pos, 0, -1, 0);
+        let below_block = self.get_block(below);
+        if can_flow_into(below_block, kind, FALLING_LEVEL) {
+            self.set_block_ticked(below, kind.block(FALLING_LEVEL));
+            return;
+        }
+        if FluidKind::of(below_block).is_some() || level <= kind.level_drop() { return; }
+        let next = level - kind.level_drop();
+        for (dx, dz) in self.flow_directions(pos, kind) {
+            let n = offset(pos, dx, 0, dz);
+            if can_flow_into(self.get_block(n), kind, next) {
+                self.set_block_ticked(n, kind.block(next));
+            }
+        }
+    }
+
+    /// Level a flowing cell should have given its neighbours right now.
+    fn expected_fluid_level(&self, pos: BlockPos, kind: FluidKind) -> u8 {
+        if FluidKind::of(self.get_block(offset(pos, 0, 1, 0))) == Some(kind) { return FALLING_LEVEL; }
+        let mut best = 0;
+        let mut sources = 0;
+        for (dx, dz) in HORIZONTAL {
+            let n = self.get_block(offset(pos, dx, 0, dz));
+            if FluidKind::of(n) != Some(kind) { continue; }
+            if n.get_fluid_level() == 8 { sources += 1; }
+            best = best.max(n.get_fluid_level());
+        }
+        // Two water sources with something to rest on make a new source
+        if kind == FluidKind::Water && sources >= 2 {
+            let below = self.get_block(offset(pos, 0, -1, 0));
+            if below.is_solid() || below == BlockType::Water { return 8; }
+        }
+        best.saturating_sub(kind.level_drop())
+    }
+
+    /// Horizontal directions toward the closest drop within the search radius, or all open
+    /// directions when there is no drop nearby (so pools spread evenly on flat ground).
+    fn flow_directions(&self, pos: BlockPos, kind: FluidKind) -> Vec<(i32, i32)> {
+        let open = |b: BlockType| can_flow_into(b, kind, 1) || (FluidKind::of(b) == Some(kind) && b.get_fluid_level() < 8);
+        let mut best = i32::MAX;
+        let mut dirs = Vec::new();
+        let mut all_open = Vec::new();
+        for (dx, dz) in HORIZONTAL {
+            let start = offset(pos, dx, 0, dz);
+            if !open(self.get_block(start)) { continue; }
+            all_open.push((dx, dz));
+
+            let mut seen = HashSet::from([pos, start]);
+            let mut queue = VecDeque::from([(start, 1)]);
+            let mut found = None;
+            while let Some((p, dist)) = queue.pop_front() {
+                if can_flow_into(self.get_block(offset(p, 0, -1, 0)), kind, FALLING_LEVEL) { found = Some(dist); break; }
+                if dist >= kind.search_radius() { continue; }
+                for (ex, ez) in HORIZONTAL {
+                    let n = offset(p, ex, 0, ez);
+                    if (n.x - pos.x).abs() > kind.search_radius() || (n.z - pos.z).abs() > kind.search_radius() { continue; }
+                    if open(self.get_block(n)) && seen.insert(n) { queue.push_back((n, dist + 1)); }
+                }
+            }
+            match found {
+                Some(d) if d < best => { best = d; dirs = vec![(dx, dz)]; }
+                Some(d) if d == best => dirs.push((dx, dz)),
+                _ => {}
+            }
+        }
+        if dirs.is_empty() { all_open } else { dirs }
+    }
+}
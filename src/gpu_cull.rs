@@ -0,0 +1,131 @@
+//! GPU-driven frustum culling
+//!
+//! Every chunk mesh lives in the shared `MeshArena`, so a whole frame of terrain is one list of
+//! `ChunkCullData` rows. `compute_cull` (cull.wgsl) turns that list into `DrawIndexedIndirect`
+//! commands, one per row and in the same order, with an instance count of 0 for chunks outside the
+//! frustum. The renderer lists every opaque row before every cutout row, so the terrain pass draws
+//! each layer with one multi-draw-indirect call over its slice of the commands. Adapters without
+//! multi-draw-indirect keep culling on the CPU with `sphere_visible`.
+
+use wgpu::*;
+
+/// Bounding sphere that contains a whole 16³ section around its centre.
+pub const CHUNK_RADIUS: f32 = 14.0;
+const INITIAL_CAPACITY: usize = 10000;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChunkCullData {
+    pub pos: [f32; 4], // x, y, z, radius
+    pub index_count: u32,
+    pub base_vertex: i32,
+    pub base_index: u32,
+    pub _pad: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DrawIndexedIndirect {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+/// Frustum planes of a view-projection matrix (left, right, bottom, top, near, far), normalised so
+/// `sphere_visible` measures real distances.
+pub fn frustum_planes(m: glam::Mat4) -> [[f32; 4]; 6] {
+    let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+    [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|p| (p / p.truncate().length()).to_array())
+}
+
+/// CPU version of the test in `compute_cull`, against planes from `Player::get_frustum_planes`.
+pub fn sphere_visible(planes: &[[f32; 4]; 6], center: glam::Vec3, radius: f32) -> bool {
+    planes.iter().all(|p| glam::Vec3::from_slice(&p[0..3]).dot(center) + p[3] >= -radius)
+}
+
+/// Whether the adapter can take the GPU path: compute, indirect draws and multi-draw-indirect.
+pub fn supported(adapter: &Adapter) -> bool {
+    let flags = adapter.get_downlevel_capabilities().flags;
+    adapter.features().contains(Features::MULTI_DRAW_INDIRECT)
+        && flags.contains(DownlevelFlags::COMPUTE_SHADERS | DownlevelFlags::INDIRECT_EXECUTION)
+}
+
+pub struct GpuCuller {
+    compute_pipeline: ComputePipeline,
+    camera_bind_group: BindGroup,
+    cull_bg_layout: BindGroupLayout,
+    chunk_data_buffer: Buffer,
+    pub indirect_draw_buffer: Buffer,
+    indirect_count_buffer: Buffer,
+    cull_bind_group: BindGroup,
+    capacity: usize,
+    pub chunk_count: u32,
+}
+
+fn cull_buffers(device: &Device, capacity: usize) -> (Buffer, Buffer) {
+    let chunk_data_buffer = device.create_buffer(&BufferDescriptor { label: Some("Chunk Data Buffer"), size: (capacity * std::mem::size_of::<ChunkCullData>()) as u64, usage: BufferUsages::STORAGE | BufferUsages::COPY_DST, mapped_at_creation: false });
+    let indirect_draw_buffer = device.create_buffer(&BufferDescriptor { label: Some("Indirect Draw Buffer"), size: (capacity * std::mem::size_of::<DrawIndexedIndirect>()) as u64, usage: BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_SRC, mapped_at_creation: false });
+    (chunk_data_buffer, indirect_draw_buffer)
+}
+
+fn cull_bind_group(device: &Device, layout: &BindGroupLayout, chunks: &Buffer, draws: &Buffer, count: &Buffer) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some("Cull BG"),
+        layout,
+        entries: &[
+            BindGroupEntry { binding: 0, resource: chunks.as_entire_binding() },
+            BindGroupEntry { binding: 1, resource: draws.as_entire_binding() },
+            BindGroupEntry { binding: 2, resource: count.as_entire_binding() },
+        ],
+    })
+}
+
+impl GpuCuller {
+    /// Builds the cull pipeline reading the view-projection matrix from `camera_buffer`.
+    pub fn new(device: &Device, camera_buffer: &Buffer) -> Self {
+        let storage = |binding, read_only| BindGroupLayoutEntry { binding, visibility: ShaderStages::COMPUTE, ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only }, has_dynamic_offset: false, min_binding_size: None }, count: None };
+        let camera_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Cull Camera Layout"),
+            entries: &[BindGroupLayoutEntry { binding: 0, visibility: ShaderStages::COMPUTE, ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None }, count: None }],
+        });
+        let cull_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Cull Layout"),
+            entries: &[storage(0, true), storage(1, false), storage(2, true)],
+        });
+        let camera_bind_group = device.create_bind_group(&BindGroupDescriptor { label: Some("Cull Camera BG"), layout: &camera_bg_layout, entries: &[BindGroupEntry { binding: 0, resource: camera_buffer.as_entire_binding() }] });
+
+        let (chunk_data_buffer, indirect_draw_buffer) = cull_buffers(device, INITIAL_CAPACITY);
+        let indirect_count_buffer = device.create_buffer(&BufferDescriptor { label: Some("Indirect Count Buffer"), size: 16, usage: BufferUsages::STORAGE | BufferUsages::COPY_DST, mapped_at_creation: false });
+        let cull_bind_group = cull_bind_group(device, &cull_bg_layout, &chunk_data_buffer, &indirect_draw_buffer, &indirect_count_buffer);
+
+        let compute_shader = device.create_shader_module(include_wgsl!("cull.wgsl"));
+        let compute_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("Compute Layout"), bind_group_layouts: &[&camera_bg_layout, &cull_bg_layout], push_constant_ranges: &[] });
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor { label: Some("Cull Pipeline"), layout: Some(&compute_pipeline_layout), module: &compute_shader, entry_point: "compute_cull" });
+
+        Self { compute_pipeline, camera_bind_group, cull_bg_layout, chunk_data_buffer, indirect_draw_buffer, indirect_count_buffer, cull_bind_group, capacity: INITIAL_CAPACITY, chunk_count: 0 }
+    }
+
+    /// Replaces the chunk list, growing the buffers if it no longer fits.
+    pub fn upload(&mut self, device: &Device, queue: &Queue, chunks: &[ChunkCullData]) {
+        if chunks.len() > self.capacity {
+            self.capacity = chunks.len().next_power_of_two();
+            (self.chunk_data_buffer, self.indirect_draw_buffer) = cull_buffers(device, self.capacity);
+            self.cull_bind_group = cull_bind_group(device, &self.cull_bg_layout, &self.chunk_data_buffer, &self.indirect_draw_buffer, &self.indirect_count_buffer);
+        }
+        self.chunk_count = chunks.len() as u32;
+        if !chunks.is_empty() { queue.write_buffer(&self.chunk_data_buffer, 0, bytemuck::cast_slice(chunks)); }
+        queue.write_buffer(&self.indirect_count_buffer, 0, bytemuck::cast_slice(&[self.chunk_count, 0, 0, 0]));
+    }
+
+    /// Records the cull pass; its commands are ready for any draw recorded after it.
+    pub fn dispatch(&self, encoder: &mut CommandEncoder) {
+        if self.chunk_count == 0 { return; }
+        let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor { label: Some("Cull Pass"), timestamp_writes: None });
+        pass.set_pipeline(&self.compute_pipeline);
+        pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        pass.set_bind_group(1, &self.cull_bind_group, &[]);
+        pass.dispatch_workgroups(self.chunk_count.div_ceil(64), 1, 1);
+    }
+}
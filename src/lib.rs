@@ -9,6 +9,7 @@ pub enum GameState { Loading, Menu, Multiplayer, Playing }
 
 pub struct Rect { pub x: f32, pub y: f32, pub w: f32, pub h: f32 }
 impl Rect { 
+    #[cfg_attr(not(feature = "client"), allow(dead_code))] // Only the client's menus hit-test
     fn contains(&self, nx: f32, ny: f32) -> bool { 
         nx >= self.x - self.w/2.0 && nx <= self.x + self.w/2.0 && ny >= self.y - self.h/2.0 && ny <= self.y + self.h/2.0 
     } 
@@ -59,7 +60,9 @@ impl MainMenu {
 
 // Audio system
 pub struct AudioSystem {
+    #[cfg(feature = "client")]
     _stream: Option<rodio::OutputStream>,
+    #[cfg(feature = "client")]
     #[allow(dead_code)]
     stream_handle: Option<rodio::OutputStreamHandle>,
     master_volume: f32,
@@ -68,13 +71,17 @@ pub struct AudioSystem {
 }
 
 impl AudioSystem {
+    /// Silent without the `client` feature (the dedicated server has no sound card to open).
     pub fn new() -> Self {
+        #[cfg(feature = "client")]
         let (stream, handle) = match rodio::OutputStream::try_default() {
             Ok((s, h)) => (Some(s), Some(h)),
             Err(_) => (None, None),
         };
         Self { 
+            #[cfg(feature = "client")]
             _stream: stream, 
+            #[cfg(feature = "client")]
             stream_handle: handle,
             master_volume: 1.0,
             music_volume: 1.0,
@@ -82,6 +89,19 @@ impl AudioSystem {
         }
     }
 
+    /// Never opens an output device, e.g. for the server's copies of players.
+    pub fn silent() -> Self {
+        Self {
+            #[cfg(feature = "client")]
+            _stream: None,
+            #[cfg(feature = "client")]
+            stream_handle: None,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+        }
+    }
+
     pub fn set_master_volume(&mut self, volume: f32) {
         self.master_volume = volume.clamp(0.0, 1.0);
     }
@@ -117,26 +137,54 @@ impl Hotbar {
     } 
 }
 
+pub mod biome;
+pub mod decoration;
+#[cfg(feature = "client")]
+pub mod entity_models;
+pub mod fluids;
+pub mod lighting;
 pub mod logger;
+pub mod netsim;
 pub mod network;
 pub mod ngrok_utils;
 pub mod noise_gen;
 pub mod player;
+pub mod region;
+pub mod server;
+#[cfg(feature = "client")]
 pub mod renderer;
 pub mod resource_manager;
 pub mod texture;
+pub mod tick;
+#[cfg(feature = "client")]
 pub mod ui_system;
 pub mod weather_system;
 pub mod combat_system;
+pub mod chunk_gen;
+pub mod chunk_snapshot;
+pub mod clouds;
 pub mod config_system;
+#[cfg(feature = "client")]
+pub mod gpu_cull;
+#[cfg(feature = "client")]
+pub mod mesh_arena;
+#[cfg(feature = "client")]
+pub mod post;
+pub mod prediction;
+pub mod protocol;
+#[cfg(feature = "client")]
+pub mod shadows;
 pub mod world;
+pub mod worldgen_check;
 
 // Re-export commonly used types for convenience
 pub use crate::player::Player;
 pub use crate::world::{World, BlockPos, BlockType};
+#[cfg(feature = "client")]
 pub use crate::renderer::Renderer;
 pub use crate::network::NetworkManager;
 pub use crate::resource_manager::{ResourceTracker, track_chunk_usage, cleanup_if_needed};
+#[cfg(feature = "client")]
 pub use crate::ui_system::{AdvancedHUD, DiabolicalMenuSystem};
 pub use crate::weather_system::{WeatherSystem, WeatherType};
 pub use crate::combat_system::{CombatSystem, Mob, MobType, DamageType};
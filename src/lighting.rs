@@ -0,0 +1,277 @@
+//! Flood-fill lighting engine
+//!
+//! Two 4-bit channels are stored per block: sky light, which starts at 15 above the world and falls
+//! straight down without loss until something absorbs it, and block light from emitters (torches,
+//! lava, fire, lit furnaces). Both spread to the six neighbours losing at least one level per step.
+//! Whole columns are lit once decorated: the generation workers light the column on its own
+//! (`light_sections`) and the main thread only exchanges light with the lit columns around it
+//! (`merge_column_light`). Single edits use the usual remove-then-refill BFS so placing and
+//! breaking blocks only touches the area that changed.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::{BlockPos, Chunk, World, CHUNK_SIZE_X, CHUNK_SIZE_Y, CHUNK_SIZE_Z, WORLD_HEIGHT};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightChannel { Sky, Block }
+
+const DIRS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+const DOWN: (i32, i32, i32) = (0, -1, 0);
+const SECTIONS: i32 = WORLD_HEIGHT / CHUNK_SIZE_Y as i32;
+
+type ChunkKey = (i32, i32, i32);
+
+fn split(pos: BlockPos) -> (ChunkKey, usize, usize, usize) {
+    ((pos.x.div_euclid(16), pos.y.div_euclid(16), pos.z.div_euclid(16)),
+     pos.x.rem_euclid(16) as usize, pos.y.rem_euclid(16) as usize, pos.z.rem_euclid(16) as usize)
+}
+
+fn offset(pos: BlockPos, d: (i32, i32, i32)) -> BlockPos { BlockPos { x: pos.x + d.0, y: pos.y + d.1, z: pos.z + d.2 } }
+
+/// Light a neighbour receives from a cell at `level` through a block of `opacity`.
+fn spread(ch: LightChannel, dir: (i32, i32, i32), level: u8, opacity: u8) -> u8 {
+    if opacity >= 15 { return 0; }
+    if ch == LightChannel::Sky && dir == DOWN && level == 15 && opacity == 0 { return 15; } // Direct sunlight
+    level.saturating_sub(opacity.max(1))
+}
+
+/// Lights column (cx, cz) from its sections alone (bottom to top), as if none of its neighbours
+/// were loaded. Needs nothing but the sections, so it runs on the generation workers; the main
+/// thread then finishes the job with `World::merge_column_light`.
+pub fn light_sections(cx: i32, cz: i32, sections: Vec<Chunk>) -> Vec<Chunk> {
+    let mut scratch = World::new(0);
+    for (cy, chunk) in sections.into_iter().enumerate() { scratch.chunks.insert((cx, cy as i32, cz), chunk); }
+    scratch.light_column(cx, cz);
+    (0..SECTIONS).filter_map(|cy| scratch.chunks.remove(&(cx, cy, cz))).collect()
+}
+
+impl World {
+    /// Light value of one channel, or None if the position is outside a lit column.
+    pub fn light_channel_at(&self, pos: BlockPos, ch: LightChannel) -> Option<u8> {
+        if pos.y < 0 || pos.y >= WORLD_HEIGHT { return None; }
+        let (key, lx, ly, lz) = split(pos);
+        if !self.lit_columns.contains(&(key.0, key.2)) { return None; }
+        let chunk = self.chunks.get(&key)?;
+        Some(match ch { LightChannel::Sky => chunk.get_sky_light(lx, ly, lz), LightChannel::Block => chunk.get_block_light(lx, ly, lz) })
+    }
+
+    fn set_light_channel(&mut self, pos: BlockPos, ch: LightChannel, val: u8, touched: &mut HashSet<ChunkKey>) {
+        let (key, lx, ly, lz) = split(pos);
+        let Some(chunk) = self.chunks.get_mut(&key) else { return; };
+        match ch { LightChannel::Sky => chunk.set_sky_light(lx, ly, lz, val), LightChannel::Block => chunk.set_block_light(lx, ly, lz, val) }
+        touched.insert(key);
+        // Faces of the neighbouring section sample this cell too
+        if lx == 0 { touched.insert((key.0 - 1, key.1, key.2)); } else if lx == CHUNK_SIZE_X - 1 { touched.insert((key.0 + 1, key.1, key.2)); }
+        if ly == 0 { touched.insert((key.0, key.1 - 1, key.2)); } else if ly == CHUNK_SIZE_Y - 1 { touched.insert((key.0, key.1 + 1, key.2)); }
+        if lz == 0 { touched.insert((key.0, key.1, key.2 - 1)); } else if lz == CHUNK_SIZE_Z - 1 { touched.insert((key.0, key.1, key.2 + 1)); }
+    }
+
+    /// Breadth-first spread from every queued cell until nothing brightens any more.
+    fn propagate_light(&mut self, mut queue: VecDeque<(BlockPos, LightChannel)>, touched: &mut HashSet<ChunkKey>) {
+        while let Some((pos, ch)) = queue.pop_front() {
+            let Some(level) = self.light_channel_at(pos, ch) else { continue; };
+            if level <= 1 { continue; }
+            for dir in DIRS {
+                let n = offset(pos, dir);
+                let Some(cur) = self.light_channel_at(n, ch) else { continue; };
+                let new = spread(ch, dir, level, self.get_block(n).light_opacity());
+                if new > cur {
+                    self.set_light_channel(n, ch, new, touched);
+                    queue.push_back((n, ch));
+                }
+            }
+        }
+    }
+
+    /// Darkens everything that was lit through the seeds; returns the cells bordering the dark
+    /// area that still hold independent light, which must be re-propagated afterwards.
+    fn unpropagate_light(&mut self, mut queue: VecDeque<(BlockPos, u8, LightChannel)>, touched: &mut HashSet<ChunkKey>) -> VecDeque<(BlockPos, LightChannel)> {
+        let mut refill = VecDeque::new();
+        while let Some((pos, level, ch)) = queue.pop_front() {
+            for dir in DIRS {
+                let n = offset(pos, dir);
+                let Some(cur) = self.light_channel_at(n, ch) else { continue; };
+                if cur == 0 { continue; }
+                let lit_by_us = cur < level || (ch == LightChannel::Sky && dir == DOWN && level == 15 && cur == 15);
+                if lit_by_us {
+                    self.set_light_channel(n, ch, 0, touched);
+                    queue.push_back((n, cur, ch));
+                    let emission = self.get_block(n).light_emission();
+                    if ch == LightChannel::Block && emission > 0 {
+                        self.set_light_channel(n, ch, emission, touched);
+                        refill.push_back((n, ch));
+                    }
+                } else {
+                    refill.push_back((n, ch));
+                }
+            }
+        }
+        refill
+    }
+
+    fn mark_light_dirty(&mut self, touched: HashSet<ChunkKey>) {
+        for key in touched {
+            if let Some(chunk) = self.chunks.get_mut(&key) {
+                chunk.mesh_dirty = true;
+                self.dirty_chunks.insert(key);
+            }
+        }
+    }
+
+    /// Computes both channels for a whole column once all its sections are loaded, pulling light in
+    /// from lit neighbour columns and pushing this column's light out into them.
+    /// Returns false (and does nothing) while sections are still missing.
+    pub fn light_column(&mut self, cx: i32, cz: i32) -> bool {
+        if !(0..SECTIONS).all(|cy| self.chunks.contains_key(&(cx, cy, cz))) { return false; }
+        for cy in 0..SECTIONS {
+            if let Some(chunk) = self.chunks.get_mut(&(cx, cy, cz)) { chunk.clear_light(); }
+        }
+        self.lit_columns.insert((cx, cz));
+
+        let mut touched = HashSet::new();
+        self.light_within_column(cx, cz, &mut touched);
+        self.exchange_border_light(cx, cz, &mut touched);
+        for cy in 0..SECTIONS {
+            if let Some(chunk) = self.chunks.get_mut(&(cx, cy, cz)) { chunk.compact_light(); }
+        }
+        self.mark_light_dirty(touched);
+        true
+    }
+
+    /// Takes the light `light_sections` computed for column (cx, cz) and lets it flow into and in
+    /// from the lit columns around it. Does nothing if the column isn't fully loaded any more.
+    pub fn merge_column_light(&mut self, cx: i32, cz: i32, lit: Vec<Chunk>) -> bool {
+        if lit.len() != SECTIONS as usize || !(0..SECTIONS).all(|cy| self.chunks.contains_key(&(cx, cy, cz))) { return false; }
+        let mut touched = HashSet::new();
+        for (cy, section) in (0..SECTIONS).zip(lit) {
+            let Some(chunk) = self.chunks.get_mut(&(cx, cy, cz)) else { continue; };
+            chunk.light = section.light;
+            chunk.light_fill = section.light_fill;
+            touched.insert((cx, cy, cz));
+        }
+        self.lit_columns.insert((cx, cz));
+        self.exchange_border_light(cx, cz, &mut touched);
+        self.mark_light_dirty(touched);
+        true
+    }
+
+    /// Sunlight and emitters of column (cx, cz), spread as far as they go inside the column and
+    /// into lit neighbours. The column must already be marked lit with its light cleared.
+    fn light_within_column(&mut self, cx: i32, cz: i32, touched: &mut HashSet<ChunkKey>) {
+        let mut queue = VecDeque::new();
+        let (bx, bz) = (cx * 16, cz * 16);
+
+        // 1. Direct sunlight straight down each x/z until it is absorbed. `open_to` is the lowest y
+        // still at full strength; only cells with a covered neighbour need to spread sideways.
+        let mut open_to = [[WORLD_HEIGHT; CHUNK_SIZE_Z]; CHUNK_SIZE_X];
+        let mut dimmed = Vec::new();
+        for (lx, row) in open_to.iter_mut().enumerate() {
+            for (lz, open) in row.iter_mut().enumerate() {
+                let mut level = 15u8;
+                for y in (0..WORLD_HEIGHT).rev() {
+                    let pos = BlockPos { x: bx + lx as i32, y, z: bz + lz as i32 };
+                    let opacity = self.get_block(pos).light_opacity();
+                    if opacity >= 15 { break; }
+                    level = level.saturating_sub(opacity);
+                    if level == 0 { break; }
+                    self.set_light_channel(pos, LightChannel::Sky, level, touched);
+                    if level == 15 { *open = y; } else { dimmed.push(pos); }
+                }
+            }
+        }
+        for lx in 0..CHUNK_SIZE_X {
+            for lz in 0..CHUNK_SIZE_Z {
+                let border = lx == 0 || lz == 0 || lx == CHUNK_SIZE_X - 1 || lz == CHUNK_SIZE_Z - 1;
+                let deepest_neighbour = if border { WORLD_HEIGHT } else {
+                    open_to[lx - 1][lz].max(open_to[lx + 1][lz]).max(open_to[lx][lz - 1]).max(open_to[lx][lz + 1])
+                };
+                for y in open_to[lx][lz]..deepest_neighbour.max(open_to[lx][lz] + 1).min(WORLD_HEIGHT) {
+                    queue.push_back((BlockPos { x: bx + lx as i32, y, z: bz + lz as i32 }, LightChannel::Sky));
+                }
+            }
+        }
+        queue.extend(dimmed.into_iter().map(|pos| (pos, LightChannel::Sky)));
+
+        // 2. Emitters
+        for cy in 0..SECTIONS {
+            let Some(chunk) = self.chunks.get(&(cx, cy, cz)) else { continue; };
+            if chunk.is_empty { continue; }
+            let mut emitters = Vec::new();
+            for lx in 0..CHUNK_SIZE_X { for ly in 0..CHUNK_SIZE_Y { for lz in 0..CHUNK_SIZE_Z {
+                let e = chunk.get_block(lx, ly, lz).light_emission();
+                if e > 0 { emitters.push((BlockPos { x: bx + lx as i32, y: cy * 16 + ly as i32, z: bz + lz as i32 }, e)); }
+            }}}
+            for (pos, e) in emitters {
+                self.set_light_channel(pos, LightChannel::Block, e, touched);
+                queue.push_back((pos, LightChannel::Block));
+            }
+        }
+        self.propagate_light(queue, touched);
+    }
+
+    /// Border cells on both sides of every edge column (cx, cz) shares with a lit column spread
+    /// across it, so light flows in from the neighbours and out of this column.
+    fn exchange_border_light(&mut self, cx: i32, cz: i32, touched: &mut HashSet<ChunkKey>) {
+        let mut queue = VecDeque::new();
+        let (bx, bz) = (cx * 16, cz * 16);
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            if !self.lit_columns.contains(&(cx + dx, cz + dz)) { continue; }
+            for i in 0..16 {
+                // (ours, theirs)
+                let sides = match (dx, dz) {
+                    (1, _) => [(bx + 15, bz + i), (bx + 16, bz + i)], (-1, _) => [(bx, bz + i), (bx - 1, bz + i)],
+                    (_, 1) => [(bx + i, bz + 15), (bx + i, bz + 16)], _ => [(bx + i, bz), (bx + i, bz - 1)],
+                };
+                for (x, z) in sides {
+                    for y in 0..WORLD_HEIGHT {
+                        let pos = BlockPos { x, y, z };
+                        for ch in [LightChannel::Sky, LightChannel::Block] {
+                            if self.light_channel_at(pos, ch).unwrap_or(0) > 1 { queue.push_back((pos, ch)); }
+                        }
+                    }
+                }
+            }
+        }
+        self.propagate_light(queue, touched);
+    }
+
+    /// Incremental update after the block at `pos` changed: removes the light that used to pass
+    /// through (or come from) it, then refills from its own emission and the surrounding cells.
+    pub fn relight_at(&mut self, pos: BlockPos) {
+        if pos.y < 0 || pos.y >= WORLD_HEIGHT { return; }
+        let (key, ..) = split(pos);
+        if !self.lit_columns.contains(&(key.0, key.2)) {
+            // The workers' copy no longer matches; it gets lit again when it comes back
+            if let Some(stale) = self.lighting.get_mut(&(key.0, key.2)) { *stale = true; }
+            return;
+        }
+        let mut touched = HashSet::new();
+
+        let mut removal = VecDeque::new();
+        for ch in [LightChannel::Sky, LightChannel::Block] {
+            let old = self.light_channel_at(pos, ch).unwrap_or(0);
+            if old > 0 {
+                self.set_light_channel(pos, ch, 0, &mut touched);
+                removal.push_back((pos, old, ch));
+            }
+        }
+        let mut refill = self.unpropagate_light(removal, &mut touched);
+
+        let block = self.get_block(pos);
+        if block.light_emission() > 0 {
+            self.set_light_channel(pos, LightChannel::Block, block.light_emission(), &mut touched);
+            refill.push_back((pos, LightChannel::Block));
+        }
+        if pos.y == WORLD_HEIGHT - 1 && block.light_opacity() < 15 {
+            self.set_light_channel(pos, LightChannel::Sky, 15 - block.light_opacity(), &mut touched);
+            refill.push_back((pos, LightChannel::Sky));
+        }
+        for dir in DIRS {
+            let n = offset(pos, dir);
+            refill.push_back((n, LightChannel::Sky));
+            refill.push_back((n, LightChannel::Block));
+        }
+        self.propagate_light(refill, &mut touched);
+        self.mark_light_dirty(touched);
+    }
+}
@@ -12,6 +12,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub struct AudioSystem {
     _stream: Option<OutputStream>,
     stream_handle: Option<rodio::OutputStreamHandle>,
+    master_volume: f32,
+    sfx_volume: f32,
 }
 
 impl AudioSystem {
@@ -20,15 +22,20 @@ impl AudioSystem {
             Ok((s, h)) => (Some(s), Some(h)),
             Err(_) => (None, None),
         };
-        Self { _stream: stream, stream_handle: handle }
+        Self { _stream: stream, stream_handle: handle, master_volume: 1.0, sfx_volume: 1.0 }
     }
 
+    pub fn set_master_volume(&mut self, volume: f32) { self.master_volume = volume.clamp(0.0, 1.0); }
+    pub fn set_music_volume(&mut self, _volume: f32) {} // No music yet
+    pub fn set_sfx_volume(&mut self, volume: f32) { self.sfx_volume = volume.clamp(0.0, 1.0); }
+
     pub fn play_step(&self, category: &str, variant: usize, in_cave: bool) {
         let handle = match &self.stream_handle {
             Some(h) => h,
             None => return,
         };
         let sink = Sink::try_new(handle).unwrap();
+        sink.set_volume(self.master_volume * self.sfx_volume);
         
         // DIABOLICAL VARIANT MODULATION: Each variant (0-4) slightly shifts frequency and duration
         let v_mod = 0.92 + (variant as f32 * 0.04); // Pitch range: 0.92 to 1.08
@@ -60,6 +67,7 @@ pub fn play(&self, sound_type: &str, in_cave: bool) {
             None => return,
         };
         let sink = Sink::try_new(handle).unwrap();
+        sink.set_volume(self.master_volume * self.sfx_volume);
         let mut dur = match sound_type {
             "click" | "pickup" => 0.05,
 "land" => 0.2,
@@ -150,8 +158,13 @@ fn gen_noise(dur: f32, freq_start: f32, freq_end: f32, reverb: bool) -> Vec<u8>
     }
 }
 
-mod renderer; mod world; mod texture; mod player; mod logger; mod noise_gen; mod network; mod ngrok_utils;
+mod renderer; mod world; mod texture; mod player; mod logger; mod noise_gen; mod network; mod ngrok_utils; mod region; mod chunk_gen; mod lighting; mod fluids; mod tick; mod decoration; mod biome; mod chunk_snapshot; mod gpu_cull; mod mesh_arena; mod shadows; mod entity_models; mod clouds; mod post; mod protocol; mod netsim; mod prediction;
+#[allow(dead_code)] // Only the cloud layer reads the weather so far; precipitation and lightning are library-only
+mod weather_system;
+#[allow(dead_code)] // Only the graphics and audio settings are applied here so far
+mod config_system;
 use renderer::Renderer; use world::{World, BlockType, BlockPos}; use player::Player; use network::{NetworkManager, Packet};
+use minecraft_clone::{resource_manager, worldgen_check};
 use glam::Vec3;
 use serde_json::json;
 use std::fs;
@@ -190,10 +203,28 @@ pub struct UIElement { pub pos: [f32; 2], pub size: [f32; 2], pub tex_idx: u32,
 pub struct Hotbar { pub slots: [Option<(world::BlockType, u32)>; 9], pub selected_slot: usize }
 impl Hotbar { fn new() -> Self { Self { slots: [None; 9], selected_slot: 0 } } }
 
+/// F2: writes the last rendered frame to screenshots/<unix millis>.png.
+fn save_screenshot(renderer: &Renderer) {
+    let path = format!("screenshots/{}.png", SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis());
+    let saved = fs::create_dir_all("screenshots").map_err(|e| e.to_string())
+        .and_then(|_| renderer.capture_frame().save(&path).map_err(|e| e.to_string()));
+    match saved {
+        Ok(()) => log::info!("Saved screenshot {}", path),
+        Err(e) => log::error!("Failed to save screenshot {}: {}", path, e),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // HEADLESS: print the worldgen golden hashes (optionally for other seeds) and exit before any window/logger exists
+    if args.len() > 1 && args[1] == "--worldgen-hashes" {
+        let seeds: Vec<u32> = args[2..].iter().filter_map(|s| s.parse().ok()).collect();
+        print!("{}", worldgen_check::format_hashes(if seeds.is_empty() { &worldgen_check::GOLDEN_SEEDS } else { &seeds }));
+        return;
+    }
+
     logger::init_logger();
     let master_seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u32;
-    let args: Vec<String> = std::env::args().collect();
 
     let event_loop = EventLoop::new().unwrap();
     let window = Arc::new(WindowBuilder::new().with_title("Minecraft Rust Clone").with_maximized(true).build(&event_loop).unwrap());
@@ -217,10 +248,13 @@ let mut renderer = pollster::block_on(Renderer::new(&window_arc));
         }
     }
 
-    let mut world = World::new(current_seed);
+    let mut world = World::new_persistent(current_seed);
     let mut player = Player::new();
     player.position = start_pos;
+    let mut weather = weather_system::WeatherSystem::new();
     let mut last_persist = Instant::now();
+    let mut last_stream_col = (i32::MAX, i32::MAX);
+    let view_cap = World::max_view_distance(resource_manager::get_resource_manager().limits().max_chunks);
     let mut accumulator = 0.0f32;
     const FIXED_TIME: f32 = 1.0 / 120.0; // 120Hz DIABOLICAL PHYSICS LOCK
     
@@ -236,10 +270,21 @@ let mut renderer = pollster::block_on(Renderer::new(&window_arc));
     let pause_menu = MainMenu::new_pause();
     let mut hosting_mgr = crate::ngrok_utils::HostingManager::new();
     let mut network_mgr: Option<NetworkManager> = None;
+    // Lag, jitter and loss to try the netcode with over loopback
+    let link_conditions = match args.iter().position(|a| a == "--netsim") {
+        Some(i) => args.get(i + 1).and_then(|spec| netsim::LinkConditions::parse(spec)).unwrap_or_else(|| {
+            log::warn!("--netsim wants <latency ms>,<jitter ms>,<loss %>; running without it");
+            Default::default()
+        }),
+        None => Default::default(),
+    };
+    let mut prediction = prediction::Prediction::new();
+    let mut remotes = prediction::RemoteEntities::new();
+    let net_clock = Instant::now();
     
     // If CLI args provided, jump straight to game
     if args.len() > 1 && args[1] == "--join-localhost" { 
-        network_mgr = Some(NetworkManager::join("127.0.0.1:7878".to_string()));
+        network_mgr = Some(NetworkManager::join("127.0.0.1:7878".to_string()).with_link_conditions(link_conditions));
         game_state = GameState::Playing;
     }
 
@@ -258,7 +303,11 @@ let mut renderer = pollster::block_on(Renderer::new(&window_arc));
     let mut modifiers = winit::keyboard::ModifiersState::default(); 
     let mut win_size = (window.inner_size().width, window.inner_size().height);
     let window_clone = window.clone();
-let audio = AudioSystem::new();
+let mut audio = AudioSystem::new();
+    // Settings from config.json (written with the defaults on first run)
+    let game_config = config_system::ConfigManager::new("config.json".to_string()).get_config().clone();
+    game_config.apply_graphics_settings(&mut renderer);
+    game_config.apply_audio_settings(&mut audio);
     let mut last_frame = Instant::now();
     let mut first_build_done = false;
 
@@ -270,7 +319,7 @@ let audio = AudioSystem::new();
 
     event_loop.run(move |event, elwt| {
         match event {
-            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => elwt.exit(),
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => { world.save_modified(); elwt.exit(); },
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
                 renderer.resize(size.width, size.height);
                 win_size = (size.width, size.height);
@@ -301,7 +350,9 @@ Event::WindowEvent { event: WindowEvent::MouseInput { button, state, .. }, .. }
                     if let Some(act) = action {
                         match act {
                             MenuAction::Singleplayer => {
-                                world = World::new(master_seed);
+                                // Resume the last played world so its saved regions are picked up
+                                world.save_modified();
+                                world = World::new_persistent(current_seed);
                                 renderer.rebuild_all_chunks(&world);
                                 game_state = GameState::Loading; // Transition to loading bar
                                 load_step = 0;
@@ -311,14 +362,15 @@ Event::WindowEvent { event: WindowEvent::MouseInput { button, state, .. }, .. }
                                 game_state = GameState::Multiplayer;
                             },
                             MenuAction::JoinAddr(addr) => {
-                                network_mgr = Some(NetworkManager::join(addr));
+                                network_mgr = Some(NetworkManager::join(addr).with_link_conditions(link_conditions));
                                 game_state = GameState::Playing;
                                 spawn_found = false;
                             },
                             MenuAction::Host => {
                                 hosting_mgr.init();
-                                network_mgr = Some(NetworkManager::host("25565".to_string(), master_seed));
-                                world = World::new(master_seed);
+                                network_mgr = Some(NetworkManager::host("25565".to_string(), master_seed).with_link_conditions(link_conditions));
+                                world.save_modified();
+                                world = World::new_persistent(master_seed);
                                 renderer.rebuild_all_chunks(&world);
                                 game_state = GameState::Playing;
                                 spawn_found = false;
@@ -326,7 +378,7 @@ Event::WindowEvent { event: WindowEvent::MouseInput { button, state, .. }, .. }
                             MenuAction::Stress => {
                                 let exe = std::env::current_exe().unwrap();
                                 for _ in 0..5 { std::process::Command::new(&exe).arg("--join-localhost").spawn().unwrap(); }
-                                network_mgr = Some(NetworkManager::host("7878".to_string(), master_seed));
+                                network_mgr = Some(NetworkManager::host("7878".to_string(), master_seed).with_link_conditions(link_conditions));
                                 world = World::new(master_seed);
                                 renderer.rebuild_all_chunks(&world);
                                 game_state = GameState::Playing;
@@ -337,7 +389,7 @@ Event::WindowEvent { event: WindowEvent::MouseInput { button, state, .. }, .. }
                                     game_state = GameState::Menu; 
                                     main_menu = MainMenu::new_main(); // DIABOLICAL RECOVERY: Rebuild the main menu buttons
                                 }
-                                else { elwt.exit(); }
+                                else { world.save_modified(); elwt.exit(); }
                             },
                             _ => {}
                         }
@@ -533,7 +585,7 @@ let _c = world.place_block(place, actual_blk);
                                             let is_submerged = world.get_block(head_p).is_water();
                                             audio.play("place", is_submerged);
                                             player.inventory.remove_one_from_hand();
-                                            if let Some(net) = &network_mgr { net.send_packet(Packet::BlockUpdate { pos: place, block: actual_blk }); }
+                                            if let Some(net) = &mut network_mgr { net.send_packet(Packet::BlockUpdate { pos: place, block: actual_blk }); }
                                             // ROOT FIX: Removed renderer.update_chunk loops to eliminate lag spikes. 
                                             // The Renderer will now detect 'mesh_dirty' and handle it off-thread.
                                         }
@@ -548,6 +600,14 @@ let _c = world.place_block(place, actual_blk);
             // --- KEYBOARD INPUT ---
             Event::WindowEvent { event: WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(key), state, .. }, .. }, .. } => {
                 let pressed = state == ElementState::Pressed;
+                if key == KeyCode::F2 && pressed { save_screenshot(&renderer); }
+                // Hot-reload the post-processing passes while tweaking them
+                if key == KeyCode::F7 && pressed {
+                    match fs::read_to_string("src/post.wgsl").map_err(|e| e.to_string()).and_then(|source| renderer.switch_shader(&source)) {
+                        Ok(()) => log::info!("Reloaded src/post.wgsl"),
+                        Err(e) => log::error!("Keeping the old post passes: {}", e),
+                    }
+                }
                 if game_state == GameState::Playing {
 if key == KeyCode::Escape && pressed {
                         if player.inventory_open { 
@@ -598,20 +658,22 @@ world.entities.push(ent);
                     } else if !is_paused && !player.inventory_open { 
                         player.handle_input(key, pressed);
                         
-                        if pressed && key == KeyCode::KeyF { player.is_flying = !player.is_flying; if player.is_flying { player.velocity = glam::Vec3::ZERO; } }
-                        if pressed && key == KeyCode::KeyT {
+                        // A dedicated server decides where players are and whether they fly
+                        let streamed = network_mgr.as_ref().is_some_and(|n| n.streamed);
+                        if pressed && key == KeyCode::KeyF && !streamed { player.is_flying = !player.is_flying; if player.is_flying { player.velocity = glam::Vec3::ZERO; } }
+                        if pressed && key == KeyCode::KeyT && !streamed {
                             let top_y = world.get_height_at(player.position.x.floor() as i32, player.position.z.floor() as i32);
                             player.position.y = top_y as f32 + 2.5;
                             player.velocity.y = 0.0;
                             log::info!("🚀 Teleported to surface: {}", top_y);
                         }
-                        if pressed && key == KeyCode::KeyN { 
+                        if pressed && key == KeyCode::KeyN && !streamed { 
                             player.is_noclip = !player.is_noclip; 
                             player.is_flying = player.is_noclip; 
                             player.admin_speed = if player.is_noclip { 5.0 } else { 1.0 }; 
                         }
-                        if pressed && key == KeyCode::Equal { player.admin_speed = (player.admin_speed + 1.0).min(10.0); }
-                        if pressed && key == KeyCode::Minus { player.admin_speed = (player.admin_speed - 1.0).max(1.0); }
+                        if pressed && key == KeyCode::Equal && !streamed { player.admin_speed = (player.admin_speed + 1.0).min(10.0); }
+                        if pressed && key == KeyCode::Minus && !streamed { player.admin_speed = (player.admin_speed - 1.0).max(1.0); }
                         if key == KeyCode::ControlLeft { player.is_sprinting = pressed; }
                         if pressed {
                             let slot = match key { KeyCode::Digit1=>Some(0), KeyCode::Digit2=>Some(1), KeyCode::Digit3=>Some(2), KeyCode::Digit4=>Some(3), KeyCode::Digit5=>Some(4), KeyCode::Digit6=>Some(5), KeyCode::Digit7=>Some(6), KeyCode::Digit8=>Some(7), KeyCode::Digit9=>Some(8), _=>None };
@@ -638,6 +700,7 @@ world.entities.push(ent);
 
                 if game_state == GameState::Loading {
                     // DIABOLICAL ASYNC BOOTLOADER: Max speed with zero main-thread stalls
+                    world.collect_generated();
                     match load_step {
                         0 => {
                             renderer.loading_message = "INITIALIZING CORE KERNEL...".to_string();
@@ -649,37 +712,28 @@ world.entities.push(ent);
                             load_step = 2;
                         }
                         2 => {
-                            // Stage 2: Parallel Column Generation
-                            // DIABOLICAL OPTIMIZATION: Generate only 4 columns per frame to keep OS responsive.
-                            let columns_per_frame = 4; 
-                            let current_col = world.chunks.len() / (crate::world::WORLD_HEIGHT as usize / 16);
-                            
-                            for i in 0..columns_per_frame {
-                                let step = (current_col + i) as i32;
-                                if step < 169 {
-                                    world.bootstrap_terrain_step(step);
-                                }
-                            }
-                            
-                            renderer.loading_progress = 0.1 + (current_col as f32 / 169.0) * 0.4;
-                            renderer.loading_message = format!("GENERATING TOPOLOGY... [STEP {}/169]", current_col);
-                            
-                            // Exit early logic: We only NEED a 3x3 radius to play immediately.
-                            // This brings the "Playable" time down from 24s to ~3s.
-                            if current_col >= 49 || current_col >= 168 { load_step = 3; }
+                            // Stage 2: Background Column Generation
+                            // The worker pool fills the 13x13 spawn area; we only wait for the first ring (radius 2).
+                            world.request_chunks_around(0, 0, 6);
+                            let sections = crate::world::WORLD_HEIGHT / 16;
+                            let first_ring_done = (-2..=2).all(|dx| (-2..=2).all(|dz| (0..sections).all(|dy| world.chunks.contains_key(&(dx, dy, dz)))));
+                            let pending = world.pending_generation();
+
+                            renderer.loading_progress = 0.1 + (world.chunks.len() as f32 / (world.chunks.len() + pending).max(1) as f32) * 0.4;
+                            renderer.loading_message = format!("GENERATING TOPOLOGY... [{} CHUNKS, {} QUEUED]", world.chunks.len(), pending);
+
+                            if first_ring_done { load_step = 3; }
                         }
                         3 => {
                             // Stage 3: Async Background Mesh Dispatch
                             renderer.loading_message = "DISPATCHING ASYNC MESHERS...".to_string();
-                            let world_arc = Arc::new(world.clone());
                             let mut keys: Vec<_> = world.chunks.keys().cloned().collect();
                             // Priority Sort: Mesh spawn area first
                             keys.sort_by_key(|k| k.0 * k.0 + k.2 * k.2);
 
                             for key in keys {
                                 if !renderer.chunk_meshes.contains_key(&key) && !renderer.pending_chunks.contains(&key) {
-                                    renderer.pending_chunks.insert(key);
-                                    let _ = renderer.mesh_tx.send((key.0, key.1, key.2, 0, world_arc.clone()));
+                                    renderer.queue_mesh(&world, key);
                                 }
                             }
                             load_step = 4;
@@ -702,12 +756,7 @@ world.entities.push(ent);
                                             let wz = dz * 16;
                                             
                                             // Force column generation immediately
-                                            let _noise = crate::noise_gen::NoiseGenerator::new(world.seed);
-                                            for y_chunk in 0..8 {
-                                                if !world.chunks.contains_key(&(dx, y_chunk, dz)) {
-                                                    world.bootstrap_terrain_step(dx * 1337 + dz); // Dummy step for force gen
-                                                }
-                                            }
+                                            world.ensure_column(dx, dz);
 
                                             let h = world.get_height_at(wx, wz);
                                             let blk = world.get_block(BlockPos { x: wx, y: h, z: wz });
@@ -806,9 +855,29 @@ world.entities.push(ent);
                 if game_state == GameState::Playing {
                     // 1. INFINITE GENERATION CALL (OPTIMIZED)
                     let p_cx = (player.position.x / 16.0).floor() as i32;
-                    let p_cy = (player.position.y / 16.0).floor() as i32;
                     let p_cz = (player.position.z / 16.0).floor() as i32;
-                    world.generate_one_chunk_around(p_cx, p_cy, p_cz, 4); // Reduced from 8
+                    // A dedicated server sends its terrain and says what to drop; hold still until the ground arrives
+                    let streamed = network_mgr.as_ref().is_some_and(|n| n.streamed);
+                    player.stasis = streamed && !world.chunks.contains_key(&(p_cx, 0, p_cz));
+                    // Keep loading (and meshing) as far as the LOD rings draw, as long as that fits the chunk budget
+                    world.view_distance = (renderer.render_distance as i32).min(view_cap);
+                    if !streamed {
+                        world.request_chunks_around(p_cx, p_cz, world.view_distance);
+                        let fresh = world.collect_generated();
+                        world.dirty_chunks.extend(fresh); // Mesh new terrain right away instead of on the next ring sweep
+                    }
+
+                    // CHUNK STREAMING: Evict what's behind us whenever we cross into a new column
+                    if !streamed && (p_cx, p_cz) != last_stream_col {
+                        last_stream_col = (p_cx, p_cz);
+                        let max_chunks = resource_manager::get_resource_manager().limits().max_chunks;
+                        let evicted = world.unload_far_chunks(p_cx, p_cz, max_chunks);
+                        renderer.unload_chunk_meshes(&evicted);
+                        resource_manager::track_chunk_usage(world.chunks.len());
+                        resource_manager::track_pending_tasks(renderer.pending_chunks.len());
+                        let stats = resource_manager::cleanup_if_needed();
+                        if stats.has_cleaned_anything() { log::warn!("[RESOURCES] Over budget after streaming: {:?}", stats); }
+                    }
 
                     player.capture_state(); 
 
@@ -820,13 +889,19 @@ world.entities.push(ent);
                         };
                         let is_cave = world.get_light_world(head_pos) < 6;
                         
-                        player.update(&world, FIXED_TIME, &audio, is_cave);
+                        // Against a dedicated server every step is also an input command for it
+                        if streamed { prediction.step(&mut player, &world, FIXED_TIME, &audio, is_cave); }
+                        else { player.update(&world, FIXED_TIME, &audio, is_cave); }
                         world.update_entities(FIXED_TIME, &mut player);
+                        // The server runs the block simulation on what it has loaded; ours would drift from it
+                        if !streamed { world.update_ticks(FIXED_TIME); }
                         accumulator -= FIXED_TIME;
                     }
                     
                     let alpha = accumulator / FIXED_TIME;
                     renderer.update_camera(&player, win_size.0 as f32 / win_size.1 as f32, alpha);
+                    weather.update(_dt_frame, &world, player.position);
+                    renderer.update_clouds(&weather);
 
                     // DIABOLICAL FIRST-FRAME STABILITY
                     // DIABOLICAL FIRST-FRAME STABILITY
@@ -835,21 +910,9 @@ world.entities.push(ent);
                         first_build_done = true;
                     }
 
-                    // --- INFINITE GENERATION (OPTIMIZED) ---
-                    let p_cx = (player.position.x / 16.0).floor() as i32;
-                    let p_cz = (player.position.z / 16.0).floor() as i32;
-                    let r_dist = 4; // Reduced from 6 for better performance
-                    for dx in -r_dist..=r_dist {
-                        for dz in -r_dist..=r_dist {
-                            let target = (p_cx + dx, 0, p_cz + dz);
-                            if !world.chunks.contains_key(&target) {
-                                world.generate_one_chunk_around(p_cx + dx, 0, p_cz + dz, 1);
-                            }
-                        }
-                    }
-
                     // --- DAY/NIGHT CYCLE ---
-                    let _day_time = (renderer.start_time.elapsed().as_secs_f32() % 600.0) / 600.0;
+                    let day_time = (renderer.start_time.elapsed().as_secs_f32() % 600.0) / 600.0;
+                    renderer.set_day_time(day_time);
 
 // DIABOLICAL AUTO-SAVE: Save every 10 seconds to stop cargo-watch restart loops
                     if last_persist.elapsed().as_millis() >= 10000 {
@@ -863,48 +926,75 @@ world.entities.push(ent);
 if let Ok(contents) = serde_json::to_string(&save_data) {
                             let _ = fs::write("target/.live_state.json", contents);
                         }
+                        world.save_modified();
                         last_persist = Instant::now();
                     }
 
                     if let Some(network) = &mut network_mgr {
                         while let Some(pkt) = network.try_recv() {
                             match pkt {
-                                Packet::Handshake { seed, .. } => {
-                                    log::info!("🌍 RECEIVED SEED: {}. REBUILDING WORLD...", seed);
+                                Packet::Handshake { seed, id, .. } => {
+                                    log::info!("🌍 RECEIVED SEED: {}. REBUILDING WORLD AS PLAYER {}...", seed, id);
+                                    network.my_id = id;
+                                    prediction = prediction::Prediction::new();
+                                    remotes = prediction::RemoteEntities::new();
                                     world = World::new(seed); renderer.rebuild_all_chunks(&world);
-                                    // RE-RUN SPAWN LOGIC FOR CLIENT
-                                    let mut spawn_found = false;
-                                    'net_spawn: for r in 0..300i32 {
-                                        for x in -r..=r { for z in -r..=r {
-                                            if x.abs() != r && z.abs() != r { continue; }
-                                            for y in (0..150).rev() {
-                                                let b = world.get_block(BlockPos{x, y, z});
-                                                if b.is_solid() && !b.is_water() {
-                                                    player.respawn(); 
-                                                    let surface_y = world.get_height_at(x, z) as f32;
-                                                    player.position = glam::Vec3::new(x as f32 + 0.5, surface_y + 2.5, z as f32 + 0.5);
-                                                    player.health = 10.0; // Set health to 10 hearts
-                                                    spawn_found = true; break 'net_spawn;
-                                                }
-                                            }
-                                        }}
-                                    }
-if !spawn_found { player.position = glam::Vec3::new(0.0, 80.0, 0.0); player.velocity = glam::Vec3::ZERO; }
+                                    // Nothing is loaded yet to search for ground in: wait above spawn until it arrives
+                                    player.respawn();
+                                    player.position = glam::Vec3::new(0.5, 80.0, 0.5);
                                 },
-                                Packet::PlayerMove { id, x, y, z, ry } => {
-
-                                    if let Some(p) = world.remote_players.iter_mut().find(|p| p.id == id) { p.position = glam::Vec3::new(x,y,z); p.rotation = ry; } 
-                                    else { world.remote_players.push(world::RemotePlayer{id, position:glam::Vec3::new(x,y,z), rotation:ry}); }
+                                Packet::PlayerMove { id, x, y, z, rx, ry } => {
+                                    remotes.push(id, net_clock.elapsed().as_secs_f64(), glam::Vec3::new(x, y, z), ry, rx);
+                                },
+                                Packet::PlayerState(snapshot) => {
+                                    if snapshot.id == network.my_id { prediction.reconcile(&mut player, &snapshot, &world, &audio); }
+                                    else { remotes.push(snapshot.id, net_clock.elapsed().as_secs_f64(), snapshot.position, snapshot.yaw, snapshot.pitch); }
                                 },
+                                Packet::PlayerInput { .. } => {}, // Only a dedicated server reads input
                                 Packet::BlockUpdate { pos, block } => { 
                                     let _c = world.place_block(pos, block); 
                                     // Renderer automatically picks up world.mesh_dirty flag
                                 },
-                                _ => {}
+                                Packet::ServerInfo { motd, players, max_players } => {
+                                    log::info!("📜 {} ({}/{} players online)", motd, players, max_players);
+                                    network.streamed = true;
+                                    // The server simulates us without any of these; predict the same
+                                    (player.is_flying, player.is_noclip, player.admin_speed) = (false, false, 1.0);
+                                },
+                                Packet::ChunkColumn { cx, cz, sections } => match network::decode_column(&sections) {
+                                    Ok(sections) => {
+                                        let fresh = world.insert_column(cx, cz, sections);
+                                        world.dirty_chunks.extend(fresh);
+                                    }
+                                    Err(e) => log::warn!("Bad chunk column {:?} from the server: {}", (cx, cz), e),
+                                },
+                                Packet::UnloadColumn { cx, cz } => {
+                                    let removed = world.remove_column(cx, cz);
+                                    renderer.unload_chunk_meshes(&removed);
+                                },
+                                Packet::PlayerJoin { id } => {
+                                    log::info!("👋 Player {} joined", id);
+                                    // Show the newcomer where we are now instead of on our next move update
+                                    if network.is_server {
+                                        let here = Packet::PlayerMove { id: network.my_id, x: player.position.x, y: player.position.y, z: player.position.z, rx: player.rotation.x, ry: player.rotation.y };
+                                        network.send_to(network::Target::Player(id), here);
+                                    }
+                                },
+                                Packet::PlayerLeave { id } => {
+                                    log::info!("👋 Player {} left", id);
+                                    remotes.remove(id);
+                                },
+                                Packet::Disconnect => log::warn!("Disconnected by the server"),
                             }
                         }
-                        net_timer += _dt_frame; 
-                        if net_timer > 0.05 { net_timer = 0.0; network.send_packet(Packet::PlayerMove { id: network.my_id, x: player.position.x, y: player.position.y, z: player.position.z, ry: player.rotation.y }); }
+                        if streamed {
+                            let commands = prediction.outgoing();
+                            if !commands.is_empty() { network.send_packet(Packet::PlayerInput { commands }); }
+                        } else {
+                            net_timer += _dt_frame; 
+                            if net_timer > 0.05 { net_timer = 0.0; network.send_packet(Packet::PlayerMove { id: network.my_id, x: player.position.x, y: player.position.y, z: player.position.z, rx: player.rotation.x, ry: player.rotation.y }); }
+                        }
+                        remotes.apply(net_clock.elapsed().as_secs_f64(), &mut world.remote_players);
                     }
 
 if !is_paused {
@@ -912,7 +1002,14 @@ if !is_paused {
                         // DEATH
                         if player.is_dead {
                             death_timer += _dt_frame;
-                            if death_timer > 3.0 {
+                            if death_timer > 3.0 && streamed {
+                                // The server picks the spawn point; stay put until its snapshot moves us
+                                let here = player.position;
+                                player.respawn();
+                                player.position = here;
+                                prediction.respawn();
+                                death_timer = 0.0;
+                            } else if death_timer > 3.0 {
                                 spawn_found = false;
                                 'respawn: for r in 0..300i32 {
                                     for x in -r..=r { for z in -r..=r {
@@ -986,7 +1083,7 @@ if !is_paused {
                                             let is_submerged = world.get_block(head_p).is_water();
                                             audio.play(s_type, is_submerged);
                                             let _c = world.break_block(hit);
-                                            if let Some(net) = &network_mgr { net.send_packet(Packet::BlockUpdate { pos: hit, block: BlockType::Air }); }
+                                            if let Some(net) = &mut network_mgr { net.send_packet(Packet::BlockUpdate { pos: hit, block: BlockType::Air }); }
                                             breaking_pos = None; break_progress = 0.0;
                                         }
                                     }
@@ -1022,20 +1119,14 @@ Event::AboutToWait => {
                 // DIABOLICAL THREADING: Only process world-gen and cursor logic if we are actually in the game.
                 if game_state == GameState::Playing && !is_paused && !player.inventory_open {
                     // DIABOLICAL SPAWN SAFETY: If player falls into void/water on load (Y < 20), teleport to surface
-                    if player.position.y < 20.0 {
+                    if player.position.y < 20.0 && !network_mgr.as_ref().is_some_and(|n| n.streamed) {
                         let surface_y = world.get_height_at(player.position.x as i32, player.position.z as i32) as f32;
                         player.position.y = surface_y + 2.5; // Spawn on surface
                         player.velocity = glam::Vec3::ZERO; // Kill momentum
                         player.health = 10.0; // Set health to 10 hearts
                     }
 
-                    let p_cx = (player.position.x / 16.0).floor() as i32;
-                    let p_cy = (player.position.y / 16.0).floor() as i32;
-                    let p_cz = (player.position.z / 16.0).floor() as i32;
-                    world.generate_one_chunk_around(p_cx, p_cy, p_cz, 8);
-
                     // Ensure cursor state is always correct
-                    let _noise = crate::noise_gen::NoiseGenerator::new(world.seed); // DIABOLICAL FIX: Prefix unused var
                     let _ = window.set_cursor_grab(CursorGrabMode::Locked);
                     window.set_cursor_visible(false);
                 }
@@ -0,0 +1,155 @@
+//! One vertex buffer and one index buffer shared by every chunk mesh
+//!
+//! Chunk meshes are suballocated from two large GPU buffers instead of owning a pair each, so the
+//! whole world can be bound once and drawn from a single indirect buffer. Each mesh holds an
+//! `ArenaSlot`; dropping the slot hands its ranges back to the arena, which reuses them on the next
+//! allocation. When a request doesn't fit anywhere the buffers double in size and the old contents
+//! are copied across, so slots keep their offsets for their whole life.
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::ops::Range;
+use wgpu::*;
+
+use crate::renderer::Vertex;
+
+const INITIAL_VERTICES: u32 = 1 << 20;
+const INITIAL_INDICES: u32 = 3 << 19;
+
+/// First-fit allocator over element indices, keeping its free list sorted and coalesced.
+struct RangeAllocator {
+    free: Vec<Range<u32>>,
+    capacity: u32,
+}
+
+impl RangeAllocator {
+    fn new(capacity: u32) -> Self {
+        let mut allocator = Self { free: Vec::new(), capacity: 0 };
+        allocator.grow(capacity);
+        allocator
+    }
+
+    fn alloc(&mut self, len: u32) -> Option<Range<u32>> {
+        let i = self.free.iter().position(|r| r.end - r.start >= len)?;
+        let start = self.free[i].start;
+        self.free[i].start += len;
+        if self.free[i].is_empty() { self.free.remove(i); }
+        Some(start..start + len)
+    }
+
+    fn release(&mut self, range: Range<u32>) {
+        if range.is_empty() { return; }
+        let i = self.free.partition_point(|r| r.start < range.start);
+        self.free.insert(i, range);
+        if i + 1 < self.free.len() && self.free[i].end == self.free[i + 1].start {
+            self.free[i].end = self.free.remove(i + 1).end;
+        }
+        if i > 0 && self.free[i - 1].end == self.free[i].start {
+            self.free[i - 1].end = self.free.remove(i).end;
+        }
+    }
+
+    /// Extends the space to `capacity`, merging the new tail into a trailing free range.
+    fn grow(&mut self, capacity: u32) {
+        let old = std::mem::replace(&mut self.capacity, capacity);
+        self.release(old..capacity);
+    }
+}
+
+/// A mesh's share of the arena. Its ranges go back to the arena when it is dropped.
+pub struct ArenaSlot {
+    pub vertices: Range<u32>,
+    pub indices: Range<u32>,
+    release: Sender<(Range<u32>, Range<u32>)>,
+}
+
+impl ArenaSlot {
+    pub fn base_vertex(&self) -> i32 { self.vertices.start as i32 }
+}
+
+impl Drop for ArenaSlot {
+    fn drop(&mut self) { let _ = self.release.send((self.vertices.clone(), self.indices.clone())); }
+}
+
+pub struct MeshArena {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    vertices: RangeAllocator,
+    indices: RangeAllocator,
+    release_tx: Sender<(Range<u32>, Range<u32>)>,
+    release_rx: Receiver<(Range<u32>, Range<u32>)>,
+}
+
+fn arena_buffer(device: &Device, label: &str, size: u64, usage: BufferUsages) -> Buffer {
+    device.create_buffer(&BufferDescriptor { label: Some(label), size, usage: usage | BufferUsages::COPY_DST | BufferUsages::COPY_SRC, mapped_at_creation: false })
+}
+
+const VERTEX_SIZE: u64 = std::mem::size_of::<Vertex>() as u64;
+
+impl MeshArena {
+    pub fn new(device: &Device) -> Self {
+        let (release_tx, release_rx) = unbounded();
+        Self {
+            vertex_buffer: arena_buffer(device, "Mesh Arena VB", INITIAL_VERTICES as u64 * VERTEX_SIZE, BufferUsages::VERTEX),
+            index_buffer: arena_buffer(device, "Mesh Arena IB", INITIAL_INDICES as u64 * 4, BufferUsages::INDEX),
+            vertices: RangeAllocator::new(INITIAL_VERTICES),
+            indices: RangeAllocator::new(INITIAL_INDICES),
+            release_tx,
+            release_rx,
+        }
+    }
+
+    /// Uploads a mesh into the arena, growing it if needed. Indices stay relative to the mesh's
+    /// first vertex; draws add `base_vertex`.
+    pub fn upload(&mut self, device: &Device, queue: &Queue, vertices: &[Vertex], indices: &[u32]) -> ArenaSlot {
+        while let Ok((v, i)) = self.release_rx.try_recv() {
+            self.vertices.release(v);
+            self.indices.release(i);
+        }
+        let v = loop {
+            match self.vertices.alloc(vertices.len() as u32) {
+                Some(r) => break r,
+                None => self.grow_vertices(device, queue, vertices.len() as u32),
+            }
+        };
+        let i = loop {
+            match self.indices.alloc(indices.len() as u32) {
+                Some(r) => break r,
+                None => self.grow_indices(device, queue, indices.len() as u32),
+            }
+        };
+        queue.write_buffer(&self.vertex_buffer, v.start as u64 * VERTEX_SIZE, bytemuck::cast_slice(vertices));
+        queue.write_buffer(&self.index_buffer, i.start as u64 * 4, bytemuck::cast_slice(indices));
+        ArenaSlot { vertices: v, indices: i, release: self.release_tx.clone() }
+    }
+
+    /// Overwrites part of a slot's indices, `offset` indices past its first one.
+    pub fn write_indices(&self, queue: &Queue, slot: &ArenaSlot, offset: u32, indices: &[u32]) {
+        debug_assert!(slot.indices.start + offset + indices.len() as u32 <= slot.indices.end);
+        queue.write_buffer(&self.index_buffer, (slot.indices.start + offset) as u64 * 4, bytemuck::cast_slice(indices));
+    }
+
+    fn grow_vertices(&mut self, device: &Device, queue: &Queue, at_least: u32) {
+        let capacity = (self.vertices.capacity * 2).max(self.vertices.capacity + at_least);
+        log::info!("Mesh arena: growing vertex buffer to {} vertices", capacity);
+        let buffer = arena_buffer(device, "Mesh Arena VB", capacity as u64 * VERTEX_SIZE, BufferUsages::VERTEX);
+        copy_into(device, queue, &self.vertex_buffer, &buffer);
+        self.vertex_buffer = buffer;
+        self.vertices.grow(capacity);
+    }
+
+    fn grow_indices(&mut self, device: &Device, queue: &Queue, at_least: u32) {
+        let capacity = (self.indices.capacity * 2).max(self.indices.capacity + at_least);
+        log::info!("Mesh arena: growing index buffer to {} indices", capacity);
+        let buffer = arena_buffer(device, "Mesh Arena IB", capacity as u64 * 4, BufferUsages::INDEX);
+        copy_into(device, queue, &self.index_buffer, &buffer);
+        self.index_buffer = buffer;
+        self.indices.grow(capacity);
+    }
+}
+
+/// Copies all of `from` to the start of `to`. Writes queued before this still land in `from` first.
+fn copy_into(device: &Device, queue: &Queue, from: &Buffer, to: &Buffer) {
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Mesh Arena Grow") });
+    encoder.copy_buffer_to_buffer(from, 0, to, 0, from.size());
+    queue.submit(Some(encoder.finish()));
+}
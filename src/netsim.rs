@@ -0,0 +1,64 @@
+//! Network condition simulator
+//!
+//! Loopback is too good a network to see what prediction and interpolation are doing. A
+//! `LinkConditioner` sits between the game and one direction of a connection and holds every
+//! packet back by a fixed latency plus random jitter, and drops a share of the packets that are
+//! safe to lose. Packets still come out in the order they went in, the way they would on the TCP
+//! stream underneath.
+//!
+//! Start the client with `--netsim <latency ms>,<jitter ms>,<loss %>` (e.g. `--netsim 150,40,5`);
+//! the conditions apply in both directions.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::world::SimpleRng;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkConditions {
+    pub latency: Duration,
+    /// Extra delay, picked uniformly between zero and this for every packet.
+    pub jitter: Duration,
+    /// Share of droppable packets lost, 0 to 1.
+    pub loss: f32,
+}
+
+impl LinkConditions {
+    /// Parses `<latency ms>,<jitter ms>,<loss %>`; missing trailing values are zero.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut values = spec.split(',').map(|v| v.trim().parse::<f32>());
+        let mut next = || values.next().unwrap_or(Ok(0.0)).ok().filter(|v| *v >= 0.0);
+        let (latency, jitter, loss) = (next()?, next()?, next()?);
+        if loss > 100.0 { return None; }
+        let ms = |v: f32| Duration::from_micros((v * 1000.0).round() as u64);
+        Some(Self { latency: ms(latency), jitter: ms(jitter), loss: loss / 100.0 })
+    }
+}
+
+pub struct LinkConditioner<T> {
+    conditions: LinkConditions,
+    rng: SimpleRng,
+    queue: VecDeque<(Instant, T)>,
+}
+
+impl<T> LinkConditioner<T> {
+    pub fn new(conditions: LinkConditions, seed: u64) -> Self {
+        Self { conditions, rng: SimpleRng::new(seed), queue: VecDeque::new() }
+    }
+
+    /// Queues `item` sent at `now`. Returns false if it was lost instead (only `droppable` ones are).
+    pub fn push(&mut self, now: Instant, item: T, droppable: bool) -> bool {
+        if droppable && self.conditions.loss > 0.0 && self.rng.next_f32() < self.conditions.loss { return false; }
+        let delay = self.conditions.latency + self.conditions.jitter.mul_f32(self.rng.next_f32());
+        // Nothing overtakes what was sent before it
+        let due = self.queue.back().map_or(now + delay, |&(last, _)| last.max(now + delay));
+        self.queue.push_back((due, item));
+        true
+    }
+
+    /// The next item due by `now`, if any.
+    pub fn pop(&mut self, now: Instant) -> Option<T> {
+        if self.queue.front()?.0 > now { return None; }
+        self.queue.pop_front().map(|(_, item)| item)
+    }
+}
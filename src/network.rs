@@ -1,32 +1,61 @@
-use std::net::{TcpListener, TcpStream};
-use std::io::{Read, Write};
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::thread;
+use std::time::Instant;
 use crossbeam_channel::{unbounded, Sender, Receiver};
 use serde::{Serialize, Deserialize};
-use crate::world::{BlockPos, BlockType};
+use crate::netsim::{LinkConditioner, LinkConditions};
+use crate::prediction::{InputCommand, PlayerSnapshot, MAX_INPUT_BATCH};
+use crate::protocol::{write_packet, PacketReader};
+use crate::region::{decode_chunk, encode_chunk};
+use crate::world::{BlockPos, BlockType, Chunk, World, WORLD_HEIGHT};
+
+const SECTIONS: usize = (WORLD_HEIGHT / 16) as usize;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Packet {
-    Handshake { username: String, seed: u32 },
-    PlayerMove { id: u32, x: f32, y: f32, z: f32, ry: f32 },
+    /// First thing a host or server sends: the world seed and the id this client plays as.
+    Handshake { username: String, seed: u32, id: u32 },
+    PlayerMove { id: u32, x: f32, y: f32, z: f32, rx: f32, ry: f32 },
     BlockUpdate { pos: BlockPos, block: BlockType },
     Disconnect,
+    /// Sent by a dedicated server right after the handshake.
+    ServerInfo { motd: String, players: u32, max_players: u32 },
+    /// A whole column from a dedicated server, one `region::encode_chunk` payload per section
+    /// from the bottom up. Replaces whatever the client had there.
+    ChunkColumn { cx: i32, cz: i32, sections: Vec<Vec<u8>> },
+    /// The client left the server's view distance of this column and should drop it.
+    UnloadColumn { cx: i32, cz: i32 },
+    /// Another player is in the game (sent for everyone already there when joining, too).
+    PlayerJoin { id: u32 },
+    PlayerLeave { id: u32 },
+    /// Client to dedicated server: every movement command it hasn't seen acknowledged, oldest first.
+    PlayerInput { commands: Vec<InputCommand> },
+    /// Dedicated server to clients, every tick for every moving player.
+    PlayerState(PlayerSnapshot),
 }
 
 #[allow(dead_code)]
 pub struct NetworkManager {
     pub is_server: bool,
     pub stream: Option<TcpStream>,
-    sender: Sender<Packet>,
+    sender: Sender<(Target, Packet)>,
     receiver: Receiver<Packet>,
+    inbound: LinkConditioner<Packet>,
+    outbound: LinkConditioner<(Target, Packet)>,
+    /// Assigned by the host in the handshake; 0 until it arrives.
     pub my_id: u32,
     pub seed: Option<u32>,
+    /// The server sends the terrain (set on its `ServerInfo`); don't generate any locally.
+    pub streamed: bool,
+    /// Where a host is listening; `None` on a client.
+    local_addr: Option<SocketAddr>,
 }
 
 impl Packet {
     pub fn validate(&self) -> Result<(), String> {
         match self {
-            Packet::Handshake { username, seed } => {
+            Packet::Handshake { username, seed, id } => {
                 if username.len() > 32 {
                     return Err("Username too long (max 32 characters)".to_string());
                 }
@@ -37,13 +66,28 @@ impl Packet {
                 if *seed > u32::MAX / 2 {
                     return Err("Invalid seed value".to_string());
                 }
+                if *id > 10000 {
+                    return Err("Invalid player ID".to_string());
+                }
+            }
+            Packet::PlayerJoin { id } | Packet::PlayerLeave { id } => {
+                if *id > 10000 {
+                    return Err("Invalid player ID".to_string());
+                }
+            }
+            Packet::PlayerInput { commands } => {
+                if commands.is_empty() || commands.len() > MAX_INPUT_BATCH {
+                    return Err(format!("{} input commands in one packet, expected 1..={}", commands.len(), MAX_INPUT_BATCH));
+                }
+                commands.iter().try_for_each(InputCommand::validate)?;
             }
-            Packet::PlayerMove { id, x, y, z, ry } => {
+            Packet::PlayerState(snapshot) => snapshot.validate()?,
+            Packet::PlayerMove { id, x, y, z, rx, ry } => {
                 if *id > 10000 {
                     return Err("Invalid player ID".to_string());
                 }
                 // Validate coordinates are within reasonable world bounds
-                if !x.is_finite() || !y.is_finite() || !z.is_finite() || !ry.is_finite() {
+                if !x.is_finite() || !y.is_finite() || !z.is_finite() || !rx.is_finite() || !ry.is_finite() {
                     return Err("Invalid coordinates (NaN or infinite)".to_string());
                 }
                 if x.abs() > 100000.0 || y.abs() > 100000.0 || z.abs() > 100000.0 {
@@ -63,18 +107,232 @@ impl Packet {
             Packet::Disconnect => {
                 // Disconnect packet is always valid
             }
+            Packet::ChunkColumn { cx, cz, sections } => {
+                if cx.abs() > 10000 / 16 || cz.abs() > 10000 / 16 {
+                    return Err("Chunk column out of bounds".to_string());
+                }
+                if sections.len() != SECTIONS {
+                    return Err(format!("Chunk column has {} sections, expected {}", sections.len(), SECTIONS));
+                }
+            }
+            Packet::UnloadColumn { cx, cz } => {
+                if cx.abs() > 10000 / 16 || cz.abs() > 10000 / 16 {
+                    return Err("Chunk column out of bounds".to_string());
+                }
+            }
+            Packet::ServerInfo { motd, players, max_players } => {
+                if motd.len() > 256 {
+                    return Err("MOTD too long (max 256 bytes)".to_string());
+                }
+                if players > max_players {
+                    return Err("More players online than the server allows".to_string());
+                }
+            }
         }
         Ok(())
     }
+
+    /// Movement updates are superseded by the next one, so the link simulator may drop them.
+    pub fn is_movement(&self) -> bool {
+        matches!(self, Packet::PlayerMove { .. } | Packet::PlayerInput { .. } | Packet::PlayerState(_))
+    }
+
+    /// `ChunkColumn` for column (cx, cz) of `world`; every section of it has to be loaded.
+    #[allow(dead_code)] // Only the dedicated server sends terrain
+    pub fn chunk_column(world: &World, cx: i32, cz: i32) -> std::io::Result<Packet> {
+        let sections = (0..SECTIONS as i32).map(|cy| match world.chunks.get(&(cx, cy, cz)) {
+            Some(chunk) => encode_chunk(chunk),
+            None => Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("section {:?} isn't loaded", (cx, cy, cz)))),
+        }).collect::<std::io::Result<_>>()?;
+        Ok(Packet::ChunkColumn { cx, cz, sections })
+    }
+}
+
+/// The sections of a `ChunkColumn`, bottom to top, ready for `World::insert_column`.
+pub fn decode_column(sections: &[Vec<u8>]) -> std::io::Result<Vec<Chunk>> {
+    sections.iter().map(|data| decode_chunk(data)).collect()
+}
+
+/// Who a packet sent through `NetworkManager` should reach. A client only talks to its server,
+/// so there every target means the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    All,
+    Player(u32),
+    /// Everyone but this player, e.g. when relaying what it sent.
+    AllExcept(u32),
+}
+
+impl Target {
+    fn includes(self, id: u32) -> bool {
+        match self {
+            Target::All => true,
+            Target::Player(target) => id == target,
+            Target::AllExcept(sender) => id != sender,
+        }
+    }
+}
+
+/// The hosting player's id; clients are numbered from 2 in the order they join.
+pub const HOST_ID: u32 = 1;
+/// How long the host's router waits for traffic before checking for new connections again.
+const ACCEPT_POLL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// What a client's reader thread reports to the host: a packet, or None once it's gone.
+type Inbound = (u32, Option<Packet>);
+
+/// The host's session table. Runs on its own thread: accepts connections, gives each an id and
+/// its own writer, routes what the host sends by `Target`, and relays what clients send to the
+/// other clients (under the sender's real id) as well as to the host's game loop.
+struct HostRouter {
+    listener: TcpListener,
+    seed: u32,
+    sessions: BTreeMap<u32, Sender<Packet>>,
+    inbox: Receiver<Inbound>,
+    inbox_tx: Sender<Inbound>,
+    to_host: Sender<Packet>,
+    next_id: u32,
+}
+
+impl HostRouter {
+    fn run(mut self, outgoing: Receiver<(Target, Packet)>) {
+        loop {
+            self.accept();
+            crossbeam_channel::select! {
+                recv(self.inbox) -> inbound => match inbound {
+                    Ok((id, Some(packet))) => self.handle(id, packet),
+                    Ok((id, None)) => self.drop_session(id),
+                    Err(_) => return,
+                },
+                recv(outgoing) -> sent => match sent {
+                    Ok((target, packet)) => self.route(target, &packet),
+                    Err(_) => return, // The host left; dropping the sessions closes every connection
+                },
+                default(ACCEPT_POLL) => {}
+            }
+        }
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let (stream, addr) = match self.listener.accept() {
+                Ok(connection) => connection,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    log::warn!("Accept failed: {:?}", e);
+                    return;
+                }
+            };
+            if let Err(e) = self.open_session(stream, addr) {
+                log::warn!("Failed to set up connection from {:?}: {:?}", addr, e);
+            }
+        }
+    }
+
+    fn open_session(&mut self, mut stream: TcpStream, addr: SocketAddr) -> std::io::Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+        println!("✨ NEW PLAYER CONNECTED: {:?} (ID: {})", addr, id);
+        stream.set_nonblocking(false)?;
+
+        // --- RADICAL MULTIPLAYER HANDSHAKE ---
+        // Forcefully sync the seed and the player's id before anything else reaches the client
+        write_packet(&mut stream, &Packet::Handshake { username: "Host".to_string(), seed: self.seed, id })?;
+        for &other in std::iter::once(&HOST_ID).chain(self.sessions.keys()) {
+            write_packet(&mut stream, &Packet::PlayerJoin { id: other })?;
+        }
+        // --------------------------------------
+
+        let reader = stream.try_clone()?;
+        let inbox = self.inbox_tx.clone();
+        thread::spawn(move || {
+            let mut reader = PacketReader::new(reader);
+            loop {
+                let packet = match reader.read_packet() {
+                    Ok(Some(packet)) => packet,
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::warn!("Dropping client {:?}: {}", addr, e);
+                        break;
+                    }
+                };
+                // Validate packet before processing
+                if let Err(e) = packet.validate() {
+                    log::warn!("Invalid packet received: {}", e);
+                    continue;
+                }
+                if inbox.send((id, Some(packet))).is_err() { return; }
+            }
+            let _ = inbox.send((id, None));
+        });
+
+        let (outbox, queue) = unbounded::<Packet>();
+        thread::spawn(move || {
+            while let Ok(packet) = queue.recv() {
+                // Validate packet before sending
+                if let Err(e) = packet.validate() {
+                    log::warn!("Attempted to send invalid packet: {}", e);
+                    continue;
+                }
+                if let Err(e) = write_packet(&mut stream, &packet) {
+                    log::debug!("Failed to write packet to stream: {:?}", e);
+                    break;
+                }
+            }
+            // Session dropped: also wakes the reader thread
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        });
+
+        self.route(Target::All, &Packet::PlayerJoin { id });
+        let _ = self.to_host.send(Packet::PlayerJoin { id });
+        self.sessions.insert(id, outbox);
+        Ok(())
+    }
+
+    fn drop_session(&mut self, id: u32) {
+        if self.sessions.remove(&id).is_some() {
+            println!("👋 PLAYER {} LEFT", id);
+            self.route(Target::All, &Packet::PlayerLeave { id });
+            let _ = self.to_host.send(Packet::PlayerLeave { id });
+        }
+    }
+
+    fn handle(&mut self, id: u32, packet: Packet) {
+        match packet {
+            Packet::PlayerMove { x, y, z, rx, ry, .. } => {
+                // Whatever id the client claims, it moves as the player this connection belongs to
+                let packet = Packet::PlayerMove { id, x, y, z, rx, ry };
+                self.route(Target::AllExcept(id), &packet);
+                let _ = self.to_host.send(packet);
+            }
+            Packet::BlockUpdate { .. } => {
+                self.route(Target::AllExcept(id), &packet);
+                let _ = self.to_host.send(packet);
+            }
+            Packet::Disconnect => self.drop_session(id),
+            // Only the host hands these out
+            Packet::Handshake { .. } | Packet::ServerInfo { .. } | Packet::ChunkColumn { .. } | Packet::UnloadColumn { .. }
+            | Packet::PlayerJoin { .. } | Packet::PlayerLeave { .. } | Packet::PlayerState(_) => {}
+            // Clients of a listen host report positions; only a dedicated server simulates input
+            Packet::PlayerInput { .. } => {}
+        }
+    }
+
+    fn route(&self, target: Target, packet: &Packet) {
+        for (&id, outbox) in &self.sessions {
+            if target.includes(id) { let _ = outbox.send(packet.clone()); }
+        }
+    }
 }
 
 impl NetworkManager {
-    pub fn host(_port: String, seed: u32) -> Self {
+    /// Listens on `port` on every interface ("0" picks a free one, see `local_addr`).
+    pub fn host(port: String, seed: u32) -> Self {
         let (tx_in, rx_in) = unbounded();
         let (tx_out, rx_out) = unbounded();
 
         // DIABOLICAL FIX: 0.0.0.0 binds to EVERY interface (LAN, Hamachi, Ngrok) simultaneously
-        let address = "0.0.0.0:25565";
+        let address = format!("0.0.0.0:{}", port);
         println!("🔥 HOSTING SERVER ON: {}", address);
 
         let listener = match TcpListener::bind(&address) {
@@ -87,104 +345,29 @@ impl NetworkManager {
         if let Err(e) = listener.set_nonblocking(true) {
             log::error!("Failed to set non-blocking mode: {:?}", e);
         }
+        let local_addr = listener.local_addr().ok();
 
-        let tx_in_clone = tx_in.clone();
-
-        thread::spawn(move || {
-            let mut client_id_counter = 2; // Host is 1
-            loop {
-                if let Ok((mut stream, addr)) = listener.accept() {
-                    println!("✨ NEW PLAYER CONNECTED: {:?} (ID: {})", addr, client_id_counter);
-
-                    let _ = stream.set_nonblocking(false);
-
-                    // --- RADICAL MULTIPLAYER HANDSHAKE ---
-                    // Forcefully sync the seed and ensure the client receives it before spawning
-                    let handshake = Packet::Handshake { username: "Host".to_string(), seed };
-                    if let Ok(bytes) = bincode::serialize(&handshake) {
-                        let _ = stream.write_all(&bytes);
-                        let _ = stream.flush();
-                    }
-                    // --------------------------------------
-
-                    let mut stream_clone = match stream.try_clone() {
-                        Ok(s) => s,
-                        Err(e) => {
-                            log::error!("Failed to clone stream: {:?}", e);
-                            continue;
-                        }
-                    };
-                    let tx_in_thread = tx_in_clone.clone();
-
-                    // Reader
-                    thread::spawn(move || {
-                        let mut buffer = [0u8; 1024];
-                        loop {
-                            match stream.read(&mut buffer) {
-                                Ok(0) => break,
-                                Ok(n) => {
-                                    if let Ok(packet) = bincode::deserialize::<Packet>(&buffer[..n]) {
-                                        // Validate packet before processing
-                                        if let Err(e) = packet.validate() {
-                                            log::warn!("Invalid packet received: {}", e);
-                                            continue;
-                                        }
-                                        if let Err(e) = tx_in_thread.send(packet) {
-                                            log::debug!("Failed to send packet to main thread: {:?}", e);
-                                            break;
-                                        }
-                                    } else {
-                                        log::warn!("Failed to deserialize packet of {} bytes", n);
-                                    }
-                                }
-                                Err(_) => {}
-                            }
-                            thread::sleep(std::time::Duration::from_millis(5));
-                        }
-                    });
-
-                    // Writer (Broadcaster)
-                    let rx_out_thread: crossbeam_channel::Receiver<Packet> = rx_out.clone();
-                    thread::spawn(move || {
-                        while let Ok(packet) = rx_out_thread.recv() {
-                            // Validate packet before sending
-                            if let Err(e) = packet.validate() {
-                                log::warn!("Attempted to send invalid packet: {}", e);
-                                continue;
-                            }
-                            match bincode::serialize(&packet) {
-                                Ok(encoded) => {
-                                    if stream_clone.write_all(&encoded).is_err() {
-                                        log::debug!("Failed to write packet to stream");
-                                        break;
-                                    }
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to serialize packet: {:?}", e);
-                                }
-                            }
-                        }
-                    });
-
-                    client_id_counter += 1;
-                }
-                thread::sleep(std::time::Duration::from_millis(100));
-            }
-        });
+        let (inbox_tx, inbox) = unbounded();
+        let router = HostRouter { listener, seed, sessions: BTreeMap::new(), inbox, inbox_tx, to_host: tx_in, next_id: HOST_ID + 1 };
+        thread::spawn(move || router.run(rx_out));
 
 NetworkManager {
             is_server: true,
             stream: None,
             sender: tx_out,
             receiver: rx_in,
-            my_id: 1,
+            inbound: LinkConditioner::new(LinkConditions::default(), 1),
+            outbound: LinkConditioner::new(LinkConditions::default(), 2),
+            my_id: HOST_ID,
             seed: Some(seed),
+            streamed: false,
+            local_addr,
         }
     }
 
     pub fn join(mut ip: String) -> Self {
         let (tx_in, rx_in) = unbounded();
-        let (tx_out, rx_out): (crossbeam_channel::Sender<Packet>, crossbeam_channel::Receiver<Packet>) = unbounded();
+        let (tx_out, rx_out) = unbounded::<(Target, Packet)>();
 
         // Sanitize Ngrok/SSH Tunnel addresses
         if ip.starts_with("tcp://") { ip = ip.replace("tcp://", ""); }
@@ -209,7 +392,7 @@ NetworkManager {
         };
         println!("\n✅ DIABOLICAL CONNECTION ESTABLISHED!");
 
-        let mut stream_read = match stream.try_clone() {
+        let stream_read = match stream.try_clone() {
             Ok(s) => s,
             Err(e) => {
                 log::error!("Failed to clone stream for reading: {:?}", e);
@@ -226,47 +409,38 @@ NetworkManager {
 
         // Reader
         thread::spawn(move || {
-            let mut buffer = [0u8; 1024];
+            let mut reader = PacketReader::new(stream_read);
             loop {
-                match stream_read.read(&mut buffer) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        if let Ok(packet) = bincode::deserialize::<Packet>(&buffer[..n]) {
-                            // Validate packet before processing
-                            if let Err(e) = packet.validate() {
-                                log::warn!("Invalid packet received from server: {}", e);
-                                continue;
-                            }
-                            // DIABOLICAL FIX: Handle channel disconnect gracefully (world rebuild/quit)
-                            if tx_in.send(packet).is_err() { break; } 
-                        } else {
-                            log::warn!("Failed to deserialize packet of {} bytes from server", n);
-                        }
+                let packet = match reader.read_packet() {
+                    Ok(Some(packet)) => packet,
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::warn!("Lost connection to server: {}", e);
+                        break;
                     }
-                    Err(_) => {}
+                };
+                // Validate packet before processing
+                if let Err(e) = packet.validate() {
+                    log::warn!("Invalid packet received from server: {}", e);
+                    continue;
                 }
-                thread::sleep(std::time::Duration::from_millis(5));
+                // DIABOLICAL FIX: Handle channel disconnect gracefully (world rebuild/quit)
+                if tx_in.send(packet).is_err() { break; }
             }
         });
 
         // Writer
         thread::spawn(move || {
-            while let Ok(packet) = rx_out.recv() {
+            // Everything goes to the server, whoever it's meant for
+            while let Ok((_, packet)) = rx_out.recv() {
                 // Validate packet before sending
                 if let Err(e) = packet.validate() {
                     log::warn!("Attempted to send invalid packet to server: {}", e);
                     continue;
                 }
-                match bincode::serialize(&packet) {
-                    Ok(encoded) => {
-                        if stream_write.write_all(&encoded).is_err() { 
-                            log::debug!("Failed to write packet to server stream");
-                            break; 
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to serialize packet for server: {:?}", e);
-                    }
+                if let Err(e) = write_packet(&mut stream_write, &packet) {
+                    log::debug!("Failed to write packet to server stream: {:?}", e);
+                    break;
                 }
             }
         });
@@ -276,16 +450,52 @@ NetworkManager {
             stream: Some(stream),
             sender: tx_out,
             receiver: rx_in,
-            my_id: 2,
+            inbound: LinkConditioner::new(LinkConditions::default(), 1),
+            outbound: LinkConditioner::new(LinkConditions::default(), 2),
+            my_id: 0,
             seed: None,
+            streamed: false,
+            local_addr: None,
         }
     }
 
-    pub fn send_packet(&self, packet: Packet) {
-        let _ = self.sender.send(packet);
+    /// The address a host is listening on, with the port it actually got.
+    #[allow(dead_code)] // The game hosts on fixed ports; tests ask for any free one
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Simulates a worse network than the real one, in both directions (see `netsim`).
+    pub fn with_link_conditions(mut self, conditions: LinkConditions) -> Self {
+        self.inbound = LinkConditioner::new(conditions, 1);
+        self.outbound = LinkConditioner::new(conditions, 2);
+        self
+    }
+
+    /// Sends to every client when hosting, to the server otherwise.
+    pub fn send_packet(&mut self, packet: Packet) {
+        self.send_to(Target::All, packet);
+    }
+
+    pub fn send_to(&mut self, target: Target, packet: Packet) {
+        let now = Instant::now();
+        let droppable = packet.is_movement();
+        self.outbound.push(now, (target, packet), droppable);
+        self.flush(now);
+    }
+
+    pub fn try_recv(&mut self) -> Option<Packet> {
+        let now = Instant::now();
+        self.flush(now);
+        while let Ok(packet) = self.receiver.try_recv() {
+            let droppable = packet.is_movement();
+            self.inbound.push(now, packet, droppable);
+        }
+        self.inbound.pop(now)
     }
 
-    pub fn try_recv(&self) -> Option<Packet> {
-        self.receiver.try_recv().ok()
+    /// Hands everything the simulated link has let through to the writer thread.
+    fn flush(&mut self, now: Instant) {
+        while let Some(sent) = self.outbound.pop(now) { let _ = self.sender.send(sent); }
     }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::biome::Biome;
+
 pub struct NoiseGenerator {
     permutation: [u8; 512],
 }
@@ -132,30 +134,17 @@ pub fn get_river_noise(&self, x: i32, z: i32) -> f64 {
         h as i32
     }
 
-#[allow(dead_code)]
-    pub fn get_biome_at(&self, x: i32, z: i32, y: i32) -> &'static str {
+/// Humidity noise for a column; pairs with the temperature from `get_height_params`.
+    pub fn get_humidity(&self, x: i32, z: i32) -> f32 {
+        self.get_noise_octaves(x as f64 * 0.01, 123.0, z as f64 * 0.01, 3) as f32
+    }
+
+    pub fn get_biome_at(&self, x: i32, z: i32, y: i32) -> Biome {
         let (cont, eros, _weird, temp) = self.get_height_params(x, z);
-        let humid = self.get_noise_octaves(x as f64 * 0.01, 44.0, z as f64 * 0.01, 3) as f32;
-        self.get_biome(cont, eros, temp, humid, y)
+        self.get_biome(cont, eros, temp, self.get_humidity(x, z), y)
     }
 
-pub fn get_biome(&self, cont: f32, eros: f32, temp: f32, humid: f32, y: i32) -> &'static str {
-        if y > 102 { return "peaks"; }
-        if cont < -0.25 { return if temp < -0.4 { "ice_ocean" } else { "ocean" }; }
-        
-        // EROSION BASED BIOMES
-        if eros < -0.5 { return "badlands"; }
-        if eros > 0.4 { return "plains"; }
-        
-        // TEMPERATURE / HUMIDITY GRID
-        if temp < -0.2 {
-            return if humid > 0.0 { "taiga" } else { "ice_plains" };
-        }
-        if temp > 0.3 {
-            return if humid < 0.0 { "desert" } else { "jungle" };
-        }
-        if humid > 0.45 { return "swamp"; }
-        
-        "forest"
+pub fn get_biome(&self, cont: f32, eros: f32, temp: f32, humid: f32, y: i32) -> Biome {
+        Biome::select(cont, eros, temp, humid, y)
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
+#[cfg(feature = "client")]
 use winit::keyboard::KeyCode;
-use glam::{Vec3, Mat4, Vec4Swizzles};
+use glam::{Vec3, Mat4};
 use crate::world::{World, BlockPos, BlockType};
 use serde::{Serialize, Deserialize};
 
@@ -249,6 +250,7 @@ bob_timer: 0.0,
         }
     }
     
+    #[cfg(feature = "client")]
     pub fn handle_input(&mut self, key: KeyCode, pressed: bool) {
         match key {
             KeyCode::KeyW => self.keys.forward = pressed, KeyCode::KeyS => self.keys.backward = pressed,
@@ -280,7 +282,8 @@ KeyCode::Digit9 => self.inventory.select_slot(8),
     }
     
 pub fn update(&mut self, world: &crate::world::World, dt: f32, audio: &crate::AudioSystem, in_cave: bool) {
-        if self.is_dead || self.inventory_open { return; }
+        // In stasis the player is held in place, e.g. while the server hasn't sent the ground yet
+        if self.is_dead || self.inventory_open || self.stasis { return; }
         
         // OPTIMIZED PHYSICS SUB-STEPPING: 4 steps for better performance
         let substeps = 4; // Reduced from 8 for better FPS
@@ -339,7 +342,7 @@ let feet_bp = BlockPos { x: self.position.x.floor() as i32, y: self.position.y.f
         }
 
         // 2. LAVA DAMAGE
-        if world.get_block(feet_bp) == BlockType::Lava || world.get_block(head_bp) == BlockType::Lava {
+        if world.get_block(feet_bp).is_lava() || world.get_block(head_bp).is_lava() {
             if self.invincible_timer <= 0.0 { self.health -= 4.0; self.invincible_timer = 0.5; }
             self.velocity.y *= 0.5; // Viscosity
         }
@@ -573,24 +576,8 @@ pub fn build_view_projection_matrix(&self, aspect: f32) -> [[f32; 4]; 4] {
 (correction * proj * view).to_cols_array_2d()
     }
 
+    #[cfg(feature = "client")]
     pub fn get_frustum_planes(&self, aspect: f32) -> [[f32; 4]; 6] {
-        let m = glam::Mat4::from_cols_array_2d(&self.build_view_projection_matrix(aspect));
-        let mut planes = [[0.0f32; 4]; 6];
-        // Extract planes and NORMALIZE them for accurate distance checks
-        let row4 = m.row(3);
-        let rows = [m.row(0), m.row(1), m.row(2)];
-
-        let p_raw = [
-            row4 + rows[0], row4 - rows[0], // Left, Right
-            row4 + rows[1], row4 - rows[1], // Bottom, Top
-            row4 + rows[2], row4 - rows[2], // Near, Far
-        ];
-
-        for i in 0..6 {
-            let p = p_raw[i];
-            let length = p.xyz().length();
-            planes[i] = (p / length).to_array();
-        }
-        planes
+        crate::gpu_cull::frustum_planes(glam::Mat4::from_cols_array_2d(&self.build_view_projection_matrix(aspect)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,183 @@
+//! Post-processing chain
+//!
+//! The world is rendered into an `HDR_FORMAT` target so emissive blocks can go brighter than
+//! white. `PostStack::run` then turns it into the frame: a half-resolution bright pass and
+//! separable blur for bloom, one composite pass (bloom, exposure, ACES tonemapping, gamma and the
+//! underwater tint) and, when enabled, FXAA. The UI is drawn afterwards, untouched.
+//!
+//! Every pass toggles through `PostSettings` without rebuilding anything; the passes themselves
+//! live in post.wgsl and can be recompiled at runtime with `reload`.
+
+use wgpu::util::DeviceExt;
+use wgpu::*;
+
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+const BLOOM_STRENGTH: f32 = 0.8;
+
+/// Which post passes run and how the picture is graded; filled from `GraphicsConfig`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostSettings {
+    pub fxaa: bool,
+    pub bloom: bool,
+    pub tonemap: bool,
+    pub exposure: f32,
+    pub gamma: f32,
+}
+
+impl Default for PostSettings {
+    fn default() -> Self {
+        Self { fxaa: true, bloom: true, tonemap: true, exposure: 1.0, gamma: 1.0 }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostParams { exposure: f32, gamma: f32, bloom: f32, tonemap: f32, underwater: f32, time: f32, _pad: [f32; 2] }
+
+struct Pipelines { bright: RenderPipeline, blur_h: RenderPipeline, blur_v: RenderPipeline, composite: RenderPipeline, fxaa: RenderPipeline }
+
+/// The intermediate targets for one frame size, and the bind group each pass reads through.
+struct Targets {
+    hdr: TextureView,
+    bright: BindGroup, // hdr -> bloom_a
+    blur_h: BindGroup, // bloom_a -> bloom_b
+    blur_v: BindGroup, // bloom_b -> bloom_a
+    composite: BindGroup, // hdr + bloom_a -> ldr, or the frame without FXAA
+    fxaa: BindGroup, // ldr -> frame
+    bloom_a: TextureView,
+    bloom_b: TextureView,
+    ldr: TextureView,
+}
+
+pub struct PostStack {
+    pub settings: PostSettings,
+    layout: BindGroupLayout,
+    pipeline_layout: PipelineLayout,
+    sampler: Sampler,
+    params: Buffer,
+    format: TextureFormat,
+    pipelines: Pipelines,
+    targets: Targets,
+}
+
+fn target(device: &Device, label: &str, width: u32, height: u32, format: TextureFormat) -> TextureView {
+    device.create_texture(&TextureDescriptor { label: Some(label), size: Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 }, mip_level_count: 1, sample_count: 1, dimension: TextureDimension::D2, format, usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING, view_formats: &[] })
+        .create_view(&TextureViewDescriptor::default())
+}
+
+fn create_pipelines(device: &Device, layout: &PipelineLayout, shader: &ShaderModule, format: TextureFormat) -> Pipelines {
+    let pass = |label: &str, entry_point: &str, format: TextureFormat| device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: VertexState { module: shader, entry_point: "vs_post", buffers: &[] },
+        fragment: Some(FragmentState { module: shader, entry_point, targets: &[Some(ColorTargetState { format, blend: None, write_mask: ColorWrites::ALL })] }),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+    });
+    Pipelines {
+        bright: pass("Bloom Bright Pass", "fs_bright", HDR_FORMAT),
+        blur_h: pass("Bloom Blur H", "fs_blur_h", HDR_FORMAT),
+        blur_v: pass("Bloom Blur V", "fs_blur_v", HDR_FORMAT),
+        composite: pass("Post Composite", "fs_composite", format),
+        fxaa: pass("FXAA", "fs_fxaa", format),
+    }
+}
+
+impl PostStack {
+    /// `format` is the frame's format, which the last pass writes.
+    pub fn new(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        let texture_entry = |binding| BindGroupLayoutEntry { binding, visibility: ShaderStages::FRAGMENT, ty: BindingType::Texture { sample_type: TextureSampleType::Float { filterable: true }, view_dimension: TextureViewDimension::D2, multisampled: false }, count: None };
+        let layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor { label: Some("post_layout"), entries: &[
+            texture_entry(0),
+            texture_entry(1),
+            BindGroupLayoutEntry { binding: 2, visibility: ShaderStages::FRAGMENT, ty: BindingType::Sampler(SamplerBindingType::Filtering), count: None },
+            BindGroupLayoutEntry { binding: 3, visibility: ShaderStages::FRAGMENT, ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None }, count: None },
+        ] });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("Post Layout"), bind_group_layouts: &[&layout], push_constant_ranges: &[] });
+        let sampler = device.create_sampler(&SamplerDescriptor { label: Some("post_sampler"), address_mode_u: AddressMode::ClampToEdge, address_mode_v: AddressMode::ClampToEdge, mag_filter: FilterMode::Linear, min_filter: FilterMode::Linear, ..Default::default() });
+        let params = device.create_buffer_init(&util::BufferInitDescriptor { label: Some("Post Params"), contents: &[0; std::mem::size_of::<PostParams>()], usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST });
+        let shader = device.create_shader_module(include_wgsl!("post.wgsl"));
+        let pipelines = create_pipelines(device, &pipeline_layout, &shader, format);
+        let targets = Self::create_targets(device, &layout, &sampler, &params, format, width, height);
+        Self { settings: PostSettings::default(), layout, pipeline_layout, sampler, params, format, pipelines, targets }
+    }
+
+    fn create_targets(device: &Device, layout: &BindGroupLayout, sampler: &Sampler, params: &Buffer, format: TextureFormat, width: u32, height: u32) -> Targets {
+        let hdr = target(device, "hdr", width, height, HDR_FORMAT);
+        let bloom_a = target(device, "bloom_a", width / 2, height / 2, HDR_FORMAT);
+        let bloom_b = target(device, "bloom_b", width / 2, height / 2, HDR_FORMAT);
+        let ldr = target(device, "ldr", width, height, format);
+        // The second texture is only read by the composite; other passes get any view they don't write
+        let bind = |label: &str, source: &TextureView, bloom: &TextureView| device.create_bind_group(&BindGroupDescriptor { label: Some(label), layout, entries: &[
+            BindGroupEntry { binding: 0, resource: BindingResource::TextureView(source) },
+            BindGroupEntry { binding: 1, resource: BindingResource::TextureView(bloom) },
+            BindGroupEntry { binding: 2, resource: BindingResource::Sampler(sampler) },
+            BindGroupEntry { binding: 3, resource: params.as_entire_binding() },
+        ] });
+        Targets {
+            bright: bind("post_bright", &hdr, &bloom_b),
+            blur_h: bind("post_blur_h", &bloom_a, &hdr),
+            blur_v: bind("post_blur_v", &bloom_b, &hdr),
+            composite: bind("post_composite", &hdr, &bloom_a),
+            fxaa: bind("post_fxaa", &ldr, &bloom_a),
+            hdr, bloom_a, bloom_b, ldr,
+        }
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.targets = Self::create_targets(device, &self.layout, &self.sampler, &self.params, self.format, width, height);
+    }
+
+    /// The HDR colour target the world is drawn into.
+    pub fn hdr_view(&self) -> &TextureView {
+        &self.targets.hdr
+    }
+
+    /// Recompiles the passes from WGSL `source`. On any compile or validation error the previous
+    /// passes stay in place and the error is returned.
+    pub fn reload(&mut self, device: &Device, source: &str) -> Result<(), String> {
+        device.push_error_scope(ErrorFilter::Validation);
+        let shader = device.create_shader_module(ShaderModuleDescriptor { label: Some("Post Shader"), source: ShaderSource::Wgsl(source.into()) });
+        let pipelines = create_pipelines(device, &self.pipeline_layout, &shader, self.format);
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+        self.pipelines = pipelines;
+        Ok(())
+    }
+
+    /// Runs the enabled passes from the HDR target into `frame`.
+    pub fn run(&self, encoder: &mut CommandEncoder, queue: &Queue, frame: &TextureView, underwater: bool, time: f32) {
+        let s = &self.settings;
+        let params = PostParams {
+            exposure: s.exposure, gamma: s.gamma.max(0.1), bloom: if s.bloom { BLOOM_STRENGTH } else { 0.0 }, tonemap: if s.tonemap { 1.0 } else { 0.0 },
+            underwater: if underwater { 1.0 } else { 0.0 }, time, _pad: [0.0; 2],
+        };
+        queue.write_buffer(&self.params, 0, bytemuck::cast_slice(&[params]));
+
+        let t = &self.targets;
+        let mut pass = |label: &str, pipeline: &RenderPipeline, bind_group: &BindGroup, target: &TextureView| {
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(RenderPassColorAttachment { view: target, resolve_target: None, ops: Operations { load: LoadOp::Clear(Color::BLACK), store: StoreOp::Store } })],
+                depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        };
+        if s.bloom {
+            pass("Bloom Bright Pass", &self.pipelines.bright, &t.bright, &t.bloom_a);
+            pass("Bloom Blur H", &self.pipelines.blur_h, &t.blur_h, &t.bloom_b);
+            pass("Bloom Blur V", &self.pipelines.blur_v, &t.blur_v, &t.bloom_a);
+        }
+        if s.fxaa {
+            pass("Post Composite", &self.pipelines.composite, &t.composite, &t.ldr);
+            pass("FXAA", &self.pipelines.fxaa, &t.fxaa, frame);
+        } else {
+            pass("Post Composite", &self.pipelines.composite, &t.composite, frame);
+        }
+    }
+}
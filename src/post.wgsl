@@ -0,0 +1,133 @@
+// Post-processing chain (post.rs). The world is drawn into an HDR target; these passes turn it into
+// the displayed frame: bloom from whatever is brighter than white (emissive blocks), then one
+// composite pass for exposure, ACES tonemapping, gamma and the underwater tint, then FXAA.
+// Every pass is a fullscreen triangle sampling the previous pass's output.
+
+struct PostParams {
+    exposure: f32,
+    gamma: f32,
+    bloom: f32, // Strength of the blurred highlights added back; 0 with bloom off
+    tonemap: f32, // 1.0 for ACES, 0.0 to just clamp
+    underwater: f32,
+    time: f32,
+    _pad0: f32,
+    _pad1: f32,
+};
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var t_bloom: texture_2d<f32>;
+@group(0) @binding(2)
+var s_linear: sampler;
+@group(0) @binding(3)
+var<uniform> params: PostParams;
+
+struct PostOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_post(@builtin(vertex_index) index: u32) -> PostOutput {
+    var out: PostOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn luma(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// --- BLOOM ---
+// Half resolution: the bilinear tap already averages 2x2 source pixels. Only the part of a pixel
+// above white survives, so lit terrain never blooms but lava, fire and torches do.
+@fragment
+fn fs_bright(in: PostOutput) -> @location(0) vec4<f32> {
+    let c = textureSample(t_source, s_linear, in.uv).rgb;
+    let l = luma(c);
+    return vec4<f32>(c * (max(l - 1.0, 0.0) / max(l, 0.0001)), 1.0);
+}
+
+// 9-tap Gaussian folded into 5 bilinear taps
+fn blur(uv: vec2<f32>, dir: vec2<f32>) -> vec4<f32> {
+    let step = dir / vec2<f32>(textureDimensions(t_source));
+    var c = textureSample(t_source, s_linear, uv).rgb * 0.227027;
+    c += textureSample(t_source, s_linear, uv + step * 1.384615).rgb * 0.316216;
+    c += textureSample(t_source, s_linear, uv - step * 1.384615).rgb * 0.316216;
+    c += textureSample(t_source, s_linear, uv + step * 3.230769).rgb * 0.070270;
+    c += textureSample(t_source, s_linear, uv - step * 3.230769).rgb * 0.070270;
+    return vec4<f32>(c, 1.0);
+}
+
+@fragment
+fn fs_blur_h(in: PostOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_v(in: PostOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+// --- COMPOSITE ---
+// Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_composite(in: PostOutput) -> @location(0) vec4<f32> {
+    // Being underwater adds a faint ripple
+    var uv = in.uv;
+    let sway = params.underwater * 0.002;
+    uv += vec2<f32>(sin(uv.y * 18.0 + params.time * 2.7), cos(uv.x * 14.0 + params.time * 2.1)) * sway;
+    uv = clamp(uv, vec2<f32>(0.0), vec2<f32>(1.0));
+
+    var c = textureSample(t_source, s_linear, uv).rgb;
+    c += textureSample(t_bloom, s_linear, uv).rgb * params.bloom;
+    if (params.underwater > 0.5) {
+        c = mix(c, vec3<f32>(0.0, 0.2, 0.8), 0.4);
+    }
+
+    c *= params.exposure;
+    c = select(clamp(c, vec3<f32>(0.0), vec3<f32>(1.0)), aces(c), params.tonemap > 0.5);
+    // The frame is sRGB, so this only bends the curve the player asked for on top of it
+    c = pow(c, vec3<f32>(1.0 / params.gamma));
+    return vec4<f32>(c, 1.0);
+}
+
+// --- FXAA ---
+// The compact FXAA: blur along the local edge direction, and fall back to the narrower blur
+// wherever the wide one would pull in colours from outside the neighbourhood's luma range.
+const FXAA_REDUCE_MIN: f32 = 0.0078125; // 1/128
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+@fragment
+fn fs_fxaa(in: PostOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_source));
+    let rgb_m = textureSample(t_source, s_linear, in.uv).rgb;
+    // Perceptual luma: the source is linear, edges are judged the way they're seen
+    let l_nw = sqrt(luma(textureSample(t_source, s_linear, in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb));
+    let l_ne = sqrt(luma(textureSample(t_source, s_linear, in.uv + vec2<f32>(1.0, -1.0) * texel).rgb));
+    let l_sw = sqrt(luma(textureSample(t_source, s_linear, in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb));
+    let l_se = sqrt(luma(textureSample(t_source, s_linear, in.uv + vec2<f32>(1.0, 1.0) * texel).rgb));
+    let l_m = sqrt(luma(rgb_m));
+    let l_min = min(l_m, min(min(l_nw, l_ne), min(l_sw, l_se)));
+    let l_max = max(l_m, max(max(l_nw, l_ne), max(l_sw, l_se)));
+
+    var dir = vec2<f32>(-((l_nw + l_ne) - (l_sw + l_se)), (l_nw + l_sw) - (l_ne + l_se));
+    let reduce = max((l_nw + l_ne + l_sw + l_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let rcp_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * rcp_min, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+    let rgb_a = 0.5 * (textureSample(t_source, s_linear, in.uv + dir * (1.0 / 3.0 - 0.5)).rgb
+        + textureSample(t_source, s_linear, in.uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+    let rgb_b = rgb_a * 0.5 + 0.25 * (textureSample(t_source, s_linear, in.uv - dir * 0.5).rgb
+        + textureSample(t_source, s_linear, in.uv + dir * 0.5).rgb);
+    let l_b = sqrt(luma(rgb_b));
+    return vec4<f32>(select(rgb_b, rgb_a, l_b < l_min || l_b > l_max), 1.0);
+}
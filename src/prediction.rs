@@ -0,0 +1,280 @@
+//! Client-side prediction, server reconciliation and remote interpolation
+//!
+//! On a dedicated server the client no longer reports where it is. Every fixed physics step
+//! becomes a sequenced `InputCommand` (keys, look direction, step length) that the client runs
+//! through `Player::update` straight away and also sends to the server, which runs the same
+//! command through the same physics. The server answers with `PlayerSnapshot`s naming the last
+//! command it applied; the client puts its player where the snapshot says and replays the
+//! commands the server hasn't seen yet, so a correction only changes anything when the two
+//! simulations disagreed.
+//!
+//! A listen host doesn't have terrain around its clients, so there players still send their
+//! positions. Either way, other players are drawn from an `InterpolationBuffer`: a short delay
+//! behind the newest update, blending between the two updates around that time instead of
+//! snapping to each one as it arrives.
+//!
+//! Where a player is is the server's business alone: it puts new players at spawn, moves them back
+//! there when a command says they respawned, and only runs as much input per session as real time
+//! has passed. Health stays the client's: the server's copy of a player only moves, and replaying
+//! commands never hurts the player twice. Flying, noclip and admin speed are the server's to grant
+//! too: a command reports them, but it runs with whatever the simulated player already has.
+
+use std::collections::{HashMap, VecDeque};
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::player::Player;
+use crate::world::{RemotePlayer, World};
+use crate::AudioSystem;
+
+/// How far behind the newest update other players are drawn, in seconds.
+pub const INTERPOLATION_DELAY: f64 = 0.1;
+/// Most commands in one `PlayerInput` packet. Every packet repeats all unacknowledged commands
+/// up to this many, so a lost packet is covered by the next one.
+pub const MAX_INPUT_BATCH: usize = 64;
+/// Commands kept for replay at most; beyond this the server is so far behind that the oldest ones
+/// no longer matter.
+const MAX_PENDING: usize = 1024;
+
+pub const KEY_FORWARD: u16 = 1 << 0;
+pub const KEY_BACKWARD: u16 = 1 << 1;
+pub const KEY_LEFT: u16 = 1 << 2;
+pub const KEY_RIGHT: u16 = 1 << 3;
+pub const KEY_UP: u16 = 1 << 4;
+pub const KEY_DOWN: u16 = 1 << 5;
+pub const KEY_JUMP: u16 = 1 << 6;
+pub const KEY_SPRINT: u16 = 1 << 7;
+pub const KEY_FLYING: u16 = 1 << 8;
+pub const KEY_NOCLIP: u16 = 1 << 9;
+
+/// One physics step of player input.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct InputCommand {
+    pub seq: u32,
+    pub dt: f32,
+    /// `KEY_*` bits.
+    pub keys: u16,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub admin_speed: f32,
+    /// The player died and came back since the previous command; the server moves it to spawn
+    /// before running this one.
+    pub respawn: bool,
+}
+
+impl InputCommand {
+    /// The input `player` is about to be simulated with.
+    pub fn capture(player: &Player, seq: u32, dt: f32, respawn: bool) -> Self {
+        let k = &player.keys;
+        let bits = [
+            (k.forward, KEY_FORWARD), (k.backward, KEY_BACKWARD), (k.left, KEY_LEFT), (k.right, KEY_RIGHT),
+            (k.up, KEY_UP), (k.down, KEY_DOWN), (k.jump_queued, KEY_JUMP),
+            (player.is_sprinting, KEY_SPRINT), (player.is_flying, KEY_FLYING), (player.is_noclip, KEY_NOCLIP),
+        ];
+        let keys = bits.iter().filter(|(held, _)| *held).fold(0, |keys, (_, bit)| keys | bit);
+        Self { seq, dt, keys, yaw: player.rotation.y, pitch: player.rotation.x, admin_speed: player.admin_speed, respawn }
+    }
+
+    /// Runs this command on `player` exactly as the client did when it predicted it. The
+    /// `KEY_FLYING` and `KEY_NOCLIP` bits and `admin_speed` are ignored: `player` keeps the
+    /// abilities it has been given. Moving a respawned player to spawn is up to the caller.
+    pub fn simulate(&self, player: &mut Player, world: &World, audio: &AudioSystem) {
+        let held = |bit| self.keys & bit != 0;
+        let k = &mut player.keys;
+        (k.forward, k.backward, k.left, k.right) = (held(KEY_FORWARD), held(KEY_BACKWARD), held(KEY_LEFT), held(KEY_RIGHT));
+        (k.up, k.down, k.jump_queued) = (held(KEY_UP), held(KEY_DOWN), held(KEY_JUMP));
+        player.is_sprinting = held(KEY_SPRINT);
+        player.rotation.y = self.yaw;
+        player.rotation.x = self.pitch;
+        player.update(world, self.dt, audio, false);
+    }
+
+    /// Whether the command is well-formed. Claimed flying, noclip or speed passes: `simulate`
+    /// ignores them rather than dropping the player's input.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.dt > 0.0 && self.dt <= 0.1) {
+            return Err("Input step length outside (0, 0.1] seconds".to_string());
+        }
+        if !self.yaw.is_finite() || !self.pitch.is_finite() || !(1.0..=10.0).contains(&self.admin_speed) {
+            return Err("Invalid look direction or speed".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Where the server has a player, and the last of that player's commands it applied.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PlayerSnapshot {
+    pub id: u32,
+    pub ack: u32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl PlayerSnapshot {
+    #[allow(dead_code)] // Only the dedicated server takes snapshots
+    pub fn of(id: u32, ack: u32, player: &Player) -> Self {
+        Self { id, ack, position: player.position, velocity: player.velocity, yaw: player.rotation.y, pitch: player.rotation.x, on_ground: player.on_ground }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id > 10000 {
+            return Err("Invalid player ID".to_string());
+        }
+        let finite = self.position.is_finite() && self.velocity.is_finite() && self.yaw.is_finite() && self.pitch.is_finite();
+        if !finite || self.position.abs().max_element() > 100000.0 {
+            return Err("Invalid player state (NaN or out of bounds)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The local player's side of the protocol: numbers and remembers commands until the server has
+/// applied them, and replays the rest on top of each snapshot.
+#[derive(Default)]
+pub struct Prediction {
+    next_seq: u32,
+    /// Simulated locally, not yet acknowledged by the server.
+    pending: VecDeque<InputCommand>,
+    last_sent: u32,
+    /// Set by `respawn` until the next command carries it.
+    respawned: bool,
+}
+
+impl Prediction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One fixed physics step of the local player, recorded for the server. A player that can't
+    /// move (dead, in stasis, in the inventory) sends nothing.
+    pub fn step(&mut self, player: &mut Player, world: &World, dt: f32, audio: &AudioSystem, in_cave: bool) {
+        if player.is_dead || player.inventory_open || player.stasis { return; }
+        self.next_seq += 1;
+        let command = InputCommand::capture(player, self.next_seq, dt, std::mem::take(&mut self.respawned));
+        player.update(world, dt, audio, in_cave);
+        self.pending.push_back(command);
+        if self.pending.len() > MAX_PENDING { self.pending.pop_front(); }
+    }
+
+    /// The local player came back from dying: the next command asks the server to put it at spawn.
+    pub fn respawn(&mut self) {
+        self.respawned = true;
+    }
+
+    /// The unacknowledged commands to send, or nothing if none were added since the last call.
+    pub fn outgoing(&mut self) -> Vec<InputCommand> {
+        if self.next_seq == self.last_sent { return Vec::new(); }
+        self.last_sent = self.next_seq;
+        let skip = self.pending.len().saturating_sub(MAX_INPUT_BATCH);
+        self.pending.iter().skip(skip).copied().collect()
+    }
+
+    /// Moves `player` to where `snapshot` has it and replays the commands applied since.
+    pub fn reconcile(&mut self, player: &mut Player, snapshot: &PlayerSnapshot, world: &World, audio: &AudioSystem) {
+        while self.pending.front().is_some_and(|c| c.seq <= snapshot.ack) { self.pending.pop_front(); }
+        if player.is_dead { return; }
+        if player.stasis {
+            // Nothing was simulated to replay; this is where the server put us (e.g. at spawn)
+            player.position = snapshot.position;
+            player.velocity = snapshot.velocity;
+            return;
+        }
+
+        // The replay re-runs physics on input that's already been lived through once: keep what
+        // the player is doing now, and don't let falls or lava hurt a second time
+        let (rotation, keys) = (player.rotation, std::mem::take(&mut player.keys));
+        let sprinting = player.is_sprinting;
+        let (health, air, invincible) = (player.health, player.air, player.invincible_timer);
+
+        player.position = snapshot.position;
+        player.velocity = snapshot.velocity;
+        player.on_ground = snapshot.on_ground;
+        for command in &self.pending { command.simulate(player, world, audio); }
+
+        player.rotation = rotation;
+        player.keys = keys;
+        player.is_sprinting = sprinting;
+        (player.health, player.air, player.invincible_timer, player.is_dead) = (health, air, invincible, false);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    time: f64,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+}
+
+/// Recent updates for one remote entity, by the time they arrived.
+#[derive(Default)]
+pub struct InterpolationBuffer {
+    samples: VecDeque<Sample>,
+}
+
+impl InterpolationBuffer {
+    pub fn push(&mut self, time: f64, position: Vec3, yaw: f32, pitch: f32) {
+        // Updates from a delayed link can arrive in a burst; keep time moving forward
+        let time = self.samples.back().map_or(time, |last| time.max(last.time));
+        self.samples.push_back(Sample { time, position, yaw, pitch });
+    }
+
+    /// Position, yaw and pitch at `time`: blended between the updates around it, held at the
+    /// oldest or newest one outside them. None before the first update.
+    pub fn sample(&self, time: f64) -> Option<(Vec3, f32, f32)> {
+        let after = self.samples.iter().position(|s| s.time > time);
+        let (a, b) = match after {
+            None => { let s = self.samples.back()?; return Some((s.position, s.yaw, s.pitch)); }
+            Some(0) => { let s = self.samples[0]; return Some((s.position, s.yaw, s.pitch)); }
+            Some(i) => (self.samples[i - 1], self.samples[i]),
+        };
+        let t = ((time - a.time) / (b.time - a.time)) as f32;
+        let turn = |from: f32, to: f32| from + ((to - from + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI) * t;
+        Some((a.position.lerp(b.position, t), turn(a.yaw, b.yaw), a.pitch + (b.pitch - a.pitch) * t))
+    }
+
+    /// Forgets updates no sample at or after `time` can need.
+    fn prune(&mut self, time: f64) {
+        while self.samples.len() > 1 && self.samples[1].time <= time { self.samples.pop_front(); }
+    }
+}
+
+/// Interpolation buffers for every other player, keyed by player id.
+#[derive(Default)]
+pub struct RemoteEntities {
+    buffers: HashMap<u32, InterpolationBuffer>,
+}
+
+impl RemoteEntities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an update for player `id` that arrived at `time` (seconds on any steady clock).
+    pub fn push(&mut self, id: u32, time: f64, position: Vec3, yaw: f32, pitch: f32) {
+        self.buffers.entry(id).or_default().push(time, position, yaw, pitch);
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.buffers.remove(&id);
+    }
+
+    /// Places every remote player where it was `INTERPOLATION_DELAY` before `time`.
+    pub fn apply(&mut self, time: f64, players: &mut Vec<RemotePlayer>) {
+        let time = time - INTERPOLATION_DELAY;
+        players.retain(|p| self.buffers.contains_key(&p.id));
+        for (&id, buffer) in &mut self.buffers {
+            buffer.prune(time);
+            let Some((position, rotation, pitch)) = buffer.sample(time) else { continue };
+            match players.iter_mut().find(|p| p.id == id) {
+                Some(p) => { p.position = position; p.rotation = rotation; p.pitch = pitch; }
+                None => players.push(RemotePlayer { id, position, rotation, pitch }),
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Framed wire protocol
+//!
+//! TCP is a byte stream: one `read` can return half a packet or three of them. Every `Packet` is
+//! therefore sent as a frame, a little-endian `u32` length followed by that many bytes: one
+//! protocol version byte and the bincode-encoded packet. `FrameDecoder` buffers whatever bytes
+//! arrive and hands back complete packets; `PacketReader` drives one from a blocking stream.
+//!
+//! A frame with the wrong version or a length over `MAX_FRAME` means the peer is incompatible or
+//! the stream is out of step. There is no way to find the next frame boundary after that, so the
+//! error is sticky and the connection should be dropped.
+
+use std::io::{self, Read, Write};
+
+use crate::network::Packet;
+
+/// Bump whenever `Packet` changes shape, so old and new builds refuse each other cleanly.
+pub const PROTOCOL_VERSION: u8 = 6; // 2: ServerInfo, 3: chunk columns, 4: player ids, 5: input commands, 6: server-side spawns
+/// Upper bound on version byte + payload. Generous enough for a compressed chunk column.
+pub const MAX_FRAME: usize = 1 << 20;
+const LENGTH_BYTES: usize = 4;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Encodes `packet` as one complete frame.
+pub fn encode_frame(packet: &Packet) -> io::Result<Vec<u8>> {
+    let payload = bincode::serialize(packet).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let length = payload.len() + 1;
+    if length > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("packet of {} bytes exceeds the {} byte frame limit", length, MAX_FRAME)));
+    }
+    let mut frame = Vec::with_capacity(LENGTH_BYTES + length);
+    frame.extend_from_slice(&(length as u32).to_le_bytes());
+    frame.push(PROTOCOL_VERSION);
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Writes `packet` as one frame. The frame goes out in a single `write_all`, so packets from
+/// different threads never interleave on a shared stream as long as each holds it for the call.
+pub fn write_packet(stream: &mut impl Write, packet: &Packet) -> io::Result<()> {
+    stream.write_all(&encode_frame(packet)?)?;
+    stream.flush()
+}
+
+/// Reassembles frames from arbitrarily split or coalesced chunks of the byte stream.
+#[derive(Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    /// Start of the first frame not handed out yet; consumed bytes are dropped lazily.
+    start: usize,
+    broken: Option<String>,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends bytes as they came off the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        if self.start > 0 && self.start >= self.buffer.len() / 2 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Bytes received but not yet returned as a packet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// The next complete packet, `Ok(None)` if more bytes are needed. Once a frame header is
+    /// invalid every later call returns the same error.
+    pub fn next_packet(&mut self) -> io::Result<Option<Packet>> {
+        if let Some(reason) = &self.broken {
+            return Err(invalid(reason.clone()));
+        }
+        let pending = &self.buffer[self.start..];
+        if pending.len() < LENGTH_BYTES + 1 {
+            return Ok(None);
+        }
+        let length = u32::from_le_bytes([pending[0], pending[1], pending[2], pending[3]]) as usize;
+        let version = pending[LENGTH_BYTES];
+        let fault = if length == 0 || length > MAX_FRAME {
+            Some(format!("frame length {} outside 1..={}", length, MAX_FRAME))
+        } else if version != PROTOCOL_VERSION {
+            Some(format!("peer speaks protocol version {}, expected {}", version, PROTOCOL_VERSION))
+        } else {
+            None
+        };
+        if let Some(reason) = fault {
+            self.broken = Some(reason.clone());
+            return Err(invalid(reason));
+        }
+        if pending.len() < LENGTH_BYTES + length {
+            return Ok(None);
+        }
+        let payload = &pending[LENGTH_BYTES + 1..LENGTH_BYTES + length];
+        self.start += LENGTH_BYTES + length;
+        bincode::deserialize(payload).map(Some).map_err(|e| invalid(format!("undecodable {} byte packet: {}", payload.len(), e)))
+    }
+}
+
+/// Reads whole packets from a blocking stream.
+pub struct PacketReader<R> {
+    stream: R,
+    decoder: FrameDecoder,
+    chunk: Vec<u8>,
+}
+
+impl<R: Read> PacketReader<R> {
+    pub fn new(stream: R) -> Self {
+        Self { stream, decoder: FrameDecoder::new(), chunk: vec![0; 8192] }
+    }
+
+    /// Blocks until a full packet has arrived. `Ok(None)` when the peer closed the connection
+    /// between frames; closing mid-frame is an `UnexpectedEof` error.
+    pub fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            if let Some(packet) = self.decoder.next_packet()? {
+                return Ok(Some(packet));
+            }
+            let n = match self.stream.read(&mut self.chunk) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if n == 0 {
+                return match self.decoder.buffered() {
+                    0 => Ok(None),
+                    left => Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("connection closed {} bytes into a frame", left))),
+                };
+            }
+            self.decoder.push(&self.chunk[..n]);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Region-file chunk persistence
+//!
+//! Chunks are grouped into region files of 32x32 columns (all 8 vertical sections of each
+//! column live in the same file). Every region file starts with a fixed header holding one
+//! offset/length/capacity entry per chunk slot, followed by the zlib-compressed chunk payloads.
+//! A rewritten chunk reuses its old slot when the new payload still fits, otherwise it is
+//! appended to the end of the file.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::world::{BlockType, Chunk, PalettedBlocks, CHUNK_SIZE_Y, WORLD_HEIGHT};
+
+pub const REGION_SIZE: i32 = 32;
+const SECTIONS: i32 = WORLD_HEIGHT / CHUNK_SIZE_Y as i32;
+const SLOT_COUNT: usize = (REGION_SIZE * REGION_SIZE * SECTIONS) as usize;
+const MAGIC: [u8; 4] = *b"MCRG";
+const VERSION: u32 = 2; // 2: paletted section payloads
+const ENTRY_BYTES: u64 = 12;
+const HEADER_BYTES: u64 = 8 + SLOT_COUNT as u64 * ENTRY_BYTES;
+const SECTOR_BYTES: u32 = 4096;
+
+#[derive(Clone, Copy, Default)]
+struct SlotEntry { offset: u32, len: u32, cap: u32 }
+
+
+struct RegionFile {
+    file: File,
+    table: Vec<SlotEntry>,
+}
+
+impl RegionFile {
+    fn open(path: &Path, create: bool) -> io::Result<Option<Self>> {
+        if !create && !path.exists() { return Ok(None); }
+        let mut file = OpenOptions::new().read(true).write(true).create(create).truncate(false).open(path)?;
+        let mut table = vec![SlotEntry::default(); SLOT_COUNT];
+
+        if file.metadata()?.len() < HEADER_BYTES {
+            // Fresh file: write an empty header so offsets never point into it
+            let mut header = Vec::with_capacity(HEADER_BYTES as usize);
+            header.extend_from_slice(&MAGIC);
+            header.extend_from_slice(&VERSION.to_le_bytes());
+            header.resize(HEADER_BYTES as usize, 0);
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&header)?;
+            return Ok(Some(Self { file, table }));
+        }
+
+        let mut header = vec![0u8; HEADER_BYTES as usize];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad region magic"));
+        }
+        let version = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        if version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported region version {}", version)));
+        }
+        for (i, entry) in table.iter_mut().enumerate() {
+            let base = 8 + i * ENTRY_BYTES as usize;
+            let word = |o: usize| u32::from_le_bytes([header[base + o], header[base + o + 1], header[base + o + 2], header[base + o + 3]]);
+            *entry = SlotEntry { offset: word(0), len: word(4), cap: word(8) };
+        }
+        Ok(Some(Self { file, table }))
+    }
+
+    fn read_slot(&mut self, slot: usize) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.table[slot];
+        if entry.len == 0 { return Ok(None); }
+        let mut buf = vec![0u8; entry.len as usize];
+        self.file.seek(SeekFrom::Start(entry.offset as u64))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(Some(buf))
+    }
+
+    fn write_slot(&mut self, slot: usize, payload: &[u8]) -> io::Result<()> {
+        let len = payload.len() as u32;
+        let mut entry = self.table[slot];
+        if entry.cap < len {
+            // Doesn't fit in the old slot: append a new sector-aligned block at the end
+            let end = self.file.seek(SeekFrom::End(0))?;
+            entry.offset = end as u32;
+            entry.cap = len.div_ceil(SECTOR_BYTES) * SECTOR_BYTES;
+            self.file.set_len(end + entry.cap as u64)?;
+        }
+        entry.len = len;
+        self.file.seek(SeekFrom::Start(entry.offset as u64))?;
+        self.file.write_all(payload)?;
+
+        let mut raw = [0u8; ENTRY_BYTES as usize];
+        raw[0..4].copy_from_slice(&entry.offset.to_le_bytes());
+        raw[4..8].copy_from_slice(&entry.len.to_le_bytes());
+        raw[8..12].copy_from_slice(&entry.cap.to_le_bytes());
+        self.file.seek(SeekFrom::Start(8 + slot as u64 * ENTRY_BYTES))?;
+        self.file.write_all(&raw)?;
+        self.table[slot] = entry;
+        Ok(())
+    }
+}
+
+/// On-disk chunk store for one world. Region files are opened lazily and their headers cached.
+pub struct RegionStore {
+    dir: PathBuf,
+    regions: Mutex<HashMap<(i32, i32), Option<RegionFile>>>,
+}
+
+impl RegionStore {
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, regions: Mutex::new(HashMap::new()) })
+    }
+
+    fn locate(cx: i32, cy: i32, cz: i32) -> Option<((i32, i32), usize)> {
+        if !(0..SECTIONS).contains(&cy) { return None; }
+        let rx = cx.div_euclid(REGION_SIZE);
+        let rz = cz.div_euclid(REGION_SIZE);
+        let lx = cx.rem_euclid(REGION_SIZE);
+        let lz = cz.rem_euclid(REGION_SIZE);
+        Some(((rx, rz), ((cy * REGION_SIZE + lz) * REGION_SIZE + lx) as usize))
+    }
+
+    fn region_path(&self, rx: i32, rz: i32) -> PathBuf { self.dir.join(format!("r.{}.{}.region", rx, rz)) }
+
+    /// Reads a chunk from disk. Returns `None` if it was never saved (or the data is unreadable).
+    pub fn load_chunk(&self, cx: i32, cy: i32, cz: i32) -> Option<Chunk> {
+        let (region, slot) = Self::locate(cx, cy, cz)?;
+        let mut regions = self.regions.lock().unwrap();
+        let file = regions.entry(region).or_insert_with(|| {
+            match RegionFile::open(&self.region_path(region.0, region.1), false) {
+                Ok(r) => r,
+                Err(e) => { log::warn!("[REGION] Failed to open region {:?}: {}", region, e); None }
+            }
+        }).as_mut()?;
+        match file.read_slot(slot) {
+            Ok(Some(data)) => match decode_chunk(&data) {
+                Ok(chunk) => Some(chunk),
+                Err(e) => { log::warn!("[REGION] Corrupt chunk {:?}: {}", (cx, cy, cz), e); None }
+            },
+            Ok(None) => None,
+            Err(e) => { log::warn!("[REGION] Failed to read chunk {:?}: {}", (cx, cy, cz), e); None }
+        }
+    }
+
+    pub fn save_chunk(&self, cx: i32, cy: i32, cz: i32, chunk: &Chunk) -> io::Result<()> {
+        let Some((region, slot)) = Self::locate(cx, cy, cz) else { return Ok(()); };
+        let payload = encode_chunk(chunk)?;
+        let mut regions = self.regions.lock().unwrap();
+        let entry = regions.entry(region).or_insert(None);
+        if entry.is_none() {
+            *entry = RegionFile::open(&self.region_path(region.0, region.1), true)?;
+        }
+        match entry.as_mut() {
+            Some(file) => file.write_slot(slot, &payload),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "region file could not be created")),
+        }
+    }
+
+    pub fn flush(&self) {
+        for file in self.regions.lock().unwrap().values_mut().flatten() {
+            let _ = file.file.sync_data();
+        }
+    }
+}
+
+/// A section's blocks in the compact on-disk format: the palette-compressed blocks, zlib'd. Light,
+/// biomes and flags aren't included; the receiver recomputes them.
+pub fn encode_chunk(chunk: &Chunk) -> io::Result<Vec<u8>> {
+    let mut blocks = chunk.blocks.clone();
+    blocks.compact();
+    let raw = bincode::serialize(&blocks).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(&raw)?;
+    encoder.finish()
+}
+
+/// Inverse of `encode_chunk`. The section comes back marked decorated and without biomes or light.
+pub fn decode_chunk(data: &[u8]) -> io::Result<Chunk> {
+    let mut raw = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut raw)?;
+    let blocks: PalettedBlocks = bincode::deserialize(&raw).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if !blocks.is_valid() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed block palette"));
+    }
+    let mut chunk = Chunk::new();
+    chunk.is_empty = blocks.is_uniform(BlockType::Air);
+    chunk.blocks = blocks;
+    chunk.decorated = true; // Saved sections already hold their features (and the player's edits to them)
+    Ok(chunk)
+}
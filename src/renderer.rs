@@ -3,9 +3,17 @@ use wgpu::*;
 use winit::window::Window;
 use std::time::Instant;
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
 use crossbeam_channel::{unbounded, Sender, Receiver};
 use crate::world::{World, BlockPos, BlockType};
+use crate::biome::Biome;
+use crate::chunk_snapshot::ChunkSnapshot;
+use crate::clouds::{CloudMode, CloudRenderer};
+use crate::entity_models::{EntityModels, ModelInstance, ModelKind, SKIN_PLAYER};
+use crate::gpu_cull::{self, ChunkCullData, GpuCuller, CHUNK_RADIUS};
+use crate::mesh_arena::{ArenaSlot, MeshArena};
+use crate::post::{PostSettings, PostStack, HDR_FORMAT};
+use crate::shadows::{ShadowMaps, ViewFrustum};
+use crate::weather_system::WeatherSystem;
 use crate::player::Player;
 use crate::MainMenu;
 use std::fs::File;
@@ -17,7 +25,20 @@ pub struct Particle { pub pos: glam::Vec3, pub vel: glam::Vec3, pub life: f32, p
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Vertex { pub position: [f32; 3], pub tex_coords: [f32; 2], pub ao: f32, pub tex_index: u32, pub light: f32 }
+pub struct Vertex { pub position: [f32; 3], pub tex_coords: [f32; 2], pub ao: f32, pub tex_index: u32, pub light: f32, pub tint: [u8; 4] }
+/// Vertex colour multiplier that leaves the texture as painted.
+pub const NO_TINT: [u8; 4] = [255; 4];
+
+/// Biome colour for a block face: grass tops and oak leaves follow their column's biome.
+fn face_tint(block: BlockType, face: usize, biome: Biome) -> [u8; 4] {
+    let [r, g, b] = match block {
+        BlockType::Grass if face == 0 => biome.def().grass_tint,
+        BlockType::Leaves => biome.def().foliage_tint,
+        _ => return NO_TINT,
+    };
+    [r, g, b, 255]
+}
+
 impl Vertex {
     pub fn desc() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
@@ -27,24 +48,102 @@ impl Vertex {
                 VertexAttribute { offset: 12, shader_location: 1, format: VertexFormat::Float32x2 }, 
                 VertexAttribute { offset: 20, shader_location: 2, format: VertexFormat::Float32 }, 
                 VertexAttribute { offset: 24, shader_location: 3, format: VertexFormat::Uint32 },
-                VertexAttribute { offset: 28, shader_location: 4, format: VertexFormat::Float32 }
+                VertexAttribute { offset: 28, shader_location: 4, format: VertexFormat::Float32 },
+                VertexAttribute { offset: 32, shader_location: 5, format: VertexFormat::Unorm8x4 }
             ],
         }
     }
 }
 
-#[allow(dead_code)]
-pub struct TextureRange {
-    pub _tex_index: u32,
-    pub _index_start: u32,
-    pub _index_count: u32,
+/// Which pass a face is drawn in: opaque first, then alpha-tested cutout (leaves, plants), then
+/// blended translucent (water, glass, ice) back to front without depth writes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderLayer { Opaque, Cutout, Translucent }
+
+impl RenderLayer {
+    pub fn of(block: BlockType) -> Self {
+        if block.is_liquid() || matches!(block, BlockType::Glass | BlockType::Ice) { RenderLayer::Translucent }
+        else if block.is_transparent() { RenderLayer::Cutout }
+        else { RenderLayer::Opaque }
+    }
+}
+
+/// Whether `neighbor` hides the face of `blk` that touches it. Opaque cubes hide everything and
+/// translucent blocks hide their own kind (no walls inside a pane of glass), but cutout blocks
+/// hide nothing, so you can see leaves through leaves.
+fn hides_face(neighbor: BlockType, blk: BlockType) -> bool {
+    (neighbor.is_solid() && !neighbor.is_transparent()) || (neighbor == blk && RenderLayer::of(blk) == RenderLayer::Translucent)
+}
+
+/// Vertex `ao` for each ambient occlusion level, from fully enclosed (0) to open (3).
+const AO_CURVE: [f32; 4] = [0.45, 0.65, 0.82, 1.0];
+
+/// Shading of one face corner: its ambient occlusion level and the light averaged over the cells
+/// around it. Greedy merging only joins faces whose four corners all match.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct CornerShade { ao: u8, light: f32 }
+
+fn occludes(block: BlockType) -> bool { block.is_solid() && !block.is_transparent() }
+
+/// Unit quad of face `face_id` with its corner at `(x, y, z)`, `w` by `h` blocks and `s` thick,
+/// in the mesher's CCW vertex order, with UVs that tile once per block.
+fn face_quad(face_id: usize, (x, y, z): (f32, f32, f32), w: f32, h: f32, s: f32) -> ([[f32; 3]; 4], [[f32; 2]; 4]) {
+    match face_id {
+        0 => ([[x, y+s, z+h], [x+w, y+s, z+h], [x+w, y+s, z], [x, y+s, z]], [[0.0, h], [w, h], [w, 0.0], [0.0, 0.0]]),
+        1 => ([[x, y, z], [x+w, y, z], [x+w, y, z+h], [x, y, z+h]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]),
+        2 => ([[x+s, y, z], [x+s, y+w, z], [x+s, y+w, z+h], [x+s, y, z+h]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]),
+        3 => ([[x, y, z+h], [x, y+w, z+h], [x, y+w, z], [x, y, z]], [[0.0, h], [w, h], [w, 0.0], [0.0, 0.0]]),
+        4 => ([[x, y, z+s], [x+w, y, z+s], [x+w, y+h, z+s], [x, y+h, z+s]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]),
+        5 => ([[x+w, y, z], [x, y, z], [x, y+h, z], [x+w, y+h, z]], [[w, 0.0], [0.0, 0.0], [0.0, h], [w, h]]),
+        _ => ([[0.0; 3]; 4], [[0.0; 2]; 4]),
+    }
+}
+
+/// Per-vertex shading of the full-detail face `face_id` whose block looks into cell `n`. Each
+/// corner checks the two edge cells and the diagonal cell beside it in `n`'s layer: two edges
+/// close it off completely, otherwise every occluder costs one AO level. Its light is the average
+/// of `n` and whichever of those three cells let light through.
+fn corner_shades(snap: &ChunkSnapshot, n: [i32; 3], face_id: usize) -> [CornerShade; 4] {
+    let normal_axis = match face_id { 0 | 1 => 1, 2 | 3 => 0, _ => 2 };
+    let (ta, tb) = match normal_axis { 0 => (1, 2), 1 => (0, 2), _ => (0, 1) };
+    let (unit, _) = face_quad(face_id, (0.0, 0.0, 0.0), 1.0, 1.0, 1.0);
+    let cell = |offsets: &[(usize, i32)]| {
+        let mut c = n;
+        for &(axis, d) in offsets { c[axis] += d; }
+        (snap.block(c[0], c[1], c[2]), snap.light(c[0], c[1], c[2]))
+    };
+    std::array::from_fn(|k| {
+        let (da, db) = (if unit[k][ta] > 0.5 { 1 } else { -1 }, if unit[k][tb] > 0.5 { 1 } else { -1 });
+        let (side_a, side_b, diag) = (cell(&[(ta, da)]), cell(&[(tb, db)]), cell(&[(ta, da), (tb, db)]));
+        let (oa, ob) = (occludes(side_a.0), occludes(side_b.0));
+        let od = occludes(diag.0) || (oa && ob);
+        let ao = if oa && ob { 0 } else { 3 - oa as u8 - ob as u8 - od as u8 };
+        let (mut sum, mut count) = (cell(&[]).1 as u32, 1);
+        for (open, light) in [(!oa, side_a.1), (!ob, side_b.1), (!od, diag.1)] {
+            if open { sum += light as u32; count += 1; }
+        }
+        CornerShade { ao, light: sum as f32 / count as f32 }
+    })
+}
+
+fn quad_indices(base: u32) -> [u32; 6] { [base, base + 1, base + 2, base, base + 2, base + 3] }
+
+/// A translucent face kept as a quad so it can be re-sorted when the camera moves.
+#[derive(Clone, Copy, Debug)]
+pub struct TranslucentQuad { pub center: [f32; 3], pub first_vertex: u32 }
+
+/// Translucent quads ordered back to front as seen from `eye`, as an index list.
+pub fn sorted_translucent_indices(quads: &[TranslucentQuad], eye: glam::Vec3) -> Vec<u32> {
+    let mut order: Vec<(f32, u32)> = quads.iter().map(|q| (eye.distance_squared(glam::Vec3::from(q.center)), q.first_vertex)).collect();
+    order.sort_by(|a, b| b.0.total_cmp(&a.0));
+    order.into_iter().flat_map(|(_, v)| quad_indices(v)).collect()
 }
 
 pub struct ChunkMesh { 
-    pub vertex_buffer: Buffer, 
-    pub index_buffer: Buffer, 
-    pub _ranges: Vec<TextureRange>,
-    pub total_indices: u32 
+    pub slot: ArenaSlot, // Vertices and indices in the shared mesh arena
+    pub layers: [std::ops::Range<u32>; 3], // Index range of each RenderLayer, relative to the slot's first index
+    translucent: Vec<TranslucentQuad>,
+    sorted_from: glam::Vec3, // Eye position the translucent range was last sorted for
 }
 
 pub struct MeshTask {
@@ -53,20 +152,65 @@ pub struct MeshTask {
     pub cz: i32,
     pub lod: u32,
     pub vertices: Vec<Vertex>,
-    pub indices: Vec<u32>,
-    pub ranges: Vec<TextureRange>,
+    pub opaque: Vec<u32>,
+    pub cutout: Vec<u32>,
+    pub translucent: Vec<TranslucentQuad>,
+}
+
+impl MeshTask {
+    fn new((cx, cy, cz): (i32, i32, i32), lod: u32) -> Self {
+        Self { cx, cy, cz, lod, vertices: Vec::new(), opaque: Vec::new(), cutout: Vec::new(), translucent: Vec::new() }
+    }
+
+    fn push_quad(&mut self, layer: RenderLayer, mut quad: [Vertex; 4]) {
+        // Split along the darker diagonal so a single occluded corner shades both triangles
+        // evenly instead of leaving a hard crease across the face
+        let shade = |v: &Vertex| v.ao * v.light;
+        if shade(&quad[0]) + shade(&quad[2]) > shade(&quad[1]) + shade(&quad[3]) { quad.rotate_left(1); }
+        let base = self.vertices.len() as u32;
+        match layer {
+            RenderLayer::Opaque => self.opaque.extend_from_slice(&quad_indices(base)),
+            RenderLayer::Cutout => self.cutout.extend_from_slice(&quad_indices(base)),
+            RenderLayer::Translucent => {
+                let c = quad.iter().fold(glam::Vec3::ZERO, |acc, v| acc + glam::Vec3::from(v.position)) / 4.0;
+                self.translucent.push(TranslucentQuad { center: c.to_array(), first_vertex: base });
+            }
+        }
+        self.vertices.extend_from_slice(&quad);
+    }
+}
+
+/// Offscreen colour target matching `config`, and the bind group that samples it for the blit.
+fn create_frame(device: &Device, config: &SurfaceConfiguration, layout: &BindGroupLayout, sampler: &Sampler) -> (Texture, BindGroup) {
+    let frame = device.create_texture(&TextureDescriptor { label: Some("frame"), size: Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 }, mip_level_count: 1, sample_count: 1, dimension: TextureDimension::D2, format: config.format, usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_SRC, view_formats: &[] });
+    let view = frame.create_view(&TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&BindGroupDescriptor { label: Some("frame_bind"), layout, entries: &[BindGroupEntry { binding: 0, resource: BindingResource::TextureView(&view) }, BindGroupEntry { binding: 1, resource: BindingResource::Sampler(sampler) }] });
+    (frame, bind_group)
 }
 
 pub struct Renderer<'a> {
     pub particles: Vec<Particle>,
-    surface: Surface<'a>, device: Device, queue: Queue, pub config: SurfaceConfiguration,
+    surface: Option<Surface<'a>>, device: Device, queue: Queue, pub config: SurfaceConfiguration,
+    // Everything is drawn into `frame` so it can be read back; with a window it is then blitted to the swapchain
+    frame: Texture, frame_bind_group: BindGroup, frame_sampler: Sampler, texture_layout: BindGroupLayout, blit_pipeline: RenderPipeline,
     pipeline: RenderPipeline, ui_pipeline: RenderPipeline,
+    cutout_pipeline: RenderPipeline, translucent_pipeline: RenderPipeline,
+    eye: glam::Vec3, // Camera position from the last update_camera, for sorting translucent faces
+    forward: glam::Vec3, // Camera direction from the last update_camera, for fitting shadow cascades
+    shadows: ShadowMaps,
+    shadow_passes: u32, // Cascades the last frame drew depth for: 0 with shadows off or the sun down
+    day_time: f32, // Where the day/night cycle is (0..1, 0 = sunrise), as the game last set it
     depth_texture: TextureView, bind_group: BindGroup,
     camera_buffer: Buffer, camera_bind_group: BindGroup,
     time_buffer: Buffer, time_bind_group: BindGroup,
 pub start_time: Instant, 
+    frozen_time: Option<f32>, // Set by freeze_time for reproducible frames
     pub chunk_meshes: HashMap<(i32, i32, i32), (ChunkMesh, u32)>, // (x, y, z) -> (Mesh, LOD_Level)
-    entity_vertex_buffer: Buffer, entity_index_buffer: Buffer,
+    entity_vertex_buffer: Buffer, entity_index_buffer: Buffer, // Block-breaking overlay
+    models: EntityModels,
+    clouds: CloudRenderer,
+    post: PostStack, // The world is drawn into its HDR target, then graded into `frame` before the UI
+    queued_models: Vec<ModelInstance>, // From submit_models, drawn by the next frame
     pub break_progress: f32,
     
     // FPS TRACKING
@@ -76,16 +220,14 @@ pub start_time: Instant,
     last_player_chunk: (i32, i32, i32),
 
     // DIABOLICAL THREADING
-    pub mesh_tx: Sender<(i32, i32, i32, u32, Arc<World>)>,
+    pub mesh_tx: Sender<(ChunkSnapshot, u32)>,
     mesh_rx: Receiver<MeshTask>,
     pub pending_chunks: HashSet<(i32, i32, i32)>,
 
     // DIABOLICAL GPU CULLING FIELDS
-    #[allow(dead_code)] compute_pipeline: ComputePipeline,
-    #[allow(dead_code)] chunk_data_buffer: Buffer,     
-    #[allow(dead_code)] indirect_draw_buffer: Buffer,   
-    #[allow(dead_code)] indirect_count_buffer: Buffer,  
-    #[allow(dead_code)] cull_bind_group: BindGroup,
+    arena: MeshArena,
+    culler: GpuCuller,
+    pub gpu_culling: bool, // Off when the adapter can't multi-draw-indirect; chunks are then culled on the CPU
 
     // LOADING SCREEN STATE
     pub loading_progress: f32,
@@ -100,24 +242,212 @@ pub start_time: Instant,
     pub fov: f32,
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct ChunkCullData {
-    pos: [f32; 4], // x, y, z, radius
-    index_count: u32,
-    base_vertex: i32,
-    base_index: u32,
-    _pad: u32,
+/// Water and lava are meshed per block rather than greedily: the surface height comes from each
+/// block's fluid level, and a column of the same fluid is drawn full height up to its top block.
+fn push_fluid_faces(snap: &ChunkSnapshot, out: &mut MeshTask) {
+    let (cx, cy, cz) = snap.key;
+    let same_fluid = |a: BlockType, b: BlockType| (a.is_water() && b.is_water()) || (a.is_lava() && b.is_lava());
+    for ly in 0..16i32 { for lz in 0..16i32 { for lx in 0..16i32 {
+        let blk = snap.block(lx, ly, lz);
+        if !blk.is_liquid() { continue; }
+        let (x, y, z) = ((cx * 16 + lx) as f32, (cy * 16 + ly) as f32, (cz * 16 + lz) as f32);
+        let look = |dx: i32, dy: i32, dz: i32| (snap.block(lx + dx, ly + dy, lz + dz), snap.light(lx + dx, ly + dy, lz + dz));
+        let (above, _) = look(0, 1, 0);
+        let t = if same_fluid(blk, above) { 1.0 } else { blk.get_fluid_height() };
+        for face in 0..6 {
+            let (dx, dy, dz) = match face { 0 => (0, 1, 0), 1 => (0, -1, 0), 2 => (1, 0, 0), 3 => (-1, 0, 0), 4 => (0, 0, 1), _ => (0, 0, -1) };
+            let (neighbor, light) = look(dx, dy, dz);
+            if same_fluid(blk, neighbor) || hides_face(neighbor, blk) { continue; }
+            // Same CCW winding as the chunk mesher, with the top lowered to the surface height
+            let positions = match face {
+                0 => [[x, y + t, z + 1.0], [x + 1.0, y + t, z + 1.0], [x + 1.0, y + t, z], [x, y + t, z]],
+                1 => [[x, y, z], [x + 1.0, y, z], [x + 1.0, y, z + 1.0], [x, y, z + 1.0]],
+                2 => [[x + 1.0, y, z], [x + 1.0, y + t, z], [x + 1.0, y + t, z + 1.0], [x + 1.0, y, z + 1.0]],
+                3 => [[x, y, z + 1.0], [x, y + t, z + 1.0], [x, y + t, z], [x, y, z]],
+                4 => [[x, y, z + 1.0], [x + 1.0, y, z + 1.0], [x + 1.0, y + t, z + 1.0], [x, y + t, z + 1.0]],
+                _ => [[x + 1.0, y, z], [x, y, z], [x, y + t, z], [x + 1.0, y + t, z]],
+            };
+            let uv = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+            let tex_index = match face { 0 => blk.get_texture_top(), 1 => blk.get_texture_bottom(), _ => blk.get_texture_side() };
+            let light = light as f32;
+            out.push_quad(RenderLayer::Translucent, std::array::from_fn(|k| Vertex { position: positions[k], tex_coords: uv[k], ao: 1.0, tex_index, light, tint: NO_TINT }));
+        }
+    }}}
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct DrawIndexedIndirect {
-    index_count: u32,
-    instance_count: u32,
-    first_index: u32,
-    base_vertex: i32,
-    first_instance: u32,
+/// Flowers, grass, saplings and crops are two crossed quads through the block, drawn double-sided
+/// in the cutout pass.
+fn push_cross_faces(snap: &ChunkSnapshot, out: &mut MeshTask) {
+    let (cx, cy, cz) = snap.key;
+    for ly in 0..16i32 { for lz in 0..16i32 { for lx in 0..16i32 {
+        let blk = snap.block(lx, ly, lz);
+        if !blk.is_cross_model() { continue; }
+        let (x, y, z) = ((cx * 16 + lx) as f32, (cy * 16 + ly) as f32, (cz * 16 + lz) as f32);
+        let tint = if blk == BlockType::TallGrass { face_tint(BlockType::Grass, 0, snap.biome(lx as usize, lz as usize)) } else { NO_TINT };
+        let (tex_index, light) = (blk.get_texture_side(), snap.light(lx, ly, lz) as f32);
+        let uv = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+        for (x0, z0, x1, z1) in [(x, z, x + 1.0, z + 1.0), (x + 1.0, z, x, z + 1.0)] {
+            let positions = [[x0, y, z0], [x1, y, z1], [x1, y + 1.0, z1], [x0, y + 1.0, z0]];
+            out.push_quad(RenderLayer::Cutout, std::array::from_fn(|k| Vertex { position: positions[k], tex_coords: uv[k], ao: 1.0, tex_index, light, tint }));
+        }
+    }}}
+}
+
+/// Outer radius, in chunks, of each LOD ring: full detail inside the first, 2x downsampled inside
+/// the second, 4x beyond.
+pub const LOD_RINGS: [f32; 2] = [8.0, 16.0];
+/// How far past a ring edge (in chunks) a mesh has to be before it switches level, so chunks
+/// sitting on an edge don't get remeshed back and forth as the player moves along it.
+const LOD_HYSTERESIS: f32 = 1.5;
+
+/// Vertical field of view of the world camera, in degrees.
+const FOV_Y_DEGREES: f32 = 75.0;
+/// Near plane of the world camera, in blocks.
+const NEAR_PLANE: f32 = 0.1;
+
+/// Chunks whose centre is within this many blocks of the camera keep their translucent faces sorted.
+const TRANSLUCENT_RESORT_RANGE: f32 = 48.0;
+
+/// LOD for a chunk `dist` chunks away whose current mesh (if any) is at `current`.
+pub fn lod_for_distance(dist: f32, current: Option<u32>) -> u32 {
+    if let Some(c) = current {
+        let inner = if c == 0 { f32::NEG_INFINITY } else { LOD_RINGS[c as usize - 1] - LOD_HYSTERESIS };
+        let outer = LOD_RINGS.get(c as usize).map_or(f32::INFINITY, |r| r + LOD_HYSTERESIS);
+        if (inner..=outer).contains(&dist) { return c; }
+    }
+    LOD_RINGS.iter().filter(|&&r| dist > r).count() as u32
+}
+
+/// LOD for section `key` seen from the player's chunk, given the LOD its current mesh (if any) has.
+/// Only the horizontal distance counts, so a column's sections share a level.
+pub fn wanted_lod(player_chunk: (i32, i32, i32), key: (i32, i32, i32), current: Option<u32>) -> u32 {
+    let (dx, dz) = ((key.0 - player_chunk.0) as f32, (key.2 - player_chunk.2) as f32);
+    lod_for_distance((dx * dx + dz * dz).sqrt(), current)
+}
+
+/// Block standing in for the `step`-sized cell with lowest corner (x, y, z): the first solid block
+/// scanning down from the cell's top layer, so distant terrain keeps its surface blocks, else the
+/// cell's fluid as a source block, else air. At step 1 it's just the block.
+fn lod_cell(snap: &ChunkSnapshot, (x, y, z): (i32, i32, i32), step: i32) -> BlockType {
+    if step == 1 { return snap.block(x, y, z); }
+    let mut fluid = BlockType::Air;
+    for dy in (0..step).rev() { for dz in 0..step { for dx in 0..step {
+        let b = snap.block(x + dx, y + dy, z + dz);
+        if b.is_solid() { return b; }
+        if fluid == BlockType::Air && b.is_liquid() { fluid = if b.is_water() { BlockType::Water } else { BlockType::Lava }; }
+    }}}
+    fluid
+}
+
+/// Greedy-meshes one section from its snapshot at `1 << lod` blocks per cell. Runs on the mesh
+/// worker threads.
+pub fn mesh_chunk(snap: &ChunkSnapshot, lod: u32) -> MeshTask {
+    let (cx, cy, cz) = snap.key;
+    let mut out = MeshTask::new(snap.key, lod);
+    if snap.is_empty { return out; }
+
+    let bx = (cx * 16) as f32;
+    let by = (cy * 16) as f32;
+    let bz = (cz * 16) as f32;
+    let step = 1 << lod;
+
+    for axis in 0..3 {
+        for d in (0..16usize).step_by(step as usize) {
+            for dir in 0..2 {
+                let face_id = match axis { 
+                    0 => if dir==0 {0} else {1},
+                    1 => if dir==0 {2} else {3},
+                    _ => if dir==0 {4} else {5}
+                };
+
+                let mask_dim = 16 / step as usize;
+                // (block, corner shading, biome tint): faces only merge when all match
+                let flat = [CornerShade { ao: 3, light: 0.0 }; 4];
+                let mut mask = vec![(BlockType::Air, flat, NO_TINT); mask_dim * mask_dim];
+
+                for u_m in 0..mask_dim {
+                    for v_m in 0..mask_dim {
+                        let u = u_m * step as usize;
+                        let v = v_m * step as usize;
+                        let (lx, ly, lz) = match axis {
+                            0 => (u, d, v), 1 => (d, u, v), _ => (u, v, d)
+                        };
+
+                        let blk = lod_cell(snap, (lx as i32, ly as i32, lz as i32), step);
+                        // Full-detail liquids get their own per-block mesh below; coarse ones are plain cells
+                        if !(blk.is_solid() || (step > 1 && blk.is_liquid())) { continue; }
+
+                        let (nx, ny, nz) = match face_id { 
+                            0 => (lx as i32, ly as i32 + step as i32, lz as i32), 
+                            1 => (lx as i32, ly as i32 - 1, lz as i32), 
+                            2 => (lx as i32 + step as i32, ly as i32, lz as i32), 
+                            3 => (lx as i32 - 1, ly as i32, lz as i32), 
+                            4 => (lx as i32, ly as i32, lz as i32 + step as i32), 
+                            5 => (lx as i32, ly as i32, lz as i32 - 1), 
+                            _ => (0, 0, 0)
+                        };
+
+                        // Neighbouring cell at this LOD (the adjacent block at full detail)
+                        let (ncx, ncy, ncz) = match face_id { 1 => (nx, ny + 1 - step, nz), 3 => (nx + 1 - step, ny, nz), 5 => (nx, ny, nz + 1 - step), _ => (nx, ny, nz) };
+
+                        // Faces on the section border are always emitted, only their light comes from the neighbour.
+                        // At coarse LODs these border walls double as skirts hiding the seams against other levels.
+                        let inside = (0..16).contains(&ncx) && (0..16).contains(&ncy) && (0..16).contains(&ncz);
+                        let neighbor = if inside { lod_cell(snap, (ncx, ncy, ncz), step) } else { BlockType::Air };
+
+                        let visible = if blk.is_liquid() { !hides_face(neighbor, blk) && !neighbor.is_liquid() } else { !hides_face(neighbor, blk) };
+                        if visible { 
+                            // Coarse cells are too far away for corner shading to show; they keep one flat light
+                            let corners = if step == 1 { corner_shades(snap, [nx, ny, nz], face_id) }
+                                else { [CornerShade { ao: 3, light: snap.light(nx, ny, nz) as f32 }; 4] };
+                            mask[v_m * mask_dim + u_m] = (blk, corners, face_tint(blk, face_id, snap.biome(lx, lz))); 
+                        }
+                    }
+                }
+
+                let mut n = 0;
+                while n < mask.len() {
+                    let cell = mask[n];
+                    let (blk, corners, tint) = cell;
+                    if blk != BlockType::Air {
+                        let mut w = 1;
+                        while (n + w) % mask_dim != 0 && mask[n + w] == cell { w += 1; }
+                        let mut h = 1;
+                        'h_loop: while (n / mask_dim + h) < mask_dim {
+                            for k in 0..w { if mask[n + k + h * mask_dim] != cell { break 'h_loop; } }
+                            h += 1;
+                        }
+
+                        let u_g = (n % mask_dim) as f32 * step as f32;
+                        let v_g = (n / mask_dim) as f32 * step as f32;
+                        let world_w = w as f32 * step as f32;
+                        let world_h = h as f32 * step as f32;
+                        let d_f = d as f32;
+                        let s_f = step as f32;
+
+                        let (wx, wy, wz) = match axis {
+                            0 => (bx + u_g, by + d_f, bz + v_g),
+                            1 => (bx + d_f, by + u_g, bz + v_g),
+                            _ => (bx + u_g, by + v_g, bz + d_f)
+                        };
+
+                        let tex_index = match face_id { 0 => blk.get_texture_top(), 1 => blk.get_texture_bottom(), _ => blk.get_texture_side() };
+
+                        let (positions, uv) = face_quad(face_id, (wx, wy, wz), world_w, world_h, s_f);
+                        out.push_quad(RenderLayer::of(blk), std::array::from_fn(|k| Vertex { position: positions[k], tex_coords: uv[k], ao: AO_CURVE[corners[k].ao as usize], tex_index, light: corners[k].light, tint }));
+
+                        for l in 0..h { for k in 0..w { mask[n + k + l * mask_dim] = (BlockType::Air, flat, NO_TINT); } }
+                    }
+                    n += 1;
+                }
+            }
+        }
+    }
+    if lod == 0 {
+        push_fluid_faces(snap, &mut out);
+        push_cross_faces(snap, &mut out);
+    }
+    out
 }
 
 impl<'a> Renderer<'a> {
@@ -136,7 +466,7 @@ impl<'a> Renderer<'a> {
         
         let forward = glam::Vec3::new(yaw_cos * pitch_cos, pitch_sin, yaw_sin * pitch_cos).normalize();
         let view = glam::Mat4::look_at_rh(eye_pos, eye_pos + forward, glam::Vec3::Y);
-        let proj = glam::Mat4::perspective_rh(75.0f32.to_radians(), aspect, 0.1, 512.0);
+        let proj = glam::Mat4::perspective_rh(FOV_Y_DEGREES.to_radians(), aspect, NEAR_PLANE, 512.0);
         
         let correction = glam::Mat4::from_cols_array(&[
             1.0, 0.0, 0.0, 0.0,
@@ -146,12 +476,33 @@ impl<'a> Renderer<'a> {
         ]);
         
         let view_proj = (correction * proj * view).to_cols_array_2d();
+        self.eye = eye_pos;
+        self.forward = forward;
         self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[view_proj]));
     }
     
     pub fn set_render_distance(&mut self, render_distance: u32) {
         self.render_distance = render_distance;
     }
+
+    /// LOD section `key` should be meshed at, given where the player was last seen.
+    fn lod_of(&self, key: (i32, i32, i32)) -> u32 {
+        wanted_lod(self.last_player_chunk, key, self.chunk_meshes.get(&key).map(|(_, lod)| *lod))
+    }
+
+    /// Snapshots section `key` and hands it to the mesh workers at the LOD its distance calls for.
+    pub fn queue_mesh(&mut self, world: &World, key: (i32, i32, i32)) {
+        self.pending_chunks.insert(key);
+        let _ = self.mesh_tx.send((ChunkSnapshot::capture(world, key), self.lod_of(key)));
+    }
+
+    /// Frees the GPU buffers of chunks the world just unloaded.
+    pub fn unload_chunk_meshes(&mut self, keys: &[(i32, i32, i32)]) {
+        for key in keys {
+            self.chunk_meshes.remove(key);
+            self.pending_chunks.remove(key);
+        }
+    }
     
     pub fn set_max_fps(&mut self, max_fps: u32) {
         self.max_fps = max_fps;
@@ -160,6 +511,50 @@ impl<'a> Renderer<'a> {
     pub fn set_fov(&mut self, fov: f32) {
         self.fov = fov;
     }
+
+    /// Extra entities (e.g. mobs) for the next frame, on top of the world's players and items.
+    #[allow(dead_code)] // Mobs live in the combat system, which only the library exposes so far
+    pub fn submit_models(&mut self, models: impl IntoIterator<Item = ModelInstance>) {
+        self.queued_models.extend(models);
+    }
+
+    /// Fancy (boxes), fast (flat) or no clouds.
+    pub fn set_cloud_mode(&mut self, mode: CloudMode) {
+        self.clouds.mode = mode;
+    }
+
+    /// Rebuilds the cloud layer from the weather's drifting clouds. Call after `update_camera`,
+    /// since the cells are sorted from the new eye position.
+    pub fn update_clouds(&mut self, weather: &WeatherSystem) {
+        self.clouds.prepare(&self.device, &self.queue, weather, self.eye);
+    }
+
+    /// Which post passes run (FXAA, bloom, tonemapping) and the exposure and gamma they grade with.
+    pub fn set_post_processing(&mut self, settings: PostSettings) {
+        self.post.settings = settings;
+    }
+
+    /// Recompiles the post-processing passes from WGSL `source` (normally post.wgsl), e.g. to
+    /// try shader edits without restarting. Keeps the current passes if `source` doesn't compile.
+    pub fn switch_shader(&mut self, source: &str) -> Result<(), String> {
+        self.post.reload(&self.device, source)
+    }
+
+    /// Number of shadow cascades (0 turns the shadow pass off) and the size of each cascade's map.
+    pub fn set_shadows(&mut self, cascades: u32, map_size: u32) {
+        self.shadows.configure(&self.device, cascades, map_size);
+    }
+
+    /// Shadow depth passes the last rendered frame ran.
+    #[allow(dead_code)] // Checked by the shadow tests; the game itself doesn't look
+    pub fn shadow_passes(&self) -> u32 {
+        self.shadow_passes
+    }
+
+    /// Where the day/night cycle is (0..1, 0 = sunrise); the shadows follow the sun or moon there.
+    pub fn set_day_time(&mut self, day_time: f32) {
+        self.day_time = day_time;
+    }
     
     pub async fn new(window: &'a Window) -> Self {
         // --- KEY FIX: Use empty flags for compatibility ---
@@ -186,24 +581,47 @@ let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
                 panic!("Failed to request adapter");
             }
         };
+        let size = window.inner_size();
+        Self::with_adapter(adapter, Some(surface), size.width, size.height).await
+    }
+
+    /// A renderer without a window: frames stay in the offscreen texture for `capture_frame`.
+    /// Takes any adapter, software rasterisers included; `None` only when there is no adapter at all.
+    #[allow(dead_code)] // For tests and tools; the game always renders into its window
+    pub async fn new_headless(width: u32, height: u32) -> Option<Renderer<'static>> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default() });
+        let adapter = instance.request_adapter(&RequestAdapterOptions { power_preference: PowerPreference::HighPerformance, compatible_surface: None, force_fallback_adapter: false }).await?;
+        Some(Renderer::with_adapter(adapter, None, width, height).await)
+    }
+
+    async fn with_adapter(adapter: Adapter, surface: Option<Surface<'a>>, width: u32, height: u32) -> Self {
         let adapter_info = adapter.get_info();
-        let (device, queue) = match adapter.request_device(&DeviceDescriptor::default(), None).await {
+        let gpu_culling = gpu_cull::supported(&adapter);
+        let required_features = if gpu_culling { Features::MULTI_DRAW_INDIRECT } else { Features::empty() };
+        let (device, queue) = match adapter.request_device(&DeviceDescriptor { label: None, required_features, required_limits: Limits::default() }, None).await {
             Ok(pair) => pair,
             Err(e) => {
                 log::error!("Failed to request device: {:?}", e);
                 panic!("Failed to request device");
             }
         };
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps.formats.iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or_else(|| {
-                log::warn!("No SRGB format found, using first available");
-                surface_caps.formats[0]
-            });
-        let config = SurfaceConfiguration { usage: TextureUsages::RENDER_ATTACHMENT, format: surface_format, width: window.inner_size().width, height: window.inner_size().height, present_mode: PresentMode::Fifo, alpha_mode: surface_caps.alpha_modes[0], view_formats: vec![], desired_maximum_frame_latency: 2 };
-        surface.configure(&device, &config);
+        let config = match &surface {
+            Some(surface) => {
+                let surface_caps = surface.get_capabilities(&adapter);
+                let surface_format = surface_caps.formats.iter()
+                    .copied()
+                    .find(|f| f.is_srgb())
+                    .unwrap_or_else(|| {
+                        log::warn!("No SRGB format found, using first available");
+                        surface_caps.formats[0]
+                    });
+                let config = SurfaceConfiguration { usage: TextureUsages::RENDER_ATTACHMENT, format: surface_format, width, height, present_mode: PresentMode::Fifo, alpha_mode: surface_caps.alpha_modes[0], view_formats: vec![], desired_maximum_frame_latency: 2 };
+                surface.configure(&device, &config);
+                config
+            }
+            // Headless: nothing is presented, the config only describes the offscreen frame
+            None => SurfaceConfiguration { usage: TextureUsages::RENDER_ATTACHMENT, format: TextureFormat::Rgba8UnormSrgb, width, height, present_mode: PresentMode::Fifo, alpha_mode: CompositeAlphaMode::Opaque, view_formats: vec![], desired_maximum_frame_latency: 2 },
+        };
 
         // DIABOLICAL ZERO-LATENCY BAKE: Generate the atlas immediately so the Menu is NEVER black.
         // This takes ~150ms on modern CPUs and ensures immediate UI availability.
@@ -257,55 +675,39 @@ let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
         let time_bind_group = device.create_bind_group(&BindGroupDescriptor { label: Some("time_bind"), layout: &time_bg_layout, entries: &[BindGroupEntry { binding: 0, resource: time_buffer.as_entire_binding() }] });
 
         let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("Pipeline Layout"), bind_group_layouts: &[&bind_group_layout, &camera_bg_layout, &time_bg_layout], push_constant_ranges: &[] });
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor { label: Some("Pipeline"), layout: Some(&pipeline_layout), vertex: VertexState { module: &shader, entry_point: "vs_main", buffers: &[Vertex::desc()] }, fragment: Some(FragmentState { module: &shader, entry_point: "fs_main", targets: &[Some(ColorTargetState { format: config.format, blend: Some(BlendState::ALPHA_BLENDING), write_mask: ColorWrites::ALL })] }), primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList, strip_index_format: None, front_face: FrontFace::Ccw, cull_mode: Some(Face::Back), ..Default::default() }, depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled: true, depth_compare: CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState::default() }), multisample: MultisampleState::default(), multiview: None });
+        // Medium shadows until GraphicsConfig says otherwise, the same as its default
+        let shadows = ShadowMaps::new(&device, &shader, &bind_group_layout, &camera_bg_layout, 3, 2048);
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("Pipeline Layout"), bind_group_layouts: &[&bind_group_layout, &camera_bg_layout, &time_bg_layout, &shadows.bind_group_layout], push_constant_ranges: &[] });
+        // One pipeline per terrain layer; they differ only in fragment entry, culling and depth writes
+        let terrain_pipeline = |label: &str, fs: &str, cull_mode: Option<Face>, depth_write_enabled: bool| device.create_render_pipeline(&RenderPipelineDescriptor { label: Some(label), layout: Some(&pipeline_layout), vertex: VertexState { module: &shader, entry_point: "vs_main", buffers: &[Vertex::desc()] }, fragment: Some(FragmentState { module: &shader, entry_point: fs, targets: &[Some(ColorTargetState { format: HDR_FORMAT, blend: Some(BlendState::ALPHA_BLENDING), write_mask: ColorWrites::ALL })] }), primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList, strip_index_format: None, front_face: FrontFace::Ccw, cull_mode, ..Default::default() }, depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled, depth_compare: CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState::default() }), multisample: MultisampleState::default(), multiview: None });
+        let pipeline = terrain_pipeline("Pipeline", "fs_main", Some(Face::Back), true);
+        let models = EntityModels::new(&device, &shader, &pipeline_layout, HDR_FORMAT);
+        let clouds = CloudRenderer::new(&device, &shader, &pipeline_layout, HDR_FORMAT);
+        let post = PostStack::new(&device, config.format, config.width, config.height);
+        let cutout_pipeline = terrain_pipeline("Cutout Pipeline", "fs_cutout", None, true); // Double-sided for crossed plants
+        let translucent_pipeline = terrain_pipeline("Translucent Pipeline", "fs_main", Some(Face::Back), false);
 
         // DIABOLICAL BINDING STABILITY: The UI pipeline only needs the Texture Atlas (Group 0). 
         // Removing Groups 1 and 2 here prevents validation errors when camera/time aren't bound.
         let ui_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("UI Layout"), bind_group_layouts: &[&bind_group_layout], push_constant_ranges: &[] });
         let ui_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor { label: Some("UI Pipeline"), layout: Some(&ui_pipeline_layout), vertex: VertexState { module: &shader, entry_point: "vs_ui", buffers: &[Vertex::desc()] }, fragment: Some(FragmentState { module: &shader, entry_point: "fs_ui", targets: &[Some(ColorTargetState { format: config.format, blend: Some(BlendState::ALPHA_BLENDING), write_mask: ColorWrites::ALL })] }), primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList, strip_index_format: None, front_face: FrontFace::Ccw, cull_mode: None, ..Default::default() }, depth_stencil: None, multisample: MultisampleState::default(), multiview: None });
 
+        let blit_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor { label: Some("Blit Pipeline"), layout: Some(&ui_pipeline_layout), vertex: VertexState { module: &shader, entry_point: "vs_blit", buffers: &[] }, fragment: Some(FragmentState { module: &shader, entry_point: "fs_blit", targets: &[Some(ColorTargetState { format: config.format, blend: None, write_mask: ColorWrites::ALL })] }), primitive: PrimitiveState::default(), depth_stencil: None, multisample: MultisampleState::default(), multiview: None });
+        let frame_sampler = device.create_sampler(&SamplerDescriptor { label: Some("frame_sampler"), ..Default::default() });
+        let (frame, frame_bind_group) = create_frame(&device, &config, &bind_group_layout, &frame_sampler);
+
         let depth_texture = device.create_texture(&TextureDescriptor { size: Extent3d { width: config.width, height: config.height, depth_or_array_layers: 1 }, mip_level_count: 1, sample_count: 1, dimension: TextureDimension::D2, format: TextureFormat::Depth32Float, usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING, label: Some("depth"), view_formats: &[] }).create_view(&TextureViewDescriptor::default());
         let entity_vertex_buffer = device.create_buffer(&BufferDescriptor { label: Some("Entity VB"), size: 1024, usage: BufferUsages::VERTEX | BufferUsages::COPY_DST, mapped_at_creation: false });
 
         // --- DIABOLICAL COMPUTE CULLER INIT ---
-        let compute_shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
-        
-// Max 10,000 chunks handled at once
-        let chunk_data_buffer = device.create_buffer(&BufferDescriptor { label: Some("Chunk Data Buffer"), size: (10000 * std::mem::size_of::<ChunkCullData>()) as u64, usage: BufferUsages::STORAGE | BufferUsages::COPY_DST, mapped_at_creation: false });
-        let indirect_draw_buffer = device.create_buffer(&BufferDescriptor { label: Some("Indirect Draw Buffer"), size: (10000 * std::mem::size_of::<DrawIndexedIndirect>()) as u64, usage: BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_DST, mapped_at_creation: false });
-        let indirect_count_buffer = device.create_buffer(&BufferDescriptor { label: Some("Indirect Count Buffer"), size: 256, usage: BufferUsages::STORAGE | BufferUsages::INDIRECT | BufferUsages::COPY_DST, mapped_at_creation: false });
-
-let cull_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("Cull Layout"),
-            entries: &[
-                BindGroupLayoutEntry { binding: 0, visibility: ShaderStages::COMPUTE, ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: None }, count: None },
-                BindGroupLayoutEntry { binding: 1, visibility: ShaderStages::COMPUTE, ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: false }, has_dynamic_offset: false, min_binding_size: None }, count: None },
-                BindGroupLayoutEntry { binding: 2, visibility: ShaderStages::COMPUTE, ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: false }, has_dynamic_offset: false, min_binding_size: None }, count: None },
-            ],
-        });
-
-        let cull_bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("Cull BG"),
-            layout: &cull_bg_layout,
-            entries: &[
-                BindGroupEntry { binding: 0, resource: chunk_data_buffer.as_entire_binding() },
-                BindGroupEntry { binding: 1, resource: indirect_draw_buffer.as_entire_binding() },
-                BindGroupEntry { binding: 2, resource: indirect_count_buffer.as_entire_binding() },
-            ],
-        });
-
-        let compute_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { 
-            label: Some("Compute Layout"), 
-            bind_group_layouts: &[&bind_group_layout, &camera_bg_layout, &time_bg_layout, &cull_bg_layout], 
-            push_constant_ranges: &[] 
-        });
-        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor { label: Some("Cull Pipeline"), layout: Some(&compute_pipeline_layout), module: &compute_shader, entry_point: "compute_cull" });
+        let arena = MeshArena::new(&device);
+        let culler = GpuCuller::new(&device, &camera_buffer);
+        log::info!("Chunk culling: {}", if gpu_culling { "GPU (compute + multi-draw-indirect)" } else { "CPU (adapter lacks multi-draw-indirect)" });
 let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("Entity IB"), size: 1024, usage: BufferUsages::INDEX | BufferUsages::COPY_DST, mapped_at_creation: false });
 
 // DIABOLICAL WORKER POOL INITIALIZATION
 // DIABOLICAL THREADED LOD MESH GENERATOR
-        let (task_tx, task_rx) = unbounded::<(i32, i32, i32, u32, Arc<World>)>();
+        let (task_tx, task_rx) = unbounded::<(ChunkSnapshot, u32)>();
         let (result_tx, result_rx) = unbounded::<MeshTask>();
         // DIABOLICAL MULTI-THREADED MESH GENERATION: Scaling to all available CPU cores
         let thread_count = std::thread::available_parallelism()
@@ -320,139 +722,23 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
             let _ = std::thread::Builder::new()
                 .name(format!("mesh_worker_{}", thread_id))
                 .spawn(move || {
-                    while let Ok((cx, cy, cz, lod, world)) = t_rx.recv() {
+                    while let Ok((snap, lod)) = t_rx.recv() {
+                        let (cx, cy, cz) = snap.key;
                         // Add validation for chunk coordinates
                         if cx.abs() > 1000 || cy.abs() > 1000 || cz.abs() > 1000 {
                             log::warn!("Invalid chunk coordinates: ({}, {}, {})", cx, cy, cz);
                             continue;
                         }
                         
-                        let mut vertices = Vec::new();
-                        let mut indices = Vec::new();
-                        let mut i_cnt = 0;
-                    
-                    if let Some(chunk) = world.chunks.get(&(cx, cy, cz)) {
-                        if chunk.is_empty {
-                            let _ = r_tx.send(MeshTask { cx, cy, cz, lod, vertices: Vec::new(), indices: Vec::new(), ranges: Vec::new() });
-                            continue;
-                        }
-
-                        let bx = (cx * 16) as f32;
-                        let by = (cy * 16) as f32;
-                        let bz = (cz * 16) as f32;
-                        let step = 1 << lod;
-                        
-                        for axis in 0..3 {
-                            for d in (0..16usize).step_by(step as usize) {
-                                for dir in 0..2 {
-                                    let face_id = match axis { 
-                                        0 => if dir==0 {0} else {1},
-                                        1 => if dir==0 {2} else {3},
-                                        _ => if dir==0 {4} else {5}
-                                    };
-                                    
-                                    let mask_dim = 16 / step as usize;
-                                    let mut mask = vec![BlockType::Air; mask_dim * mask_dim];
-                                    
-                                    for u_m in 0..mask_dim {
-                                        for v_m in 0..mask_dim {
-                                            let u = u_m * step as usize;
-                                            let v = v_m * step as usize;
-                                            let (lx, ly, lz) = match axis {
-                                                0 => (u, d, v), 1 => (d, u, v), _ => (u, v, d)
-                                            };
-                                            
-                                            let blk = chunk.get_block(lx, ly, lz);
-                                            if !blk.is_solid() { continue; }
-                                            
-                                            let (nx, ny, nz) = match face_id { 
-                                                0 => (lx as i32, ly as i32 + step as i32, lz as i32), 
-                                                1 => (lx as i32, ly as i32 - 1, lz as i32), 
-                                                2 => (lx as i32 + step as i32, ly as i32, lz as i32), 
-                                                3 => (lx as i32 - 1, ly as i32, lz as i32), 
-                                                4 => (lx as i32, ly as i32, lz as i32 + step as i32), 
-                                                5 => (lx as i32, ly as i32, lz as i32 - 1), 
-                                                _ => (0, 0, 0)
-                                            };
-                                            
-                                            let neighbor = if nx >= 0 && nx < 16 && ny >= 0 && ny < 16 && nz >= 0 && nz < 16 {
-                                                chunk.get_block(nx as usize, ny as usize, nz as usize)
-                                            } else {
-                                                BlockType::Air
-                                            };
-                                            
-                                            if !neighbor.is_solid() || (neighbor.is_transparent() && neighbor != blk) { 
-                                                mask[v_m * mask_dim + u_m] = blk; 
-                                            }
-                                        }
-                                    }
-                                    
-                                    let mut n = 0;
-                                    while n < mask.len() {
-                                        let blk = mask[n];
-                                        if blk != BlockType::Air {
-                                            let mut w = 1;
-                                            while (n + w) % mask_dim != 0 && mask[n + w] == blk { w += 1; }
-                                            let mut h = 1;
-                                            'h_loop: while (n / mask_dim + h) < mask_dim {
-                                                for k in 0..w { if mask[n + k + h * mask_dim] != blk { break 'h_loop; } }
-                                                h += 1;
-                                            }
-                                            
-                                            let u_g = (n % mask_dim) as f32 * step as f32;
-                                            let v_g = (n / mask_dim) as f32 * step as f32;
-                                            let world_w = w as f32 * step as f32;
-                                            let world_h = h as f32 * step as f32;
-                                            let d_f = d as f32;
-                                            let s_f = step as f32;
-                                            
-                                            let (wx, wy, wz) = match axis {
-                                                0 => (bx + u_g, by + d_f, bz + v_g),
-                                                1 => (bx + d_f, by + u_g, bz + v_g),
-                                                _ => (bx + u_g, by + v_g, bz + d_f)
-                                            };
-                                            
-                                            let tex_index = match face_id { 0 => blk.get_texture_top(), 1 => blk.get_texture_bottom(), _ => blk.get_texture_side() };
-                                            
-                                            // CORRECTED CCW WINDING ORDERS FOR LOD MESHES
-                                            let (positions, uv) = match face_id {
-                                                0 => ([[wx, wy+s_f, wz+world_h], [wx+world_w, wy+s_f, wz+world_h], [wx+world_w, wy+s_f, wz], [wx, wy+s_f, wz]], [[0.0, world_h], [world_w, world_h], [world_w, 0.0], [0.0, 0.0]]),
-                                                1 => ([[wx, wy, wz], [wx+world_w, wy, wz], [wx+world_w, wy, wz+world_h], [wx, wy, wz+world_h]], [[0.0, 0.0], [world_w, 0.0], [world_w, world_h], [0.0, world_h]]),
-                                                2 => ([[wx+s_f, wy, wz], [wx+s_f, wy+world_w, wz], [wx+s_f, wy+world_w, wz+world_h], [wx+s_f, wy, wz+world_h]], [[0.0, 0.0], [world_w, 0.0], [world_w, world_h], [0.0, world_h]]),
-                                                3 => ([[wx, wy, wz+world_h], [wx, wy+world_w, wz+world_h], [wx, wy+world_w, wz], [wx, wy, wz]], [[0.0, world_h], [world_w, world_h], [world_w, 0.0], [0.0, 0.0]]),
-                                                4 => ([[wx, wy, wz+s_f], [wx+world_w, wy, wz+s_f], [wx+world_w, wy+world_h, wz+s_f], [wx, wy+world_h, wz+s_f]], [[0.0, 0.0], [world_w, 0.0], [world_w, world_h], [0.0, world_h]]),
-                                                5 => ([[wx+world_w, wy, wz], [wx, wy, wz], [wx, wy+world_h, wz], [wx+world_w, wy+world_h, wz]], [[world_w, 0.0], [0.0, 0.0], [0.0, world_h], [world_w, world_h]]),
-                                                _ => ([[0.0; 3]; 4], [[0.0; 2]; 4]),
-                                            };
-                                            
-                                            let base_i = i_cnt;
-                                            vertices.push(Vertex { position: positions[0], tex_coords: uv[0], ao: 1.0, tex_index, light: 15.0 });
-                                            vertices.push(Vertex { position: positions[1], tex_coords: uv[1], ao: 1.0, tex_index, light: 15.0 });
-                                            vertices.push(Vertex { position: positions[2], tex_coords: uv[2], ao: 1.0, tex_index, light: 15.0 });
-                                            vertices.push(Vertex { position: positions[3], tex_coords: uv[3], ao: 1.0, tex_index, light: 15.0 });
-                                            indices.extend_from_slice(&[base_i, base_i + 1, base_i + 2, base_i, base_i + 2, base_i + 3]);
-                                            i_cnt += 4;
-                                            
-                                            for l in 0..h { for k in 0..w { mask[n + k + l * mask_dim] = BlockType::Air; } }
-                                        }
-                                        n += 1;
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let mut final_ranges = Vec::new();
-                    if !indices.is_empty() { final_ranges.push(TextureRange { _tex_index: 0, _index_start: 0, _index_count: indices.len() as u32 }); }
-                    let _ = r_tx.send(MeshTask { cx, cy, cz, lod, vertices, indices, ranges: final_ranges });
+                        let _ = r_tx.send(mesh_chunk(&snap, lod));
                 }
             });
         }
 
         Self {
-            particles: Vec::new(), surface, device, queue, config, pipeline, ui_pipeline, depth_texture, bind_group, camera_bind_group, camera_buffer, time_bind_group, time_buffer, start_time: Instant::now(), 
+            particles: Vec::new(), surface, device, queue, config, frame, frame_bind_group, frame_sampler, texture_layout: bind_group_layout, blit_pipeline, pipeline, ui_pipeline, cutout_pipeline, translucent_pipeline, eye: glam::Vec3::ZERO, forward: glam::Vec3::X, shadows, shadow_passes: 0, day_time: 0.0, depth_texture, bind_group, camera_bind_group, camera_buffer, time_bind_group, time_buffer, start_time: Instant::now(), frozen_time: None,
             chunk_meshes: HashMap::new(),
-            entity_vertex_buffer, entity_index_buffer, 
+            entity_vertex_buffer, entity_index_buffer, models, clouds, post, queued_models: Vec::new(),
             break_progress: 0.0,
             fps: 0.0,
             frame_count: 0,
@@ -461,11 +747,9 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
             mesh_tx: task_tx,
             mesh_rx: result_rx,
             pending_chunks: HashSet::new(),
-            compute_pipeline,
-            chunk_data_buffer,
-            indirect_draw_buffer,
-            indirect_count_buffer,
-            cull_bind_group,
+            arena,
+            culler,
+            gpu_culling,
             loading_progress: 0.0,
             loading_message: "INITIALIZING...".to_string(),
             transition_alpha: 1.0,
@@ -500,12 +784,16 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
         let max_tasks_per_frame = 32; // Increased from 8 for much better performance
         let mut processed = 0;
         
-        while let Ok(task) = self.mesh_rx.try_recv() {
-            if processed >= max_tasks_per_frame {
-                break; // Process more next frame
-            }
+        // Check the budget before receiving: a result taken off the channel and then dropped would
+        // leave its section pending forever
+        while processed < max_tasks_per_frame {
+            let Ok(task) = self.mesh_rx.try_recv() else { break };
             
-            self.pending_chunks.remove(&(task.cx, task.cy, task.cz));
+            // Results for chunks that were unloaded (or wiped by a rebuild) while meshing are stale
+            if !self.pending_chunks.remove(&(task.cx, task.cy, task.cz)) {
+                processed += 1;
+                continue;
+            }
             self.chunk_meshes.remove(&(task.cx, task.cy, task.cz));
 
             if !task.vertices.is_empty() {
@@ -513,30 +801,17 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
                 #[cfg(debug_assertions)]
                 {
                     // Validate mesh data to prevent GPU crashes
-                    if task.vertices.len() > 100000 || task.indices.len() > 200000 {
-                        log::warn!("Rejecting oversized mesh for chunk ({}, {}, {}): {} vertices, {} indices", 
-                                 task.cx, task.cy, task.cz, task.vertices.len(), task.indices.len());
+                    if task.vertices.len() > 100000 {
+                        log::warn!("Rejecting oversized mesh for chunk ({}, {}, {}): {} vertices", 
+                                 task.cx, task.cy, task.cz, task.vertices.len());
                         processed += 1;
                         continue;
                     }
                 }
                 
-                let vb = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { 
-                    label: Some(&format!("Chunk VB ({},{},{})", task.cx, task.cy, task.cz)), 
-                    contents: bytemuck::cast_slice(&task.vertices), 
-                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST 
-                });
-                let ib = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { 
-                    label: Some(&format!("Chunk IB ({},{},{})", task.cx, task.cy, task.cz)), 
-                    contents: bytemuck::cast_slice(&task.indices), 
-                    usage: BufferUsages::INDEX | BufferUsages::COPY_DST 
-                });
-                self.chunk_meshes.insert((task.cx, task.cy, task.cz), (ChunkMesh { 
-                    vertex_buffer: vb, 
-                    index_buffer: ib, 
-                    _ranges: task.ranges, 
-                    total_indices: task.indices.len() as u32 
-                }, task.lod));
+                let (key, lod) = ((task.cx, task.cy, task.cz), task.lod);
+                let mesh = self.upload_mesh(task);
+                self.chunk_meshes.insert(key, (mesh, lod));
             }
             processed += 1;
         }
@@ -569,14 +844,7 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
     }
 
     pub fn render_loading_screen(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = match self.surface.get_current_texture() {
-            Ok(t) => t,
-            Err(e) => {
-                self.dump_crash_telemetry(&format!("{:?}", e));
-                return Err(e);
-            }
-        };
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = self.frame.create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Loading Encoder") });
         let _aspect = self.config.width as f32 / self.config.height as f32;
         let _time = self.init_time.elapsed().as_secs_f32();
@@ -636,15 +904,15 @@ let entity_index_buffer = device.create_buffer(&BufferDescriptor { label: Some("
             }
         }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        Ok(())
+        self.present(encoder).inspect_err(|e| self.dump_crash_telemetry(&format!("{:?}", e)))
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.config.width = width; self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+            if let Some(surface) = &self.surface { surface.configure(&self.device, &self.config); }
+            (self.frame, self.frame_bind_group) = create_frame(&self.device, &self.config, &self.texture_layout, &self.frame_sampler);
+            self.post.resize(&self.device, width, height);
             self.depth_texture = self.device.create_texture(&TextureDescriptor { size: Extent3d { width, height, depth_or_array_layers: 1 }, mip_level_count: 1, sample_count: 1, dimension: TextureDimension::D2, format: TextureFormat::Depth32Float, usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING, label: Some("depth"), view_formats: &[] }).create_view(&TextureViewDescriptor::default());
         }
     }
@@ -666,265 +934,65 @@ pub fn rebuild_all_chunks(&mut self, world: &World) {
             self.update_chunk(key.0, key.1, key.2, world);
             chunk_count += 1;
         }
-        self.update_clouds(world);
         log::info!("Queued {} chunks for rebuilding", chunk_count);
     }
 
-    fn update_clouds(&mut self, world: &World) {
-        let mut vertices = Vec::new(); let mut indices = Vec::new(); let mut offset = 0;
-        let cloud_y = 110.0;
-        for cx in -10..10 {
-            for cz in -10..10 {
-                let wx = cx * 16; let wz = cz * 16;
-                let noise = crate::noise_gen::NoiseGenerator::new(world.seed);
-                if noise.get_noise3d(wx as f64 * 0.01, 0.0, wz as f64 * 0.01) > 0.4 {
-                    self.add_face(&mut vertices, &mut indices, &mut offset, wx, cloud_y as i32, wz, 0, 228, 1.0, 1.0);
-                    self.add_face(&mut vertices, &mut indices, &mut offset, wx, cloud_y as i32, wz, 1, 228, 1.0, 0.8);
-                }
-            }
-        }
-        if !vertices.is_empty() {
-            let vb = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: Some("Cloud VB"), contents: bytemuck::cast_slice(&vertices), usage: BufferUsages::VERTEX });
-            let ib = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: Some("Cloud IB"), contents: bytemuck::cast_slice(&indices), usage: BufferUsages::INDEX });
-            self.chunk_meshes.insert((999, 999, 999), (ChunkMesh { vertex_buffer: vb, index_buffer: ib, _ranges: vec![TextureRange { _tex_index: 228, _index_start: 0, _index_count: indices.len() as u32 }], total_indices: indices.len() as u32 }, 0));
-        }
-    }
-
-    // RADICAL FIX: Completely rewritten update_chunk with proper coordinate handling
+    /// Remeshes one section right away on the calling thread, for edits the player should see this frame.
     pub fn update_chunk(&mut self, cx: i32, cy: i32, cz: i32, world: &World) {
-        // DIABOLICAL PURGE: Before we even check the chunk, we ensure the "dirty" flag is cleared
-        // locally so the generator doesn't get stuck in a loop.
-        if let Some(chunk) = world.chunks.get(&(cx, cy, cz)) {
-            if chunk.is_empty {
-                self.chunk_meshes.remove(&(cx, cy, cz));
-                return;
-            }
-            
-            let mut chunk_v = Vec::new();
-            let mut chunk_i = Vec::new();
-            let mut i_cnt = 0u32;
-            
-            let bx = (cx * 16) as f32;
-            let by = (cy * 16) as f32;
-            let bz = (cz * 16) as f32;
-
-            // RADICAL FIX: Consistent 16x16x16 greedy meshing for all axes
-            for axis in 0..3 {
-                let dims_u = 16usize;
-                let dims_v = 16usize;
-                let dims_main = 16usize;
-
-                for d in 0..dims_main {
-                    for dir in 0..2 { 
-                        let face_id = match axis { 
-                            0 => if dir==0 {0} else {1}, // Y: Top/Bottom
-                            1 => if dir==0 {2} else {3}, // X: Right/Left
-                            _ => if dir==0 {4} else {5}  // Z: Front/Back
-                        };
-                        
-                        let mut mask = vec![BlockType::Air; dims_u * dims_v];
-                        
-                        // Build visibility mask
-                        for u_idx in 0..dims_u {
-                            for v_idx in 0..dims_v {
-                                // RADICAL FIX: Consistent coordinate mapping
-                                let (lx, ly, lz) = match axis {
-                                    0 => (u_idx, d, v_idx),      // Y-slice: x=u, y=d, z=v
-                                    1 => (d, u_idx, v_idx),      // X-slice: x=d, y=u, z=v
-                                    _ => (u_idx, v_idx, d)       // Z-slice: x=u, y=v, z=d
-                                };
-                                
-                                let blk = chunk.get_block(lx, ly, lz);
-                                if !blk.is_solid() && !blk.is_liquid() {
-                                    continue;
-                                }
-                                
-                                // Check neighbor
-                                let (nx, ny, nz) = match face_id {
-                                    0 => (lx as i32, ly as i32 + 1, lz as i32),
-                                    1 => (lx as i32, ly as i32 - 1, lz as i32),
-                                    2 => (lx as i32 + 1, ly as i32, lz as i32),
-                                    3 => (lx as i32 - 1, ly as i32, lz as i32),
-                                    4 => (lx as i32, ly as i32, lz as i32 + 1),
-                                    5 => (lx as i32, ly as i32, lz as i32 - 1),
-                                    _ => (0, 0, 0)
-                                };
-                                
-                                // DIABOLICAL BOUNDARY SYNC: Ghost blocks often appear because the neighbor 
-                                // lookup fails or returns 'Air' incorrectly during a chunk transition.
-                                let neighbor = {
-                                    let wx = cx * 16 + nx;
-                                    let wy = cy * 16 + ny;
-                                    let wz = cz * 16 + nz;
-                                    world.get_block(BlockPos { x: wx, y: wy, z: wz })
-                                };
-
-                                // ROOT CAUSE FIX: A block is visible if the neighbor is not solid OR 
-                                // if the neighbor is the EXACT SAME transparent block (prevents internal faces).
-                                let visible = !neighbor.is_solid() || (neighbor.is_transparent() && neighbor != blk);
-                                if visible { 
-                                    mask[v_idx * dims_u + u_idx] = blk; 
-                                }
-                            }
-                        }
-
-                        // Greedy merge
-                        let mut n = 0;
-                        while n < mask.len() {
-                            let blk = mask[n];
-                            if blk != BlockType::Air {
-                                let mut w = 1;
-                                while (n + w) % dims_u != 0 && mask[n + w] == blk { 
-                                    w += 1; 
-                                }
-                                
-                                let mut h = 1;
-                                'h_loop: while (n / dims_u + h) < dims_v {
-                                    for k in 0..w {
-                                        if mask[n + k + h * dims_u] != blk { 
-                                            break 'h_loop; 
-                                        }
-                                    }
-                                    h += 1;
-                                }
-
-                                let u_greedy = (n % dims_u) as i32;
-                                let v_greedy = (n / dims_u) as i32;
-                                
-                                // RADICAL FIX: Consistent world position calculation
-                                let (wx, wy, wz) = match axis {
-                                    0 => (bx + u_greedy as f32, by + d as f32, bz + v_greedy as f32),
-                                    1 => (bx + d as f32, by + u_greedy as f32, bz + v_greedy as f32),
-                                    _ => (bx + u_greedy as f32, by + v_greedy as f32, bz + d as f32)
-                                };
-                                
-                                let world_w = w as f32;
-                                let world_h = h as f32;
-
-                                self.add_face_greedy(&mut chunk_v, &mut chunk_i, &mut i_cnt, wx, wy, wz, world_w, world_h, face_id, blk);
-
-
-                                for l in 0..h {
-                                    for k in 0..w { 
-                                        mask[n + k + l * dims_u] = BlockType::Air; 
-                                    }
-                                }
-                            }
-                            n += 1;
-                        }
-                    }
-                }
-            }
-
-            if !chunk_v.is_empty() {
-                let vb = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { 
-                    label: Some("Chunk VB"), 
-                    contents: bytemuck::cast_slice(&chunk_v), 
-                    usage: BufferUsages::VERTEX 
-                });
-                let ib = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor { 
-                    label: Some("Chunk IB"), 
-                    contents: bytemuck::cast_slice(&chunk_i), 
-                    usage: BufferUsages::INDEX 
-                });
-                self.chunk_meshes.insert((cx, cy, cz), (ChunkMesh { 
-                    vertex_buffer: vb, 
-                    index_buffer: ib, 
-                    _ranges: Vec::new(), 
-                    total_indices: chunk_i.len() as u32 
-                }, 0));
-            } else {
-                self.chunk_meshes.remove(&(cx, cy, cz));
-            }
+        let task = mesh_chunk(&ChunkSnapshot::capture(world, (cx, cy, cz)), 0);
+        if task.vertices.is_empty() {
+            self.chunk_meshes.remove(&(cx, cy, cz));
+            return;
         }
+        let mesh = self.upload_mesh(task);
+        self.chunk_meshes.insert((cx, cy, cz), (mesh, 0));
     }
 
-
-
-fn add_face(&self, v: &mut Vec<Vertex>, i: &mut Vec<u32>, off: &mut u32, x: i32, y: i32, z: i32, face: u8, tex: u32, h: f32, light: f32) {
-        let x = x as f32; let y = y as f32; let z = z as f32;
-        let (p0, p1, p2, p3, uv0, uv1, uv2, uv3) = match face {
-            0 => ([x,y+h,z+1.0], [x+1.0,y+h,z+1.0], [x+1.0,y+h,z], [x,y+h,z], [0.0,1.0], [1.0,1.0], [1.0,0.0], [0.0,0.0]),
-            1 => ([x,y,z], [x+1.0,y,z], [x+1.0,y,z+1.0], [x,y,z+1.0], [0.0,0.0], [1.0,0.0], [1.0,1.0], [0.0,1.0]),
-            2 => ([x,y,z], [x,y,z+1.0], [x,y+h,z+1.0], [x,y+h,z], [0.0,1.0], [1.0,1.0], [1.0,0.0], [0.0,0.0]),
-            3 => ([x+1.0,y,z+1.0], [x+1.0,y,z], [x+1.0,y+h,z], [x+1.0,y+h,z+1.0], [0.0,1.0], [1.0,1.0], [1.0,0.0], [0.0,0.0]),
-            4 => ([x,y,z+1.0], [x+1.0,y,z+1.0], [x+1.0,y+h,z+1.0], [x,y+h,z+1.0], [0.0,1.0], [1.0,1.0], [1.0,0.0], [0.0,0.0]),
-            5 => ([x+1.0,y,z], [x,y,z], [x,y+h,z], [x+1.0,y+h,z], [0.0,1.0], [1.0,1.0], [1.0,0.0], [0.0,0.0]),
-            _ => return,
-        };
-        v.push(Vertex{position:p0, tex_coords:uv0, ao:1.0, tex_index:tex, light}); 
-        v.push(Vertex{position:p1, tex_coords:uv1, ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:p2, tex_coords:uv2, ao:1.0, tex_index:tex, light}); 
-        v.push(Vertex{position:p3, tex_coords:uv3, ao:1.0, tex_index:tex, light});
-        i.push(*off); i.push(*off+1); i.push(*off+2); i.push(*off); i.push(*off+2); i.push(*off+3); *off += 4;
+    /// Uploads a finished mesh into the arena as opaque, cutout and (sorted) translucent index ranges.
+    fn upload_mesh(&mut self, task: MeshTask) -> ChunkMesh {
+        let translucent = sorted_translucent_indices(&task.translucent, self.eye);
+        let (o, c, t) = (task.opaque.len() as u32, task.cutout.len() as u32, translucent.len() as u32);
+        let mut indices = task.opaque;
+        indices.extend(task.cutout);
+        indices.extend(translucent);
+        let slot = self.arena.upload(&self.device, &self.queue, &task.vertices, &indices);
+        ChunkMesh { slot, layers: [0..o, o..o + c, o + c..o + c + t], translucent: task.translucent, sorted_from: self.eye }
     }
 
-    fn add_rotated_quad(&self, v: &mut Vec<Vertex>, i: &mut Vec<u32>, off: &mut u32, c: [f32;3], rot: f32, rx: f32, ry: f32, rz: f32, s: f32, face: usize, tex: u32) {
-        let (sin, cos) = rot.sin_cos();
-        let rotate = |x: f32, z: f32| (x * cos - z * sin, x * sin + z * cos);
-        let (p0, p1, p2, p3) = match face {
-            0 => ((rx,ry+s,rz+s), (rx+s,ry+s,rz+s), (rx+s,ry+s,rz), (rx,ry+s,rz)),
-            1 => ((rx,ry,rz), (rx+s,ry,rz), (rx+s,ry,rz+s), (rx,ry,rz+s)),
-            2 => ((rx,ry,rz), (rx,ry,rz+s), (rx,ry+s,rz+s), (rx,ry+s,rz)),
-            3 => ((rx+s,ry,rz+s), (rx+s,ry,rz), (rx+s,ry+s,rz), (rx+s,ry+s,rz+s)),
-            4 => ((rx,ry,rz+s), (rx+s,ry,rz+s), (rx+s,ry+s,rz+s), (rx,ry+s,rz+s)),
-            5 => ((rx+s,ry,rz), (rx,ry,rz), (rx,ry+s,rz), (rx+s,ry+s,rz)), _ => return,
-        };
-        let t = |p: (f32,f32,f32)| { let r = rotate(p.0, p.2); [c[0]+r.0, c[1]+p.1, c[2]+r.1] };
-        v.push(Vertex{position:t(p0), tex_coords:[0.0,1.0], ao:1.0, tex_index:tex, light: 15.0}); v.push(Vertex{position:t(p1), tex_coords:[1.0,1.0], ao:1.0, tex_index:tex, light: 15.0});
-        v.push(Vertex{position:t(p2), tex_coords:[1.0,0.0], ao:1.0, tex_index:tex, light: 15.0}); v.push(Vertex{position:t(p3), tex_coords:[0.0,0.0], ao:1.0, tex_index:tex, light: 15.0});
-        i.push(*off); i.push(*off+1); i.push(*off+2); i.push(*off); i.push(*off+2); i.push(*off+3); *off += 4;
+    /// Re-sorts the translucent faces of nearby chunks once the camera has moved a block since their
+    /// last sort. Far chunks keep the order they were meshed with; it barely changes from there.
+    fn resort_translucent(&mut self) {
+        let eye = self.eye;
+        for (&(cx, cy, cz), (mesh, _)) in self.chunk_meshes.iter_mut() {
+            if mesh.translucent.is_empty() || mesh.sorted_from.distance_squared(eye) < 1.0 { continue; }
+            let center = glam::Vec3::new(cx as f32 * 16.0 + 8.0, cy as f32 * 16.0 + 8.0, cz as f32 * 16.0 + 8.0);
+            if center.distance(eye) > TRANSLUCENT_RESORT_RANGE { continue; }
+            let indices = sorted_translucent_indices(&mesh.translucent, eye);
+            self.arena.write_indices(&self.queue, &mesh.slot, mesh.layers[2].start, &indices);
+            mesh.sorted_from = eye;
+        }
     }
 
 fn _add_cross_face(&self, v: &mut Vec<Vertex>, i: &mut Vec<u32>, off: &mut u32, x: i32, y: i32, z: i32, tex: u32, light: f32) {
         let x = x as f32; let y = y as f32; let z = z as f32;
         // Diagonal 1
-        v.push(Vertex{position:[x, y+1.0, z], tex_coords:[0.0,0.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x+1.0, y+1.0, z+1.0], tex_coords:[1.0,0.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x+1.0, y, z+1.0], tex_coords:[1.0,1.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x, y, z], tex_coords:[0.0,1.0], ao:1.0, tex_index:tex, light});
+        v.push(Vertex{position:[x, y+1.0, z], tex_coords:[0.0,0.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x+1.0, y+1.0, z+1.0], tex_coords:[1.0,0.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x+1.0, y, z+1.0], tex_coords:[1.0,1.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x, y, z], tex_coords:[0.0,1.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
         i.push(*off); i.push(*off+1); i.push(*off+2); i.push(*off); i.push(*off+2); i.push(*off+3); *off += 4;
         
         // Diagonal 2
-        v.push(Vertex{position:[x, y+1.0, z+1.0], tex_coords:[0.0,0.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x+1.0, y+1.0, z], tex_coords:[1.0,0.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x+1.0, y, z], tex_coords:[1.0,1.0], ao:1.0, tex_index:tex, light});
-        v.push(Vertex{position:[x, y, z+1.0], tex_coords:[0.0,1.0], ao:1.0, tex_index:tex, light});
+        v.push(Vertex{position:[x, y+1.0, z+1.0], tex_coords:[0.0,0.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x+1.0, y+1.0, z], tex_coords:[1.0,0.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x+1.0, y, z], tex_coords:[1.0,1.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
+        v.push(Vertex{position:[x, y, z+1.0], tex_coords:[0.0,1.0], ao:1.0, tex_index:tex, light, tint: NO_TINT});
         i.push(*off); i.push(*off+1); i.push(*off+2); i.push(*off); i.push(*off+2); i.push(*off+3); *off += 4;
     }
 // DIABOLICAL GREEDY MESHER HELPER: Absolute Positional Integrity (Fixes Plane Fighting)
-    fn add_face_greedy(&self, v: &mut Vec<Vertex>, i: &mut Vec<u32>, i_count: &mut u32, x: f32, y: f32, z: f32, w: f32, h: f32, face: usize, block: BlockType) {
-        let tex_index = match face {
-            0 => block.get_texture_top(),
-            1 => block.get_texture_bottom(),
-            _ => block.get_texture_side()
-        };
-        
-        // BIT-LEVEL PARITY FIX: Correct UV mapping and winding for all 6 faces to prevent "Reversed Texture" bugs.
-        // WGPU uses Y-down UV, so top-left of a block is (0,0).
-        let (positions, uv) = match face {
-            0 => ([[x, y + 1.0, z + h], [x + w, y + 1.0, z + h], [x + w, y + 1.0, z], [x, y + 1.0, z]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Top
-            1 => ([[x, y, z], [x + w, y, z], [x + w, y, z + h], [x, y, z + h]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Bottom
-            2 => ([[x + 1.0, y, z + h], [x + 1.0, y + w, z + h], [x + 1.0, y + w, z], [x + 1.0, y, z]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Right (East)
-            3 => ([[x, y, z], [x, y + w, z], [x, y + w, z + h], [x, y, z + h]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Left (West)
-            4 => ([[x, y, z + 1.0], [x + w, y, z + 1.0], [x + w, y + h, z + 1.0], [x, y + h, z + 1.0]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Front
-            5 => ([[x + w, y, z], [x, y, z], [x, y + h, z], [x + w, y + h, z]], [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]]), // Back
-            _ => ([[0.0; 3]; 4], [[0.0; 2]; 4]),
-        };
-
-        let base_i = *i_count;
-        v.push(Vertex { position: positions[0], tex_coords: uv[0], ao: 1.0, tex_index, light: 15.0 });
-        v.push(Vertex { position: positions[1], tex_coords: uv[1], ao: 1.0, tex_index, light: 15.0 });
-        v.push(Vertex { position: positions[2], tex_coords: uv[2], ao: 1.0, tex_index, light: 15.0 });
-        v.push(Vertex { position: positions[3], tex_coords: uv[3], ao: 1.0, tex_index, light: 15.0 });
-        
-        i.extend_from_slice(&[base_i, base_i + 1, base_i + 2, base_i, base_i + 2, base_i + 3]);
-        *i_count += 4;
-    }
-
 pub fn add_ui_quad(&self, uv: &mut Vec<Vertex>, ui: &mut Vec<u32>, uoff: &mut u32, x: f32, y: f32, w: f32, h: f32, tex_index: u32) {
-        uv.push(Vertex{position:[x,y+h,0.0], tex_coords:[0.0,0.0], ao:1.0, tex_index, light: 1.0}); uv.push(Vertex{position:[x+w,y+h,0.0], tex_coords:[1.0,0.0], ao:1.0, tex_index, light: 1.0});
-        uv.push(Vertex{position:[x+w,y,0.0], tex_coords:[1.0,1.0], ao:1.0, tex_index, light: 1.0}); uv.push(Vertex{position:[x,y,0.0], tex_coords:[0.0,1.0], ao:1.0, tex_index, light: 1.0});
+        uv.push(Vertex{position:[x,y+h,0.0], tex_coords:[0.0,0.0], ao:1.0, tex_index, light: 1.0, tint: NO_TINT}); uv.push(Vertex{position:[x+w,y+h,0.0], tex_coords:[1.0,0.0], ao:1.0, tex_index, light: 1.0, tint: NO_TINT});
+        uv.push(Vertex{position:[x+w,y,0.0], tex_coords:[1.0,1.0], ao:1.0, tex_index, light: 1.0, tint: NO_TINT}); uv.push(Vertex{position:[x,y,0.0], tex_coords:[0.0,1.0], ao:1.0, tex_index, light: 1.0, tint: NO_TINT});
         ui.push(*uoff); ui.push(*uoff+1); ui.push(*uoff+2); ui.push(*uoff); ui.push(*uoff+2); ui.push(*uoff+3); *uoff += 4;
     }
 
@@ -950,8 +1018,7 @@ pub fn draw_text(&self, text: &str, start_x: f32, y: f32, scale: f32, v: &mut Ve
         }
     }
 pub fn render_multiplayer_menu(&mut self, menu: &mut crate::MainMenu, hosting: &crate::ngrok_utils::HostingManager, _width: u32, _height: u32) -> Result<(), wgpu::SurfaceError> {
-    let output = self.surface.get_current_texture()?;
-    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = self.frame.create_view(&wgpu::TextureViewDescriptor::default());
     let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Multiplayer") });
     let mut v: Vec<Vertex> = Vec::new(); let mut i: Vec<u32> = Vec::new(); let mut off = 0;
 
@@ -993,14 +1060,11 @@ pub fn render_multiplayer_menu(&mut self, menu: &mut crate::MainMenu, hosting: &
         rpass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
         rpass.draw_indexed(0..i.len() as u32, 0, 0..1);
     }
-    self.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
-    Ok(())
+    self.present(encoder)
 }
 
 pub fn render_main_menu(&mut self, menu: &MainMenu, _width: u32, _height: u32) -> Result<(), wgpu::SurfaceError> {
-    let output = self.surface.get_current_texture()?;
-    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = self.frame.create_view(&wgpu::TextureViewDescriptor::default());
     let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Menu") });
 
     let mut vertices: Vec<Vertex> = Vec::new();
@@ -1027,10 +1091,10 @@ pub fn render_main_menu(&mut self, menu: &MainMenu, _width: u32, _height: u32) -
         let rect = &btn.rect;
         
         // DIABOLICAL UV FIX: vs_ui shader handles atlas offsets. Buttons must pass 0.0-1.0 local UVs.
-        vertices.push(Vertex { position: [rect.x - rect.w / 2.0, rect.y - rect.h / 2.0, 0.0], tex_coords: [0.0, 1.0], ao: 1.0, tex_index: tex_id, light: 1.0 });
-        vertices.push(Vertex { position: [rect.x + rect.w / 2.0, rect.y - rect.h / 2.0, 0.0], tex_coords: [1.0, 1.0], ao: 1.0, tex_index: tex_id, light: 1.0 });
-        vertices.push(Vertex { position: [rect.x + rect.w / 2.0, rect.y + rect.h / 2.0, 0.0], tex_coords: [1.0, 0.0], ao: 1.0, tex_index: tex_id, light: 1.0 });
-        vertices.push(Vertex { position: [rect.x - rect.w / 2.0, rect.y + rect.h / 2.0, 0.0], tex_coords: [0.0, 0.0], ao: 1.0, tex_index: tex_id, light: 1.0 });
+        vertices.push(Vertex { position: [rect.x - rect.w / 2.0, rect.y - rect.h / 2.0, 0.0], tex_coords: [0.0, 1.0], ao: 1.0, tex_index: tex_id, light: 1.0, tint: NO_TINT });
+        vertices.push(Vertex { position: [rect.x + rect.w / 2.0, rect.y - rect.h / 2.0, 0.0], tex_coords: [1.0, 1.0], ao: 1.0, tex_index: tex_id, light: 1.0, tint: NO_TINT });
+        vertices.push(Vertex { position: [rect.x + rect.w / 2.0, rect.y + rect.h / 2.0, 0.0], tex_coords: [1.0, 0.0], ao: 1.0, tex_index: tex_id, light: 1.0, tint: NO_TINT });
+        vertices.push(Vertex { position: [rect.x - rect.w / 2.0, rect.y + rect.h / 2.0, 0.0], tex_coords: [0.0, 0.0], ao: 1.0, tex_index: tex_id, light: 1.0, tint: NO_TINT });
         indices.extend_from_slice(&[idx_offset, idx_offset + 1, idx_offset + 2, idx_offset, idx_offset + 2, idx_offset + 3]);
         idx_offset += 4;
 
@@ -1059,14 +1123,11 @@ rpass.set_pipeline(&self.ui_pipeline);
         rpass.draw_indexed(0..indices.len() as u32, 0, 0..1);
     }
 
-self.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
-    Ok(())
+self.present(encoder)
 }
 
 pub fn render_pause_menu(&mut self, menu: &MainMenu, world: &World, player: &Player, cursor_pos: (f64, f64), width: u32, height: u32) -> Result<(), wgpu::SurfaceError> {
-    let output = self.surface.get_current_texture()?;
-    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = self.frame.create_view(&wgpu::TextureViewDescriptor::default());
     let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Pause") });
 
     // Composite Call: Render the world FIRST into the SAME view and encoder
@@ -1108,35 +1169,96 @@ pub fn render_pause_menu(&mut self, menu: &MainMenu, world: &World, player: &Pla
         rpass.draw_indexed(0..indices.len() as u32, 0, 0..1);
     }
 
-    self.queue.submit(std::iter::once(encoder.finish()));
-    output.present();
-    Ok(())
+    self.present(encoder)
 }
 
 pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, cursor_pos: (f64, f64), _width: u32, _height: u32) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&TextureViewDescriptor::default());
+        let view = self.frame.create_view(&TextureViewDescriptor::default());
         let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Render Encoder") });
 
         self.render_internal(world, player, is_paused, cursor_pos, &view, &mut encoder);
 
+        self.present(encoder)
+    }
+
+    /// Submits a finished frame and, with a window, blits it onto the next swapchain image. The
+    /// frame is submitted even if the swapchain is lost, so `capture_frame` still sees it.
+    fn present(&mut self, mut encoder: CommandEncoder) -> Result<(), wgpu::SurfaceError> {
+        let Some(surface) = &self.surface else {
+            self.queue.submit(std::iter::once(encoder.finish()));
+            return Ok(());
+        };
+        let output = match surface.get_current_texture() {
+            Ok(output) => output,
+            Err(e) => {
+                self.queue.submit(std::iter::once(encoder.finish()));
+                return Err(e);
+            }
+        };
+        let view = output.texture.create_view(&TextureViewDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Blit Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment { view: &view, resolve_target: None, ops: Operations { load: LoadOp::Clear(Color::BLACK), store: StoreOp::Store } })],
+                depth_stencil_attachment: None, timestamp_writes: None, occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.blit_pipeline);
+            pass.set_bind_group(0, &self.frame_bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         Ok(())
     }
 
+    /// Reads the last rendered frame back from the GPU, blocking until it is finished.
+    /// Backs the F2 screenshot key and the golden-image tests.
+    pub fn capture_frame(&self) -> image::RgbaImage {
+        let (width, height) = (self.config.width, self.config.height);
+        let row_bytes = (width * 4).div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback = self.device.create_buffer(&BufferDescriptor { label: Some("Frame Readback"), size: (row_bytes * height) as u64, usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ, mapped_at_creation: false });
+        let mut encoder = self.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Capture Encoder") });
+        encoder.copy_texture_to_buffer(
+            self.frame.as_image_copy(),
+            ImageCopyBuffer { buffer: &readback, layout: ImageDataLayout { offset: 0, bytes_per_row: Some(row_bytes), rows_per_image: Some(height) } },
+            Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        slice.map_async(MapMode::Read, |_| {});
+        self.device.poll(Maintain::Wait);
+        let bgra = matches!(self.config.format, TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb);
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in slice.get_mapped_range().chunks(row_bytes as usize) {
+            for px in row[..(width * 4) as usize].chunks_exact(4) {
+                // Alpha is whatever blending left behind; the window shows the frame opaque
+                pixels.extend_from_slice(&if bgra { [px[2], px[1], px[0], 255] } else { [px[0], px[1], px[2], 255] });
+            }
+        }
+        readback.unmap();
+        image::RgbaImage::from_raw(width, height, pixels).expect("readback holds exactly one frame")
+    }
+
+    /// Pins the animation clock (water, dropped items) at `secs` and stops the FPS
+    /// counter, so the same scene always renders the same frame.
+    #[allow(dead_code)] // For the golden tests; the game's clock always runs
+    pub fn freeze_time(&mut self, secs: f32) {
+        self.frozen_time = Some(secs);
+    }
+
     /// DIABOLICAL RENDER CORE: Separated from presentation to allow composite passes (like Pause Menu)
     fn render_internal(&mut self, world: &World, player: &Player, is_paused: bool, cursor_pos: (f64, f64), view: &TextureView, encoder: &mut CommandEncoder) {
         // 1. FPS Calculation & Console Output
         self.frame_count += 1;
         let time_since_last = self.last_fps_time.elapsed();
-        if time_since_last.as_secs_f32() >= 1.0 {
+        if self.frozen_time.is_none() && time_since_last.as_secs_f32() >= 1.0 {
             self.fps = self.frame_count as f32 / time_since_last.as_secs_f32();
             
             // DIABOLICAL TELEMETRY SNAPSHOT: Hyper-Exhaustive high-density diagnostic
             let p = player.position; let v = player.velocity;
             let dt_val = time_since_last.as_secs_f32() / self.frame_count as f32;
-            log::info!("[STAT] FPS:{:<3.0} | DT:{:.4}s | CHK:{} | PND:{} | POS:({:.1},{:.1},{:.1}) | VEL:({:.2},{:.2},{:.2}) | GRD:{} FLY:{} SPR:{} | PTCL:{} | TRK:{} | DIRTY:{}", 
+            log::info!("[STAT] FPS:{:<3.0} | DT:{:.4}s | CHK:{} | PND:{} | POS:({:.1},{:.1},{:.1}) | VEL:({:.2},{:.2},{:.2}) | GRD:{} FLY:{} SPR:{} | PTCL:{} | TRK:{} | DIRTY:{} | BIO:{}", 
                 self.fps, 
                 dt_val,
                 self.chunk_meshes.len(), 
@@ -1148,7 +1270,8 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
                 if player.is_sprinting {'Y'} else {'N'},
                 self.particles.len(),
                 0, // Placeholder for TRK (Tracking) to match format string index
-                world.dirty_chunks.len() // Track pending mesh updates
+                world.dirty_chunks.len(), // Track pending mesh updates
+                world.get_biome(BlockPos { x: p.x.floor() as i32, y: p.y.floor() as i32, z: p.z.floor() as i32 }).map_or("-", |b| b.name())
             );
             
             self.frame_count = 0;
@@ -1163,21 +1286,19 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
         let p_cz = (player.position.z / 16.0).floor() as i32;
         
         let player_moved = (p_cx, p_cz) != (self.last_player_chunk.0, self.last_player_chunk.2);
-        let world_arc = Arc::new(world.clone());
 
         // A. Handle explicitly dirty chunks FIRST (Block breaking/placing)
         for &target in &world.dirty_chunks.clone() {
             if !self.pending_chunks.contains(&target) {
                 // Force immediate remesh for broken/placed blocks
-                self.pending_chunks.insert(target);
-                let _ = self.mesh_tx.send((target.0, target.1, target.2, 0, world_arc.clone()));
+                self.queue_mesh(world, target);
             }
         }
 
         // B. Handle background loading and movement (More aggressive for better coverage)
         if player_moved || self.frame_count % 15 == 0 { // Check every 15 frames instead of 30
             self.last_player_chunk = (p_cx, 0, p_cz);
-            let r_dist = 12; // Increased from 10 for better chunk loading
+            let r_dist = self.render_distance as i32;
             let max_vertical = crate::world::WORLD_HEIGHT / 16;
             
             for dx in -r_dist..=r_dist {
@@ -1186,14 +1307,17 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
                     for dy in 0..max_vertical {
                         let target = (p_cx + dx, dy, p_cz + dz);
                         if let Some(_c) = world.chunks.get(&target) {
-                            if !self.chunk_meshes.contains_key(&target) && !self.pending_chunks.contains(&target) {
-                                self.pending_chunks.insert(target);
-                                let _ = self.mesh_tx.send((target.0, target.1, target.2, 0, world_arc.clone()));
+                            let stale = self.chunk_meshes.get(&target).is_none_or(|(_, lod)| *lod != self.lod_of(target));
+                            if stale && !self.pending_chunks.contains(&target) {
+                                self.queue_mesh(world, target);
                             }
                         }
                     }
                 }
             }
+            // Meshes that fell out of range (chunk still loaded, e.g. render distance was lowered)
+            let keep = world.unload_distance();
+            self.chunk_meshes.retain(|k, _| (k.0 - p_cx) * (k.0 - p_cx) + (k.2 - p_cz) * (k.2 - p_cz) <= keep * keep);
         }
 
         // 3. Setup Uniforms
@@ -1202,21 +1326,16 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
         // DIABOLICAL FIX: camera_buffer is already written by main.rs via update_camera(). 
         // Removing redundant write to save PCIe bandwidth and GPU sync points.
         
-        let time = self.start_time.elapsed().as_secs_f32();
+        let time = self.frozen_time.unwrap_or_else(|| self.start_time.elapsed().as_secs_f32());
         let eye_bp = BlockPos { x: player.position.x.floor() as i32, y: (player.position.y + player.height * 0.4).floor() as i32, z: player.position.z.floor() as i32 };
         let is_underwater = if world.get_block(eye_bp).is_water() { 1.0f32 } else { 0.0f32 };
         
-        let noise_gen = crate::noise_gen::NoiseGenerator::new(world.seed); 
-        let (cont, eros, _weird, temp) = noise_gen.get_height_params(eye_bp.x, eye_bp.z);
-        let humid = noise_gen.get_noise_octaves(eye_bp.x as f64 * 0.01, 44.0, eye_bp.z as f64 * 0.01, 3) as f32;
-        let biome = noise_gen.get_biome(cont, eros, temp, humid, eye_bp.y);
-        let fog_color = match biome {
-            "swamp" => [0.3, 0.4, 0.2, 1.0], "desert" => [0.8, 0.7, 0.5, 1.0], "ice_plains" => [0.9, 0.9, 1.0, 1.0], _ => [0.5, 0.8, 0.9, 1.0],
-        };
+        let [r, g, b] = world.get_biome(eye_bp).unwrap_or_default().def().fog_color;
+        let fog_color = [r, g, b, 1.0];
         self.queue.write_buffer(&self.time_buffer, 0, bytemuck::cast_slice(&[fog_color[0], fog_color[1], fog_color[2], fog_color[3], time, is_underwater, 0.0, 0.0]));
 
         // 4. Entity Buffer Preparation
-        let mut ent_v = Vec::new(); let mut ent_i = Vec::new(); let mut ent_off = 0;
+        let mut ent_v = Vec::new(); let mut ent_i = Vec::new();
         
         // DIABOLICAL IN-WORLD BREAKING ANIMATION
         if self.break_progress > 0.0 {
@@ -1240,23 +1359,26 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
                     5 => ([[x+1.0,y,z-eps], [x,y,z-eps], [x,y+1.0,z-eps], [x+1.0,y+1.0,z-eps]], [[0.0,1.0],[1.0,1.0],[1.0,0.0],[0.0,0.0]]),
                     _ => ([[0.0;3];4], [[0.0;2];4]),
                 };
-                let base = ent_off;
-                for i in 0..4 { ent_v.push(Vertex { position: p[i], tex_coords: uv[i], ao: 1.0, tex_index: crack_tex, light: 15.0 }); }
-                ent_i.extend_from_slice(&[base, base+1, base+2, base, base+2, base+3]);
-                ent_off += 4;
+                for i in 0..4 { ent_v.push(Vertex { position: p[i], tex_coords: uv[i], ao: 1.0, tex_index: crack_tex, light: 15.0, tint: NO_TINT }); }
+                ent_i.extend_from_slice(&[0, 1, 2, 0, 2, 3]);
             }
         }
 
-        for rp in &world.remote_players {
-            for f in 0..6 { self.add_rotated_quad(&mut ent_v, &mut ent_i, &mut ent_off, [rp.position.x, rp.position.y, rp.position.z], rp.rotation, -0.3, 0.0, -0.3, 0.6, f, 13); }
-            for f in 0..6 { self.add_rotated_quad(&mut ent_v, &mut ent_i, &mut ent_off, [rp.position.x, rp.position.y+0.65, rp.position.z], rp.rotation, -0.3, 0.0, -0.3, 0.6, f, 13); }
-            for f in 0..6 { self.add_rotated_quad(&mut ent_v, &mut ent_i, &mut ent_off, [rp.position.x, rp.position.y+1.3, rp.position.z], rp.rotation, -0.25, 0.0, -0.25, 0.5, f, 13); }
-        }
-        for e in &world.entities {
+        // Instanced models: remote players, spinning dropped items and whatever was submitted
+        let light_at = |p: glam::Vec3| world.get_light_world(BlockPos { x: p.x.floor() as i32, y: (p.y + 0.5).floor() as i32, z: p.z.floor() as i32 }) as f32;
+        let mut models = std::mem::take(&mut self.queued_models);
+        models.extend(world.remote_players.iter().map(|rp| ModelInstance {
+            kind: ModelKind::Humanoid, id: rp.id, position: rp.position, yaw: rp.rotation, look_yaw: rp.rotation, look_pitch: rp.pitch,
+            gait: None, arm_pitch: 0.0, skin: SKIN_PLAYER, scale: 0.9, light: light_at(rp.position),
+        }));
+        models.extend(world.entities.iter().map(|e| {
             let (t, _, _) = e.item_type.get_texture_indices();
-            let rot = time * 1.5 + e.bob_offset; let by = ((time * 4.0 + e.bob_offset).sin() * 0.05) + 0.12;
-            for f in 0..6 { self.add_rotated_quad(&mut ent_v, &mut ent_i, &mut ent_off, [e.position.x, e.position.y+by, e.position.z], rot, -0.125, -0.125, -0.125, 0.25, f, t); }
-        }
+            let position = e.position + glam::Vec3::new(0.0, (time * 4.0 + e.bob_offset).sin() * 0.05 + 0.12, 0.0);
+            ModelInstance { kind: ModelKind::Item, id: 0, position, yaw: time * 1.5 + e.bob_offset, look_yaw: 0.0, look_pitch: 0.0, gait: None, arm_pitch: 0.0, skin: t, scale: 0.25, light: light_at(e.position) }
+        }));
+        let planes = player.get_frustum_planes(aspect);
+        models.retain(|m| gpu_cull::sphere_visible(&planes, m.position + glam::Vec3::new(0.0, m.scale, 0.0), 2.0 * m.scale));
+        self.models.prepare(&self.device, &self.queue, &models, time);
         
         // DIABOLICAL OPTIMIZATION: Stop re-creating GPU buffers every frame. 
         // Re-use buffers and only expand if necessary. This stops GPU driver hitching completely.
@@ -1272,11 +1394,42 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
             self.queue.write_buffer(&self.entity_index_buffer, 0, bytemuck::cast_slice(&ent_i));
         }
 
-        // 5. 3D Pass
+        // 5. GPU Culling: one row per chunk mesh and solid layer (all opaque rows, then all cutout
+        // rows), turned into indirect draws by the compute pass
+        self.resort_translucent();
+        let chunk_center = |(cx, cy, cz): (i32, i32, i32)| glam::Vec3::new(cx as f32 * 16.0 + 8.0, cy as f32 * 16.0 + 8.0, cz as f32 * 16.0 + 8.0);
+        let mut opaque_rows = 0;
+        if self.gpu_culling {
+            let mut rows = Vec::new();
+            for layer in [RenderLayer::Opaque, RenderLayer::Cutout] {
+                for (&key, (mesh, _)) in &self.chunk_meshes {
+                    let range = &mesh.layers[layer as usize];
+                    if range.is_empty() { continue; }
+                    rows.push(ChunkCullData { pos: chunk_center(key).extend(CHUNK_RADIUS).to_array(), index_count: range.len() as u32, base_vertex: mesh.slot.base_vertex(), base_index: mesh.slot.indices.start + range.start, _pad: 0 });
+                }
+                if layer == RenderLayer::Opaque { opaque_rows = rows.len() as u32; }
+            }
+            self.culler.upload(&self.device, &self.queue, &rows);
+            self.culler.dispatch(encoder);
+        }
+
+        // Shadow cascades: every opaque and cutout range, depth only, from the sun
+        let frustum = ViewFrustum { eye: self.eye, forward: self.forward, fov_y: FOV_Y_DEGREES.to_radians(), aspect };
+        self.shadow_passes = 0;
+        if self.shadows.update(&self.queue, &frustum, NEAR_PLANE, self.day_time) {
+            let casters: Vec<_> = self.chunk_meshes.iter()
+                .filter(|(_, (mesh, _))| mesh.layers[0].start < mesh.layers[1].end)
+                .map(|(&key, (mesh, _))| (chunk_center(key), mesh.slot.indices.start + mesh.layers[0].start..mesh.slot.indices.start + mesh.layers[1].end, mesh.slot.base_vertex()))
+                .collect();
+            self.shadows.render(encoder, &self.bind_group, &self.arena, &casters);
+            self.shadow_passes = self.shadows.cascade_count;
+        }
+
+        // 6. 3D Pass
         {
             let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("3D Pass"), 
-                color_attachments: &[Some(RenderPassColorAttachment { view: &view, resolve_target: None, ops: Operations { load: LoadOp::Clear(Color { r: fog_color[0] as f64, g: fog_color[1] as f64, b: fog_color[2] as f64, a: 1.0 }), store: StoreOp::Store } })], 
+                color_attachments: &[Some(RenderPassColorAttachment { view: self.post.hdr_view(), resolve_target: None, ops: Operations { load: LoadOp::Clear(Color { r: fog_color[0] as f64, g: fog_color[1] as f64, b: fog_color[2] as f64, a: 1.0 }), store: StoreOp::Store } })], 
                 depth_stencil_attachment: Some(RenderPassDepthStencilAttachment { view: &self.depth_texture, depth_ops: Some(Operations { load: LoadOp::Clear(1.0), store: StoreOp::Store }), stencil_ops: None }), 
                 timestamp_writes: None, occlusion_query_set: None 
             });
@@ -1285,40 +1438,28 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
             pass.set_bind_group(0, &self.bind_group, &[]); 
             pass.set_bind_group(1, &self.camera_bind_group, &[]); 
             pass.set_bind_group(2, &self.time_bind_group, &[]);
-
-            // Draw World with FRUSTUM CULLING (Pre-extract normals for raw speed)
-            let planes = player.get_frustum_planes(aspect);
-            let p_normals: [glam::Vec3; 6] = [
-                glam::Vec3::from_slice(&planes[0][0..3]), glam::Vec3::from_slice(&planes[1][0..3]),
-                glam::Vec3::from_slice(&planes[2][0..3]), glam::Vec3::from_slice(&planes[3][0..3]),
-                glam::Vec3::from_slice(&planes[4][0..3]), glam::Vec3::from_slice(&planes[5][0..3]),
-            ];
-
-            for (&(cx, cy, cz), (mesh, _)) in &self.chunk_meshes {
-                if cx == 999 { continue; }
-                
-                let center = glam::Vec3::new(cx as f32 * 16.0 + 8.0, cy as f32 * 16.0 + 8.0, cz as f32 * 16.0 + 8.0);
-                let radius = 14.0; 
-                let mut visible = true;
-                for i in 0..6 {
-                    if p_normals[i].dot(center) + planes[i][3] < -radius {
-                        visible = false;
-                        break;
+            pass.set_bind_group(3, &self.shadows.bind_group, &[]);
+
+            // Draw World: every chunk mesh sits in the arena, so bind it once
+            pass.set_vertex_buffer(0, self.arena.vertex_buffer.slice(..));
+            pass.set_index_buffer(self.arena.index_buffer.slice(..), IndexFormat::Uint32);
+            let visible: Vec<_> = self.chunk_meshes.iter()
+                .filter(|(&key, _)| gpu_cull::sphere_visible(&planes, chunk_center(key), CHUNK_RADIUS))
+                .collect();
+
+            // Opaque, then alpha-tested cutout; both write depth
+            for (layer, pipeline) in [(RenderLayer::Opaque, &self.pipeline), (RenderLayer::Cutout, &self.cutout_pipeline)] {
+                pass.set_pipeline(pipeline);
+                if self.gpu_culling {
+                    let (first, count) = if layer == RenderLayer::Opaque { (0, opaque_rows) } else { (opaque_rows, self.culler.chunk_count - opaque_rows) };
+                    if count > 0 { pass.multi_draw_indexed_indirect(&self.culler.indirect_draw_buffer, first as u64 * std::mem::size_of::<gpu_cull::DrawIndexedIndirect>() as u64, count); }
+                } else {
+                    // CPU FRUSTUM CULLING fallback
+                    for (_, (mesh, _)) in &visible {
+                        let range = &mesh.layers[layer as usize];
+                        if !range.is_empty() { pass.draw_indexed(mesh.slot.indices.start + range.start..mesh.slot.indices.start + range.end, mesh.slot.base_vertex(), 0..1); }
                     }
                 }
-
-                if visible {
-                    pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-                    pass.set_index_buffer(mesh.index_buffer.slice(..), IndexFormat::Uint32);
-                    pass.draw_indexed(0..mesh.total_indices, 0, 0..1);
-                }
-            }
-
-            // Draw Clouds
-            if let Some((m, _)) = self.chunk_meshes.get(&(999, 999, 999)) {
-                pass.set_vertex_buffer(0, m.vertex_buffer.slice(..));
-                pass.set_index_buffer(m.index_buffer.slice(..), IndexFormat::Uint32);
-                pass.draw_indexed(0..m.total_indices, 0, 0..1);
             }
 
             // Draw Entities
@@ -1327,9 +1468,27 @@ pub fn render_game(&mut self, world: &World, player: &Player, is_paused: bool, c
                 pass.set_index_buffer(self.entity_index_buffer.slice(..), IndexFormat::Uint32); 
                 pass.draw_indexed(0..ent_i.len() as u32, 0, 0..1); 
             }
+            self.models.draw(&mut pass);
+
+            // Clouds are far above nearly all water, so they go before the translucent chunks
+            self.clouds.draw(&mut pass);
+
+            // Translucent last, farthest chunk first, blended over everything without writing depth
+            let mut translucent: Vec<_> = visible.iter().filter(|(_, (m, _))| !m.layers[2].is_empty()).collect();
+            translucent.sort_by(|a, b| chunk_center(*b.0).distance_squared(self.eye).total_cmp(&chunk_center(*a.0).distance_squared(self.eye)));
+            if !translucent.is_empty() {
+                pass.set_pipeline(&self.translucent_pipeline);
+                pass.set_vertex_buffer(0, self.arena.vertex_buffer.slice(..));
+                pass.set_index_buffer(self.arena.index_buffer.slice(..), IndexFormat::Uint32);
+                for (_, (mesh, _)) in translucent {
+                    let range = &mesh.layers[RenderLayer::Translucent as usize];
+                    pass.draw_indexed(mesh.slot.indices.start + range.start..mesh.slot.indices.start + range.end, mesh.slot.base_vertex(), 0..1);
+                }
+            }
         }
+        self.post.run(encoder, &self.queue, view, is_underwater > 0.5, time);
 
-       // 6. UI Logic & Render Pass
+       // 7. UI Logic & Render Pass
         let mut uv = Vec::new(); let mut ui = Vec::new(); let mut uoff = 0;
         
         // Crosshair
@@ -19,7 +19,7 @@ pub struct ResourceLimits {
 impl Default for ResourceLimits {
     fn default() -> Self {
         Self {
-            max_chunks: 2000,           // Maximum number of loaded chunks
+            max_chunks: 30000,          // Maximum number of loaded sections (8 per column); view distances are capped to fit, at 33 columns with this budget
             max_entities: 1000,          // Maximum number of entities
             max_particles: 5000,        // Maximum number of particles
             max_pending_tasks: 100,     // Maximum pending mesh tasks
@@ -0,0 +1,410 @@
+//! Dedicated server
+//!
+//! `Server` owns the authoritative `World`. Every tick it accepts new connections, applies and
+//! relays what clients sent, runs the game tick and item physics, and keeps the terrain around
+//! each player (and spawn) loaded. Each connection has a reader thread feeding one shared inbox
+//! and a writer thread draining its own outbox, so a slow client never stalls the tick. The id a
+//! player is known by goes out in its handshake, and everyone hears when players join or leave.
+//!
+//! Players move by sending input, not positions: the server runs each command through the same
+//! `Player` physics the client predicted it with and sends everyone a snapshot of every player
+//! whose state changed that tick (see `prediction`). It decides where players spawn and respawn
+//! itself, and each session only gets as much simulated time as real time has passed. Nothing a
+//! client sends makes its player fly, pass through blocks or move faster: the server's copy walks
+//! with the default abilities, whatever the commands claim.
+//!
+//! Clients don't generate terrain: every player is sent the real columns within view distance,
+//! nearest first, and told to drop the ones they've left behind. Each session remembers which
+//! columns it has, and streaming is throttled per tick and by how far the client's outbox is
+//! behind, so joining next to a big build doesn't flood the connection. Blocks the game tick
+//! changes in a column a client already has follow as `BlockUpdate`s.
+//!
+//! Nothing here touches wgpu, winit or rodio: the `server` binary builds with
+//! `--no-default-features` and just calls `tick` at `TICKS_PER_SECOND`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use glam::Vec3;
+
+use crate::config_system::NetworkConfig;
+use crate::network::Packet;
+use crate::player::Player;
+use crate::prediction::PlayerSnapshot;
+use crate::protocol::{write_packet, PacketReader};
+use crate::world::{BlockPos, World};
+use crate::AudioSystem;
+
+/// Time per tick spent loading or generating columns, nearest first (at least one column always
+/// goes through). Terrain fills in around a new player over a few seconds instead of stalling
+/// everyone else's tick.
+const TERRAIN_BUDGET: Duration = Duration::from_millis(20);
+const SPAWN_COLUMN: (i32, i32) = (0, 0);
+/// Columns sent to one client per tick at most.
+const COLUMNS_PER_TICK: usize = 4;
+/// No more columns go out while this many packets are still queued for the client.
+const MAX_BACKLOG: usize = 32;
+/// Simulated time a session can bank while its input is delayed, in seconds. Commands beyond what
+/// real time allows are acknowledged without being run, so the client snaps back.
+const MAX_INPUT_AHEAD: f32 = 0.5;
+/// How far from the middle of the spawn column to look for dry ground to spawn on, in blocks.
+const SPAWN_SEARCH: i32 = 16;
+
+struct Session {
+    addr: SocketAddr,
+    outbox: Sender<Packet>,
+    /// The server's copy of the player, moved only by its input commands.
+    player: Player,
+    /// Last input command acknowledged; 0 until the first one.
+    ack: u32,
+    /// False until the spawn column is there to put the player on.
+    spawned: bool,
+    /// Seconds of input this session may still simulate, and when that was last topped up.
+    input_budget: f32,
+    budget_at: Instant,
+    /// Last snapshot sent of this player; unchanged players aren't sent again.
+    last_state: Option<PlayerSnapshot>,
+    /// Column the player is standing in; None until it has been placed at spawn.
+    column: Option<(i32, i32)>,
+    /// Columns this client has been sent and not told to unload.
+    sent: HashSet<(i32, i32)>,
+}
+
+/// What a reader thread reports: a packet from the client, or None once the connection is gone.
+type Inbound = (u32, Option<Packet>);
+
+pub struct Server {
+    pub world: World,
+    config: NetworkConfig,
+    listener: TcpListener,
+    sessions: BTreeMap<u32, Session>,
+    inbox: Receiver<Inbound>,
+    inbox_tx: Sender<Inbound>,
+    next_id: u32,
+    /// Set when a player changes column, so chunks out of everyone's range get unloaded.
+    centers_moved: bool,
+    audio: AudioSystem,
+}
+
+impl Server {
+    /// Listens on `config.server_port` on every interface (0 picks a free port).
+    pub fn bind(mut world: World, config: NetworkConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", config.server_port))?;
+        listener.set_nonblocking(true)?;
+        let max_chunks = crate::resource_manager::get_resource_manager().limits().max_chunks;
+        world.view_distance = (config.view_distance as i32).min(World::max_view_distance(max_chunks));
+        world.ticks.record_changes();
+        let (inbox_tx, inbox) = unbounded();
+        Ok(Self { world, config, listener, sessions: BTreeMap::new(), inbox, inbox_tx, next_id: 1, centers_moved: false, audio: AudioSystem::silent() })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Connected players as (id, address).
+    pub fn players(&self) -> Vec<(u32, SocketAddr)> {
+        self.sessions.iter().map(|(&id, s)| (id, s.addr)).collect()
+    }
+
+    /// One server step of `dt` seconds.
+    pub fn tick(&mut self, dt: f32) {
+        self.accept();
+        while let Ok((id, packet)) = self.inbox.try_recv() {
+            match packet {
+                Some(packet) => self.handle(id, packet),
+                None => self.drop_session(id),
+            }
+        }
+        self.world.update_ticks(dt);
+        self.send_tick_changes();
+        // Inventories live on the clients, so nothing collects items here; they just settle and despawn
+        self.world.update_items(dt, |_, _| false);
+        self.load_terrain();
+        self.spawn_players();
+        self.stream_columns();
+        for snapshot in self.snapshots() { self.broadcast(&Packet::PlayerState(snapshot), None); }
+    }
+
+    /// Writes every modified chunk to disk; returns how many were saved.
+    pub fn save(&mut self) -> usize {
+        self.world.save_modified()
+    }
+
+    /// Tells every client the server is going away and closes their connections. Doesn't save.
+    pub fn shutdown(&mut self) {
+        self.broadcast(&Packet::Disconnect, None);
+        self.sessions.clear();
+    }
+
+    fn accept(&mut self) {
+        loop {
+            let (stream, addr) = match self.listener.accept() {
+                Ok(connection) => connection,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    log::warn!("[SERVER] Accept failed: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = self.open_session(stream, addr) {
+                log::warn!("[SERVER] Could not set up connection from {}: {}", addr, e);
+            }
+        }
+    }
+
+    fn open_session(&mut self, mut stream: TcpStream, addr: SocketAddr) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        if self.sessions.len() >= self.config.max_players as usize {
+            log::info!("[SERVER] Refused {}: server full ({} players)", addr, self.config.max_players);
+            return write_packet(&mut stream, &Packet::Disconnect);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        write_packet(&mut stream, &Packet::Handshake { username: "Server".to_string(), seed: self.world.seed, id })?;
+        write_packet(&mut stream, &Packet::ServerInfo { motd: self.config.motd.clone(), players: self.sessions.len() as u32 + 1, max_players: self.config.max_players })?;
+        for &other in self.sessions.keys() {
+            write_packet(&mut stream, &Packet::PlayerJoin { id: other })?;
+        }
+        let reader = stream.try_clone()?;
+        let inbox = self.inbox_tx.clone();
+        thread::spawn(move || {
+            let mut reader = PacketReader::new(reader);
+            loop {
+                match reader.read_packet() {
+                    Ok(Some(packet)) => match packet.validate() {
+                        Ok(()) => if inbox.send((id, Some(packet))).is_err() { return; },
+                        Err(e) => log::warn!("[SERVER] Invalid packet from player {}: {}", id, e),
+                    },
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::warn!("[SERVER] Dropping player {}: {}", id, e);
+                        break;
+                    }
+                }
+            }
+            let _ = inbox.send((id, None));
+        });
+
+        let (outbox, queue) = unbounded::<Packet>();
+        thread::spawn(move || {
+            let mut stream = stream;
+            while let Ok(packet) = queue.recv() {
+                if let Err(e) = write_packet(&mut stream, &packet) {
+                    log::debug!("[SERVER] Write to player {} failed: {:?}", id, e);
+                    break;
+                }
+            }
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        });
+
+        log::info!("[SERVER] Player {} joined from {} ({}/{})", id, addr, self.sessions.len() + 1, self.config.max_players);
+        self.broadcast(&Packet::PlayerJoin { id }, None);
+        // The newcomer has to see everyone, including those standing still
+        for session in self.sessions.values_mut() { session.last_state = None; }
+        let mut player = Player::new();
+        player.invincible_timer = f32::INFINITY; // Health is tracked by the client; this copy only moves
+        let session = Session {
+            addr, outbox, player, ack: 0, spawned: false, input_budget: MAX_INPUT_AHEAD, budget_at: Instant::now(),
+            last_state: None, column: None, sent: HashSet::new(),
+        };
+        self.sessions.insert(id, session);
+        Ok(())
+    }
+
+    fn drop_session(&mut self, id: u32) {
+        if let Some(session) = self.sessions.remove(&id) {
+            log::info!("[SERVER] Player {} ({}) left ({}/{})", id, session.addr, self.sessions.len(), self.config.max_players);
+            self.centers_moved = true;
+            self.broadcast(&Packet::PlayerLeave { id }, None);
+        }
+    }
+
+    fn handle(&mut self, id: u32, packet: Packet) {
+        match packet {
+            Packet::PlayerInput { commands } => {
+                let spawn = self.spawn_point();
+                let Some(session) = self.sessions.get_mut(&id) else { return };
+                // Not placed yet: the client resends whatever isn't acknowledged
+                if !session.spawned { return; }
+                let now = Instant::now();
+                session.input_budget = (session.input_budget + now.duration_since(session.budget_at).as_secs_f32()).min(MAX_INPUT_AHEAD);
+                session.budget_at = now;
+                // Every packet repeats the commands not acknowledged yet; apply each one once
+                for command in &commands {
+                    if command.seq <= session.ack { continue; }
+                    session.ack = command.seq;
+                    if command.respawn {
+                        if let Some(spawn) = spawn { session.player.position = spawn; }
+                        session.player.velocity = Vec3::ZERO;
+                    }
+                    if command.dt > session.input_budget { continue; }
+                    session.input_budget -= command.dt;
+                    let at = session.player.position;
+                    let column = ((at.x / 16.0).floor() as i32, (at.z / 16.0).floor() as i32);
+                    session.player.stasis = !self.world.chunks.contains_key(&(column.0, 0, column.1));
+                    command.simulate(&mut session.player, &self.world, &self.audio);
+                }
+                let position = session.player.position;
+                let column = ((position.x / 16.0).floor() as i32, (position.z / 16.0).floor() as i32);
+                if session.column != Some(column) { self.centers_moved = true; }
+                session.column = Some(column);
+            }
+            Packet::BlockUpdate { pos, block } => {
+                self.world.place_block(pos, block);
+                self.broadcast(&Packet::BlockUpdate { pos, block }, Some(id));
+            }
+            Packet::Disconnect => {
+                // The reader thread reports the closed connection, which ends the session
+                if let Some(session) = self.sessions.get(&id) { let _ = session.outbox.send(Packet::Disconnect); }
+            }
+            // Players move by input here; a client can't just say where it is
+            Packet::PlayerMove { .. } => {}
+            Packet::Handshake { .. } | Packet::ServerInfo { .. } | Packet::ChunkColumn { .. } | Packet::UnloadColumn { .. }
+            | Packet::PlayerJoin { .. } | Packet::PlayerLeave { .. } | Packet::PlayerState(_) => {}
+        }
+    }
+
+    fn broadcast(&self, packet: &Packet, except: Option<u32>) {
+        for (&id, session) in &self.sessions {
+            if Some(id) != except { let _ = session.outbox.send(packet.clone()); }
+        }
+    }
+
+    /// Sends what the game tick changed (fluids, crops, falling blocks...) to everyone holding
+    /// the column. Columns not sent yet go out with the change already in them.
+    fn send_tick_changes(&mut self) {
+        let mut seen = HashSet::new();
+        for pos in self.world.ticks.take_changes() {
+            if !seen.insert(pos) { continue; }
+            let column = (pos.x.div_euclid(16), pos.z.div_euclid(16));
+            let packet = Packet::BlockUpdate { pos, block: self.world.get_block(pos) };
+            for session in self.sessions.values().filter(|s| s.sent.contains(&column)) {
+                let _ = session.outbox.send(packet.clone());
+            }
+        }
+    }
+
+    /// Where every placed player whose position, look or acknowledged command changed since the
+    /// last call is now.
+    fn snapshots(&mut self) -> Vec<PlayerSnapshot> {
+        let mut changed = Vec::new();
+        for (&id, session) in self.sessions.iter_mut().filter(|(_, s)| s.spawned) {
+            let snapshot = PlayerSnapshot::of(id, session.ack, &session.player);
+            if session.last_state.replace(snapshot) != Some(snapshot) { changed.push(snapshot); }
+        }
+        changed
+    }
+
+    /// Dry ground nearest the middle of the spawn column, once that column is decorated.
+    fn spawn_point(&self) -> Option<Vec3> {
+        if !self.world.decorated_columns.contains(&SPAWN_COLUMN) { return None; }
+        let (ox, oz) = (SPAWN_COLUMN.0 * 16 + 8, SPAWN_COLUMN.1 * 16 + 8);
+        for r in 0..=SPAWN_SEARCH {
+            for x in -r..=r {
+                for z in -r..=r {
+                    if x.abs() != r && z.abs() != r { continue; }
+                    let (x, z) = (ox + x, oz + z);
+                    let y = self.world.get_height_at(x, z);
+                    let ground = self.world.get_block(BlockPos { x, y, z });
+                    if ground.is_solid() && !ground.is_water() { return Some(Vec3::new(x as f32 + 0.5, y as f32 + 2.5, z as f32 + 0.5)); }
+                }
+            }
+        }
+        Some(Vec3::new(ox as f32 + 0.5, self.world.get_height_at(ox, oz) as f32 + 2.5, oz as f32 + 0.5))
+    }
+
+    /// Puts players waiting to join at spawn once it's there; their first snapshot tells them.
+    fn spawn_players(&mut self) {
+        let Some(spawn) = self.spawn_point() else { return };
+        for session in self.sessions.values_mut().filter(|s| !s.spawned) {
+            session.player.position = spawn;
+            session.player.velocity = Vec3::ZERO;
+            session.spawned = true;
+            let column = ((spawn.x / 16.0).floor() as i32, (spawn.z / 16.0).floor() as i32);
+            if session.column != Some(column) { self.centers_moved = true; }
+            session.column = Some(column);
+        }
+    }
+
+    /// Columns every player (and spawn) is standing in.
+    fn centers(&self) -> Vec<(i32, i32)> {
+        let mut centers: Vec<_> = std::iter::once(SPAWN_COLUMN).chain(self.sessions.values().filter_map(|s| s.column)).collect();
+        centers.sort_unstable();
+        centers.dedup();
+        centers
+    }
+
+    /// Loads or generates the nearest missing columns around any centre, and unloads what nobody
+    /// is near any more. Loads one column past the view distance: a column can only be streamed
+    /// once it's decorated, which needs its neighbours.
+    fn load_terrain(&mut self) {
+        let centers = self.centers();
+        if std::mem::take(&mut self.centers_moved) {
+            let max_chunks = crate::resource_manager::get_resource_manager().limits().max_chunks;
+            self.world.unload_chunks_outside(&centers, max_chunks);
+        }
+        let needed = self.world.needed_distance();
+        let radius = needed as i32;
+        let mut missing = HashSet::new();
+        for &(cx, cz) in &centers {
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    if (x * x + z * z) as f32 > needed * needed { continue; }
+                    if !self.world.chunks.contains_key(&(cx + x, 0, cz + z)) { missing.insert((cx + x, cz + z)); }
+                }
+            }
+        }
+        let dist = |&(x, z): &(i32, i32)| centers.iter().map(|&(cx, cz)| (x - cx) * (x - cx) + (z - cz) * (z - cz)).min().unwrap_or(0);
+        let mut missing: Vec<_> = missing.into_iter().collect();
+        missing.sort_unstable_by_key(|c| (dist(c), *c));
+        let started = Instant::now();
+        for (cx, cz) in missing {
+            self.world.ensure_column(cx, cz);
+            if started.elapsed() >= TERRAIN_BUDGET { break; }
+        }
+        // The server has no meshes to rebuild
+        self.world.dirty_chunks.clear();
+    }
+
+    /// Per client: unload columns out of range, then send the nearest finished ones it lacks.
+    fn stream_columns(&mut self) {
+        let view = self.world.view_distance;
+        let keep = self.world.unload_distance();
+        for session in self.sessions.values_mut() {
+            let (cx, cz) = session.column.unwrap_or(SPAWN_COLUMN);
+            let dist = |&(x, z): &(i32, i32)| (x - cx) * (x - cx) + (z - cz) * (z - cz);
+
+            let mut gone: Vec<_> = session.sent.iter().filter(|c| dist(c) > keep * keep).copied().collect();
+            gone.sort_unstable();
+            for (x, z) in gone {
+                session.sent.remove(&(x, z));
+                let _ = session.outbox.send(Packet::UnloadColumn { cx: x, cz: z });
+            }
+
+            let mut wanted = Vec::new();
+            for x in cx - view..=cx + view {
+                for z in cz - view..=cz + view {
+                    let column = (x, z);
+                    if dist(&column) <= view * view && !session.sent.contains(&column) && self.world.decorated_columns.contains(&column) { wanted.push(column); }
+                }
+            }
+            wanted.sort_unstable_by_key(|c| (dist(c), *c));
+            for (x, z) in wanted.into_iter().take(COLUMNS_PER_TICK) {
+                if session.outbox.len() >= MAX_BACKLOG { break; }
+                match Packet::chunk_column(&self.world, x, z) {
+                    Ok(packet) => {
+                        session.sent.insert((x, z));
+                        let _ = session.outbox.send(packet);
+                    }
+                    Err(e) => log::warn!("[SERVER] Could not encode column {:?}: {}", (x, z), e),
+                }
+            }
+        }
+    }
+}
@@ -20,6 +20,7 @@ struct VertexInput {
     @location(2) ao: f32,
     @location(3) tex_index: u32,
     @location(4) light: f32,
+    @location(5) tint: vec4<f32>, // Biome colour for grass and leaves, white otherwise
 };
 
 struct VertexOutput {
@@ -29,6 +30,8 @@ struct VertexOutput {
     @location(2) depth: f32,
     @location(3) light: f32,
     @location(4) tex_index: u32,
+    @location(5) tint: vec3<f32>,
+    @location(6) world_pos: vec3<f32>,
 };
 
 @vertex
@@ -41,7 +44,9 @@ fn vs_main(model: VertexInput) -> VertexOutput {
     let light_level = f32(model.light) / 15.0;
     out.light = max(0.1, light_level);
     out.tex_index = model.tex_index;
+    out.tint = model.tint.rgb;
     out.tex_coords = model.tex_coords; // Pass raw world-scale coordinates for tiling
+    out.world_pos = model.position;
     return out;
 }
 
@@ -50,8 +55,7 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+fn sample_block(in: VertexOutput) -> vec4<f32> {
     // DIABOLICAL FRAGMENT TILING: Calculate atlas UVs here to support large greedy quads
     let atlas_size = 32.0; 
     let u_step = 1.0 / atlas_size;
@@ -71,7 +75,12 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         (row + v_clamped) * v_step
     );
 
-    var base_color = textureSample(t_diffuse, s_diffuse, atlas_uv);
+    return textureSample(t_diffuse, s_diffuse, atlas_uv);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var base_color = sample_block(in);
     
     // DIABOLICAL OVERLAY FIX: If alpha is low but not zero, allow blending for cracks.
     if (base_color.a < 0.01) { discard; }
@@ -80,10 +89,68 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     if (in.tex_index == 9u) {
         base_color.a = 0.7;
     }
-    
+    return shade(in, base_color);
+}
+
+// Leaves and plants: alpha-tested against the texture and drawn fully opaque, so they can write depth
+@fragment
+fn fs_cutout(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base_color = sample_block(in);
+    if (base_color.a < 0.5) { discard; }
+    return shade(in, vec4<f32>(base_color.rgb, 1.0));
+}
+
+// --- CASCADED SUN SHADOWS ---
+struct ShadowUniform {
+    light_view_proj: array<mat4x4<f32>, 4>,
+    splits: vec4<f32>, // View depth where each cascade ends
+    light_dir: vec4<f32>, // xyz towards the light, w shadow strength
+    cascade_count: u32,
+    map_size: f32,
+};
+@group(3) @binding(0)
+var shadow_map: texture_depth_2d_array;
+@group(3) @binding(1)
+var shadow_sampler: sampler_comparison;
+@group(3) @binding(2)
+var<uniform> shadow: ShadowUniform;
+
+// Fraction of sunlight reaching `world_pos`: the cascade comes from the view depth, then a 3x3 PCF
+// kernel softens the edge
+fn sun_visibility(world_pos: vec3<f32>, view_depth: f32) -> f32 {
+    if (shadow.cascade_count == 0u || shadow.light_dir.w <= 0.0) { return 1.0; }
+    var cascade = shadow.cascade_count;
+    for (var i = 0u; i < shadow.cascade_count; i++) {
+        if (view_depth < shadow.splits[i]) { cascade = i; break; }
+    }
+    if (cascade == shadow.cascade_count) { return 1.0; }
+
+    let p = shadow.light_view_proj[cascade] * vec4<f32>(world_pos, 1.0);
+    let uv = p.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    let texel = 1.0 / shadow.map_size;
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + vec2<f32>(f32(x), f32(y)) * texel, cascade, p.z);
+        }
+    }
+    return lit / 9.0;
+}
+
+// Lava, fire and torches are pushed past white, which is what the bloom pass picks up
+fn emission(tex_index: u32) -> f32 {
+    switch tex_index {
+        case 200u, 201u: { return 1.5; }
+        case 20u: { return 0.8; }
+        default: { return 0.0; }
+    }
+}
+
+fn shade(in: VertexOutput, base_color: vec4<f32>) -> vec4<f32> {
 // --- VOXEL LIGHTING ---
-    let brightness = in.light; 
-    var lit_color = base_color.rgb * in.ao * brightness;
+    let shadowed = (1.0 - sun_visibility(in.world_pos, in.depth)) * shadow.light_dir.w;
+    let brightness = in.light * (1.0 - 0.45 * shadowed);
+    var lit_color = base_color.rgb * in.tint * in.ao * brightness * (1.0 + emission(in.tex_index));
     
 // DIABOLICAL CLOUD SHADOWS REMOVED: Caused flickering "dark circles" on blocks
     let _shadow_x = in.tex_coords.x * 100.0;
@@ -94,13 +161,24 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // DIABOLICAL ATMOSPHERIC PERSPECTIVE: Increase fog for that surreal "Endless Monolith" look
     lit_color = mix(lit_color, time_data.sky_color.rgb, clamp(fog_factor * 1.2, 0.0, 1.0));
 
-    // Underwater Tint
-    if (time_data.underwater > 0.5) {
-        lit_color = mix(lit_color, vec3<f32>(0.0, 0.2, 0.8), 0.4);
-    }
-
     return vec4<f32>(lit_color, base_color.a);
 }
+// Shadow depth pass: terrain seen from the light, with the cascade's matrix bound as the camera
+@vertex
+fn vs_shadow(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    out.tex_coords = model.tex_coords;
+    out.tex_index = model.tex_index;
+    return out;
+}
+
+// Leaves and plants only cast shadows where their texture is solid
+@fragment
+fn fs_shadow(in: VertexOutput) {
+    if (sample_block(in).a < 0.5) { discard; }
+}
+
 // [ADD TO THE VERY END OF FILE]
 
 @vertex
@@ -111,6 +189,7 @@ fn vs_ui(model: VertexInput) -> VertexOutput {
     out.depth = 0.0;
     out.ao = 1.0;
     out.tex_index = model.tex_index;
+    out.tint = vec3<f32>(1.0);
     
     // Texture Atlas Logic (Decoupled from Camera)
     let atlas_size = 32.0;
@@ -134,61 +213,126 @@ let color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
     return color;
 }
 
-// --- DIABOLICAL COMPUTE CULLER ---
+// --- INSTANCED ENTITIES ---
+// Model vertices are baked once per model (entity_models.rs); each instance places one entity and
+// carries its limb angles, and the parts are turned about their pivots here.
+struct ModelVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) pivot: vec3<f32>,
+    @location(2) tex_coords: vec2<f32>,
+    @location(3) tile: u32,
+    @location(4) limb: u32, // 0 body, 1 head, 2/3 right/left arm, 4/5 right/left leg
+    @location(5) shade: f32,
+};
+
+struct EntityInstanceInput {
+    @location(6) transform_0: vec4<f32>,
+    @location(7) transform_1: vec4<f32>,
+    @location(8) transform_2: vec4<f32>,
+    @location(9) transform_3: vec4<f32>,
+    @location(10) pose: vec4<f32>, // leg swing, arm pitch, head yaw, head pitch
+    @location(11) skin: u32,
+    @location(12) light: f32,
+};
+
+// Positive angles swing a limb towards +X, the way models face
+fn rotate_z(a: f32) -> mat3x3<f32> {
+    let c = cos(a); let s = sin(a);
+    return mat3x3<f32>(vec3<f32>(c, s, 0.0), vec3<f32>(-s, c, 0.0), vec3<f32>(0.0, 0.0, 1.0));
+}
+
+fn rotate_y(a: f32) -> mat3x3<f32> {
+    let c = cos(a); let s = sin(a);
+    return mat3x3<f32>(vec3<f32>(c, 0.0, -s), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(s, 0.0, c));
+}
+
+@vertex
+fn vs_entity(model: ModelVertexInput, instance: EntityInstanceInput) -> VertexOutput {
+    let pose = instance.pose;
+    var limb = mat3x3<f32>(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.0, 0.0, 1.0));
+    switch model.limb {
+        case 1u: { limb = rotate_y(-pose.z) * rotate_z(pose.w); }
+        case 2u: { limb = rotate_z(pose.y - pose.x); }
+        case 3u: { limb = rotate_z(pose.y + pose.x); }
+        case 4u: { limb = rotate_z(pose.x); }
+        case 5u: { limb = rotate_z(-pose.x); }
+        default: {}
+    }
+    let local = limb * (model.position - model.pivot) + model.pivot;
+    let transform = mat4x4<f32>(instance.transform_0, instance.transform_1, instance.transform_2, instance.transform_3);
+    let world = transform * vec4<f32>(local, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * world;
+    out.depth = out.clip_position.w;
+    out.ao = model.shade;
+    out.light = max(0.1, instance.light / 15.0);
+    out.tex_index = instance.skin + model.tile;
+    out.tex_coords = model.tex_coords;
+    out.tint = vec3<f32>(1.0);
+    out.world_pos = world.xyz;
+    return out;
+}
+
+// --- CLOUDS ---
+// Every cloud cell is an instance of the same unit cube (clouds.rs); faces the instance masks out
+// (where a neighbouring cell hides them) collapse to a point and are never rasterised.
+struct CloudVertexInput {
+    @location(0) corner: vec3<f32>,
+    @location(1) face: u32,
+    @location(2) shade: f32,
+};
 
-struct ChunkCullData {
-    pos_radius: vec4<f32>,
-    index_count: u32,
-    base_vertex: i32,
-    base_index: u32,
-    _pad: u32,
+struct CloudInstanceInput {
+    @location(3) min: vec3<f32>,
+    @location(4) size: vec3<f32>,
+    @location(5) color: vec4<f32>,
+    @location(6) faces: u32,
 };
 
-struct DrawIndexedIndirect {
-    index_count: u32,
-    instance_count: u32,
-    first_index: u32,
-    base_vertex: i32,
-    first_instance: u32,
+struct CloudOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) fog: f32,
 };
 
-@group(3) @binding(0) var<storage, read> chunks: array<ChunkCullData>;
-@group(3) @binding(1) var<storage, read_write> draw_commands: array<DrawIndexedIndirect>;
-@group(3) @binding(2) var<storage, read_write> draw_counter: atomic<u32>;
-
-@compute @workgroup_size(64)
-fn compute_cull(@builtin(global_invocation_id) id: vec3<u32>) {
-    let idx = id.x;
-    if (idx >= arrayLength(&chunks)) { return; }
-
-    let center = chunks[idx].pos_radius.xyz;
-    let radius = chunks[idx].pos_radius.w;
-
-// DIABOLICAL FRUSTUM EXTRACTION: Correctly pull planes from the View-Projection matrix
-    let m = camera.view_proj;
-    let p0 = vec4<f32>(m[0][3] + m[0][0], m[1][3] + m[1][0], m[2][3] + m[2][0], m[3][3] + m[3][0]);
-    let p1 = vec4<f32>(m[0][3] - m[0][0], m[1][3] - m[1][0], m[2][3] - m[2][0], m[3][3] - m[3][0]);
-    let p2 = vec4<f32>(m[0][3] + m[0][1], m[1][3] + m[1][1], m[2][3] + m[2][1], m[3][3] + m[3][1]);
-    let p3 = vec4<f32>(m[0][3] - m[0][1], m[1][3] - m[1][1], m[2][3] - m[2][1], m[3][3] - m[3][1]);
-    let p4 = vec4<f32>(m[0][3] + m[0][2], m[1][3] + m[1][2], m[2][3] + m[2][2], m[3][3] + m[3][2]);
-    let p5 = vec4<f32>(m[0][3] - m[0][2], m[1][3] - m[1][2], m[2][3] - m[2][2], m[3][3] - m[3][2]);
-
-    var visible = true;
-    // Normalized Frustum Test
-    if (dot(p0.xyz, center) + p0.w <= -radius) { visible = false; }
-    if (dot(p1.xyz, center) + p1.w <= -radius) { visible = false; }
-    if (dot(p2.xyz, center) + p2.w <= -radius) { visible = false; }
-    if (dot(p3.xyz, center) + p3.w <= -radius) { visible = false; }
-    if (dot(p4.xyz, center) + p4.w <= -radius) { visible = false; }
-    if (dot(p5.xyz, center) + p5.w <= -radius) { visible = false; }
-
-    if (visible) {
-        // DIABOLICAL ATOMIC APPEND: Write the draw command directly to the storage buffer
-        let out_idx = atomicAdd(&draw_counter, 1u);
-        draw_commands[out_idx].index_count = chunks[idx].index_count;
-        draw_commands[out_idx].instance_count = 1u;
-        draw_commands[out_idx].first_index = chunks[idx].base_index;
-        draw_commands[out_idx].base_vertex = chunks[idx].base_vertex;
-        draw_commands[out_idx].first_instance = 0u;
+@vertex
+fn vs_cloud(model: CloudVertexInput, instance: CloudInstanceInput) -> CloudOutput {
+    var out: CloudOutput;
+    if (((instance.faces >> model.face) & 1u) == 0u) {
+        out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        return out;
     }
-}
\ No newline at end of file
+    out.clip_position = camera.view_proj * vec4<f32>(instance.min + model.corner * instance.size, 1.0);
+    out.color = vec4<f32>(instance.color.rgb * model.shade, instance.color.a);
+    // Much thinner than the terrain fog: clouds are meant to be seen from far away
+    out.fog = clamp(1.0 - exp(-out.clip_position.w * 0.004), 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_cloud(in: CloudOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(mix(in.color.rgb, time_data.sky_color.rgb, in.fog), in.color.a);
+}
+
+// --- FRAME BLIT ---
+// Everything is drawn into an offscreen frame (so it can be read back for screenshots); with a
+// window, this copies it onto the swapchain image as one fullscreen triangle.
+struct BlitOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_blit(@builtin(vertex_index) index: u32) -> BlitOutput {
+    var out: BlitOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_blit(in: BlitOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.uv);
+}
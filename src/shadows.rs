@@ -0,0 +1,210 @@
+//! Cascaded sun shadows
+//!
+//! The camera frustum out to `SHADOW_DISTANCE` is cut into 2-4 slices along the view direction,
+//! each covered by an orthographic depth map rendered from the sun (or moon) into one layer of a
+//! depth texture array. `fs_main` picks the slice from the fragment's view depth and softens the
+//! depth comparison with a 3x3 PCF kernel. Cascade count and map size follow
+//! `GraphicsConfig::shadow_quality`; with shadows off no depth pass runs and the shader sees a
+//! cascade count of 0.
+
+use std::ops::Range;
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+use wgpu::*;
+use crate::gpu_cull::{self, CHUNK_RADIUS};
+use crate::mesh_arena::MeshArena;
+use crate::renderer::Vertex;
+
+pub const MAX_CASCADES: usize = 4;
+/// How far from the camera shadows are drawn, in blocks.
+pub const SHADOW_DISTANCE: f32 = 160.0;
+/// Extra depth kept between each cascade and the light, so casters outside the view (the cliff
+/// behind the player) still land in the map.
+const CASTER_MARGIN: f32 = 128.0;
+/// Blend between logarithmic (1.0) and evenly spaced (0.0) cascade splits.
+const SPLIT_LAMBDA: f32 = 0.75;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowUniform {
+    pub light_view_proj: [[[f32; 4]; 4]; MAX_CASCADES],
+    pub splits: [f32; MAX_CASCADES], // View depth where each cascade ends
+    pub light_dir: [f32; 4], // xyz towards the light, w shadow strength
+    pub cascade_count: u32,
+    pub map_size: f32,
+    pub _pad: [u32; 2],
+}
+
+/// Unit vector towards the light at `day_time` (0..1, 0 = sunrise) and how strong its shadows
+/// are. Same arc as `TimeSystem::get_sun_moon_position`: the sun crosses from +X over the zenith
+/// to -X during the first half of the day and the moon, at 0.3 strength, during the second.
+/// Shadows fade out as either one nears the horizon.
+pub fn sun_direction(day_time: f32) -> (Vec3, f32) {
+    let day = day_time.rem_euclid(1.0);
+    let (angle, brightness) = if day < 0.5 { (day * std::f32::consts::TAU, 1.0) } else { ((day - 0.5) * std::f32::consts::TAU, 0.3) };
+    let dir = Vec3::new(angle.cos(), angle.sin(), 0.0);
+    (dir, brightness * (dir.y / 0.25).clamp(0.0, 1.0))
+}
+
+/// View depth at which each of `count` cascades ends, the last one at `SHADOW_DISTANCE`.
+pub fn cascade_splits(near: f32, count: usize) -> [f32; MAX_CASCADES] {
+    let mut splits = [SHADOW_DISTANCE; MAX_CASCADES];
+    for (i, split) in splits.iter_mut().enumerate().take(count.saturating_sub(1)) {
+        let t = (i + 1) as f32 / count as f32;
+        let log = near * (SHADOW_DISTANCE / near).powf(t);
+        let even = near + (SHADOW_DISTANCE - near) * t;
+        *split = SPLIT_LAMBDA * log + (1.0 - SPLIT_LAMBDA) * even;
+    }
+    splits
+}
+
+/// The camera a set of cascades is fitted to.
+#[derive(Clone, Copy, Debug)]
+pub struct ViewFrustum { pub eye: Vec3, pub forward: Vec3, pub fov_y: f32, pub aspect: f32 }
+
+/// Light-space view-projection covering the slice of `view` between view depths `near` and `far`.
+/// The box is built around the slice's bounding sphere so its size doesn't change as the camera
+/// turns, and its origin is snapped to whole texels so shadow edges don't crawl as it moves.
+pub fn cascade_matrix(view: &ViewFrustum, near: f32, far: f32, light_dir: Vec3, map_size: u32) -> Mat4 {
+    let right = view.forward.cross(Vec3::Y).try_normalize().unwrap_or(Vec3::X);
+    let up = right.cross(view.forward);
+    let half = (view.fov_y * 0.5).tan();
+    let corners: Vec<Vec3> = [near, far].iter().flat_map(|&d| {
+        let (h, w) = (d * half, d * half * view.aspect);
+        let c = view.eye + view.forward * d;
+        [c - right * w - up * h, c + right * w - up * h, c + right * w + up * h, c - right * w + up * h]
+    }).collect();
+    let center = corners.iter().copied().sum::<Vec3>() / corners.len() as f32;
+    let radius = (corners.iter().map(|c| c.distance(center)).fold(0.0, f32::max) * 16.0).ceil() / 16.0;
+
+    // The sun moves in the XY plane, so Z is never parallel to it
+    let light_view = Mat4::look_at_rh(center + light_dir * (radius + CASTER_MARGIN), center, Vec3::Z);
+    let mut proj = Mat4::orthographic_rh(-radius, radius, -radius, radius, 0.0, 2.0 * radius + CASTER_MARGIN);
+    let origin = (proj * light_view * Vec4::W).xy() * (map_size as f32 * 0.5);
+    let snap = (origin.round() - origin) * (2.0 / map_size as f32);
+    proj.w_axis.x += snap.x;
+    proj.w_axis.y += snap.y;
+    proj * light_view
+}
+
+pub struct ShadowMaps {
+    pipeline: RenderPipeline,
+    pub bind_group_layout: BindGroupLayout, // Group 3 of the terrain pipelines
+    pub bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    sampler: Sampler,
+    layer_views: Vec<TextureView>,
+    cascade_bind_groups: Vec<(Buffer, BindGroup)>, // Each cascade's matrix, bound as the camera
+    uniform: ShadowUniform,
+    pub cascade_count: u32,
+    map_size: u32,
+}
+
+impl ShadowMaps {
+    /// Builds the depth pipeline over the terrain `shader`, drawing with the atlas bound in
+    /// `texture_layout` (for alpha-tested leaves) and each cascade's matrix in `camera_layout`.
+    pub fn new(device: &Device, shader: &ShaderModule, texture_layout: &BindGroupLayout, camera_layout: &BindGroupLayout, cascades: u32, map_size: u32) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Shadow Layout"),
+            entries: &[
+                BindGroupLayoutEntry { binding: 0, visibility: ShaderStages::FRAGMENT, ty: BindingType::Texture { sample_type: TextureSampleType::Depth, view_dimension: TextureViewDimension::D2Array, multisampled: false }, count: None },
+                BindGroupLayoutEntry { binding: 1, visibility: ShaderStages::FRAGMENT, ty: BindingType::Sampler(SamplerBindingType::Comparison), count: None },
+                BindGroupLayoutEntry { binding: 2, visibility: ShaderStages::FRAGMENT, ty: BindingType::Buffer { ty: BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: None }, count: None },
+            ],
+        });
+        let uniform = ShadowUniform { light_view_proj: [Mat4::IDENTITY.to_cols_array_2d(); MAX_CASCADES], splits: [0.0; MAX_CASCADES], light_dir: [0.0; 4], cascade_count: 0, map_size: 1.0, _pad: [0; 2] };
+        let uniform_buffer = device.create_buffer(&BufferDescriptor { label: Some("Shadow Uniform"), size: std::mem::size_of::<ShadowUniform>() as u64, usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST, mapped_at_creation: false });
+        let sampler = device.create_sampler(&SamplerDescriptor { label: Some("Shadow Sampler"), mag_filter: FilterMode::Linear, min_filter: FilterMode::Linear, compare: Some(CompareFunction::LessEqual), ..Default::default() });
+        let cascade_bind_groups = (0..MAX_CASCADES).map(|_| {
+            let buffer = device.create_buffer(&BufferDescriptor { label: Some("Cascade Camera"), size: 64, usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST, mapped_at_creation: false });
+            let bg = device.create_bind_group(&BindGroupDescriptor { label: Some("cascade_bind"), layout: camera_layout, entries: &[BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() }] });
+            (buffer, bg)
+        }).collect();
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor { label: Some("Shadow Pipeline Layout"), bind_group_layouts: &[texture_layout, camera_layout], push_constant_ranges: &[] });
+        // Both faces are drawn so crossed plants cast shadows; the depth bias keeps lit faces from
+        // shadowing themselves
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"), layout: Some(&layout),
+            vertex: VertexState { module: shader, entry_point: "vs_shadow", buffers: &[Vertex::desc()] },
+            fragment: Some(FragmentState { module: shader, entry_point: "fs_shadow", targets: &[] }),
+            primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList, front_face: FrontFace::Ccw, cull_mode: None, ..Default::default() },
+            depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled: true, depth_compare: CompareFunction::Less, stencil: StencilState::default(), bias: DepthBiasState { constant: 2, slope_scale: 2.0, clamp: 0.0 } }),
+            multisample: MultisampleState::default(), multiview: None,
+        });
+
+        let (layer_views, bind_group) = Self::create_maps(device, &bind_group_layout, &sampler, &uniform_buffer, 1, 1);
+        let mut shadows = Self { pipeline, bind_group_layout, bind_group, uniform_buffer, sampler, layer_views, cascade_bind_groups, uniform, cascade_count: 0, map_size: 1 };
+        shadows.configure(device, cascades, map_size);
+        shadows
+    }
+
+    fn create_maps(device: &Device, layout: &BindGroupLayout, sampler: &Sampler, uniform: &Buffer, layers: u32, size: u32) -> (Vec<TextureView>, BindGroup) {
+        let texture = device.create_texture(&TextureDescriptor { label: Some("Shadow Maps"), size: Extent3d { width: size, height: size, depth_or_array_layers: layers }, mip_level_count: 1, sample_count: 1, dimension: TextureDimension::D2, format: TextureFormat::Depth32Float, usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING, view_formats: &[] });
+        let array_view = texture.create_view(&TextureViewDescriptor { dimension: Some(TextureViewDimension::D2Array), ..Default::default() });
+        let layer_views = (0..layers).map(|layer| texture.create_view(&TextureViewDescriptor { dimension: Some(TextureViewDimension::D2), base_array_layer: layer, array_layer_count: Some(1), ..Default::default() })).collect();
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("shadow_bind"), layout,
+            entries: &[
+                BindGroupEntry { binding: 0, resource: BindingResource::TextureView(&array_view) },
+                BindGroupEntry { binding: 1, resource: BindingResource::Sampler(sampler) },
+                BindGroupEntry { binding: 2, resource: uniform.as_entire_binding() },
+            ],
+        });
+        (layer_views, bind_group)
+    }
+
+    /// Reallocates the maps for `cascades` cascades (0 turns shadows off) of `map_size`² texels.
+    pub fn configure(&mut self, device: &Device, cascades: u32, map_size: u32) {
+        let cascades = cascades.min(MAX_CASCADES as u32);
+        let map_size = map_size.clamp(1, device.limits().max_texture_dimension_2d);
+        if cascades == self.cascade_count && map_size == self.map_size { return; }
+        // Shadows off still binds a 1x1 map so the terrain pipelines keep their layout
+        let (layers, size) = if cascades == 0 { (1, 1) } else { (cascades, map_size) };
+        (self.layer_views, self.bind_group) = Self::create_maps(device, &self.bind_group_layout, &self.sampler, &self.uniform_buffer, layers, size);
+        self.cascade_count = cascades;
+        self.map_size = map_size;
+        log::info!("Shadows: {}", if cascades == 0 { "off".to_string() } else { format!("{} cascades at {}x{}", cascades, map_size, map_size) });
+    }
+
+    /// Refits the cascades to `view` for the light at `day_time` and uploads them. Returns whether
+    /// there's anything to render: shadows are on and the light is above the horizon.
+    pub fn update(&mut self, queue: &Queue, view: &ViewFrustum, near: f32, day_time: f32) -> bool {
+        let (light_dir, strength) = sun_direction(day_time);
+        let count = self.cascade_count as usize;
+        let splits = cascade_splits(near, count);
+        for i in 0..count {
+            let start = if i == 0 { near } else { splits[i - 1] };
+            let m = cascade_matrix(view, start, splits[i], light_dir, self.map_size);
+            self.uniform.light_view_proj[i] = m.to_cols_array_2d();
+            queue.write_buffer(&self.cascade_bind_groups[i].0, 0, bytemuck::cast_slice(&[m.to_cols_array_2d()]));
+        }
+        self.uniform.splits = splits;
+        self.uniform.light_dir = light_dir.extend(strength).to_array();
+        self.uniform.cascade_count = self.cascade_count;
+        self.uniform.map_size = self.map_size as f32;
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+        count > 0 && strength > 0.0
+    }
+
+    /// Records one depth pass per cascade over the solid `chunks` (centre, arena index range,
+    /// base vertex) that fall inside it.
+    pub fn render(&self, encoder: &mut CommandEncoder, atlas: &BindGroup, arena: &MeshArena, chunks: &[(Vec3, Range<u32>, i32)]) {
+        for (i, layer) in self.layer_views.iter().enumerate().take(self.cascade_count as usize) {
+            let planes = gpu_cull::frustum_planes(Mat4::from_cols_array_2d(&self.uniform.light_view_proj[i]));
+            let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment { view: layer, depth_ops: Some(Operations { load: LoadOp::Clear(1.0), store: StoreOp::Store }), stencil_ops: None }),
+                timestamp_writes: None, occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, atlas, &[]);
+            pass.set_bind_group(1, &self.cascade_bind_groups[i].1, &[]);
+            pass.set_vertex_buffer(0, arena.vertex_buffer.slice(..));
+            pass.set_index_buffer(arena.index_buffer.slice(..), IndexFormat::Uint32);
+            for (center, range, base_vertex) in chunks {
+                if gpu_cull::sphere_visible(&planes, *center, CHUNK_RADIUS) { pass.draw_indexed(range.clone(), *base_vertex, 0..1); }
+            }
+        }
+    }
+}
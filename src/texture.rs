@@ -162,6 +162,22 @@ Self::generate_ui_bar_data(&mut data, block_size, atlas_width, UI_BAR);
 // --- 11. CLOUDS (Index 228) ---
         Self::generate_noise(&mut data, block_size, atlas_width, 228, [255, 255, 255], 10);
 
+        // --- 11b. ENTITY SKINS (Indices 260-280): skin, shirt, trousers; see entity_models ---
+        let skins: [[[u8; 3]; 3]; 7] = [
+            [[200, 150, 110], [40, 160, 170], [50, 50, 140]],  // Player
+            [[80, 140, 70], [40, 160, 170], [50, 50, 140]],    // Zombie
+            [[200, 200, 200], [180, 180, 180], [170, 170, 170]], // Skeleton
+            [[90, 180, 80], [70, 160, 60], [60, 140, 50]],     // Creeper
+            [[50, 40, 40], [40, 32, 32], [30, 25, 25]],        // Spider
+            [[210, 210, 205], [200, 200, 195], [190, 190, 185]], // Wolf
+            [[190, 140, 100], [110, 80, 50], [90, 60, 40]],    // Villager
+        ];
+        for (i, tiles) in skins.iter().enumerate() {
+            for (t, color) in tiles.iter().enumerate() {
+                Self::generate_noise(&mut data, block_size, atlas_width, 260 + (i * 3 + t) as u32, *color, 8);
+            }
+        }
+
         // --- 12. SPECIALTY BLOCKS ---
         Self::generate_generic(&mut data, block_size, atlas_width, 120, [255, 220, 0]); // Gold Block
         Self::generate_generic(&mut data, block_size, atlas_width, 121, [230, 230, 230]); // Iron Block
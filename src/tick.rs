@@ -0,0 +1,288 @@
+//! World tick: random ticks and scheduled block updates
+//!
+//! The world advances in fixed game ticks (20 per second). Every tick picks `RANDOM_TICKS_PER_SECTION`
+//! random cells in each loaded section and hands them to their block's behaviour (crops, saplings,
+//! grass, farmland...), then runs every delayed update that has come due (fluids, falling blocks,
+//! fire). Sections are visited in sorted order and all randomness comes from one seeded RNG, so the
+//! same world and the same edits always tick the same way.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::decoration::{tree_blocks, TreeKind};
+use crate::fluids::FluidBehaviour;
+use crate::world::{BlockPos, BlockType, SimpleRng, World, CHUNK_SIZE_X, CHUNK_SIZE_Y, CHUNK_SIZE_Z};
+
+pub const TICKS_PER_SECOND: f32 = 20.0;
+pub const RANDOM_TICKS_PER_SECTION: usize = 3;
+const MAX_SCHEDULED_PER_TICK: usize = 1024; // Leftovers stay queued for the next tick
+const NEIGHBOURS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+/// Per-block logic driven by the world tick. Every hook defaults to doing nothing.
+pub trait BlockBehaviour: Sync {
+    /// Called for blocks picked by the random tick.
+    fn random_tick(&self, _world: &mut World, _pos: BlockPos, _rng: &mut SimpleRng) {}
+    /// Called when an update queued with `World::schedule_tick` comes due.
+    fn scheduled_tick(&self, _world: &mut World, _pos: BlockPos, _rng: &mut SimpleRng) {}
+    /// Called when the block itself or one of its six neighbours changed.
+    fn neighbour_changed(&self, _world: &mut World, _pos: BlockPos) {}
+}
+
+/// Behaviour registry: which blocks do anything on their own.
+pub fn behaviour(block: BlockType) -> Option<&'static dyn BlockBehaviour> {
+    match block {
+        BlockType::Wheat0 | BlockType::Wheat1 | BlockType::Wheat2 | BlockType::Wheat3 |
+        BlockType::Wheat4 | BlockType::Wheat5 | BlockType::Wheat6 | BlockType::Wheat7 => Some(&Crop),
+        BlockType::OakSapling => Some(&Sapling),
+        BlockType::Grass => Some(&GrassSpread),
+        BlockType::FarmlandDry | BlockType::FarmlandWet => Some(&Farmland),
+        BlockType::Sand | BlockType::Gravel => Some(&Falling),
+        BlockType::Fire => Some(&FireSpread),
+        BlockType::Sugarcane | BlockType::Cactus => Some(&ColumnPlant),
+        b if b.is_liquid() => Some(&FluidBehaviour),
+        _ => None,
+    }
+}
+
+/// Tick clock plus the queue of delayed block updates, ordered by due tick then position.
+#[derive(Clone)]
+pub struct TickScheduler {
+    queue: BinaryHeap<Reverse<(u64, i32, i32, i32)>>,
+    scheduled: HashSet<BlockPos>,
+    tick: u64,
+    clock: f32,
+    rng: SimpleRng,
+    /// Positions ticks have changed since the last `take_changes`, once someone asked for them.
+    changed: Option<Vec<BlockPos>>,
+}
+
+impl TickScheduler {
+    pub fn new(seed: u32) -> Self {
+        Self { queue: BinaryHeap::new(), scheduled: HashSet::new(), tick: 0, clock: 0.0, rng: SimpleRng::new(seed as u64 ^ 0x7469_636B), changed: None }
+    }
+
+    /// Game ticks run since the world was created.
+    #[allow(dead_code)] // Only the dedicated server saves and restores the clock
+    pub fn game_tick(&self) -> u64 { self.tick }
+
+    /// Resumes the clock of a saved world. Only meaningful before any tick has been scheduled.
+    #[allow(dead_code)]
+    pub fn set_game_tick(&mut self, tick: u64) { self.tick = tick; }
+
+    /// Starts remembering which positions ticks change, for whoever has to pass them on.
+    #[allow(dead_code)] // Only the dedicated server sends tick changes to clients
+    pub fn record_changes(&mut self) { self.changed.get_or_insert_with(Vec::new); }
+
+    /// Positions changed by ticks since the last call, oldest first (possibly repeated).
+    #[allow(dead_code)]
+    pub fn take_changes(&mut self) -> Vec<BlockPos> { self.changed.as_mut().map(std::mem::take).unwrap_or_default() }
+
+    pub(crate) fn note_change(&mut self, pos: BlockPos) {
+        if let Some(changed) = &mut self.changed { changed.push(pos); }
+    }
+}
+
+fn offset(pos: BlockPos, dx: i32, dy: i32, dz: i32) -> BlockPos { BlockPos { x: pos.x + dx, y: pos.y + dy, z: pos.z + dz } }
+
+fn chance(rng: &mut SimpleRng, one_in: u32) -> bool { rng.next_u32().is_multiple_of(one_in) }
+
+impl World {
+    /// Queues a scheduled tick for `pos` in `delay` ticks. A position already waiting keeps its slot.
+    pub fn schedule_tick(&mut self, pos: BlockPos, delay: u64) {
+        if !self.ticks.scheduled.insert(pos) { return; }
+        let due = self.ticks.tick + delay.max(1);
+        self.ticks.queue.push(Reverse((due, pos.x, pos.y, pos.z)));
+    }
+
+    /// Tells the changed block and its six neighbours about the change.
+    pub fn notify_neighbours(&mut self, pos: BlockPos) {
+        for p in std::iter::once(pos).chain(NEIGHBOURS.iter().map(|&(dx, dy, dz)| offset(pos, dx, dy, dz))) {
+            if let Some(b) = behaviour(self.get_block(p)) { b.neighbour_changed(self, p); }
+        }
+    }
+
+    /// Advances the tick clock by `dt` seconds, running as many game ticks as have elapsed.
+    pub fn update_ticks(&mut self, dt: f32) {
+        self.ticks.clock += dt;
+        while self.ticks.clock >= 1.0 / TICKS_PER_SECOND {
+            self.ticks.clock -= 1.0 / TICKS_PER_SECOND;
+            self.tick();
+        }
+    }
+
+    /// One game tick: random ticks in every loaded section, then the scheduled updates now due.
+    pub fn tick(&mut self) {
+        self.ticks.tick += 1;
+        let mut rng = self.ticks.rng.clone();
+
+        let mut sections: Vec<_> = self.chunks.iter().filter(|(_, c)| !c.is_empty).map(|(&k, _)| k).collect();
+        sections.sort_unstable();
+        for (cx, cy, cz) in sections {
+            for _ in 0..RANDOM_TICKS_PER_SECTION {
+                let r = rng.next_u32() as usize;
+                let (lx, ly, lz) = (r % CHUNK_SIZE_X, (r >> 4) % CHUNK_SIZE_Y, (r >> 8) % CHUNK_SIZE_Z);
+                let pos = BlockPos { x: cx * 16 + lx as i32, y: cy * 16 + ly as i32, z: cz * 16 + lz as i32 };
+                if let Some(b) = behaviour(self.get_block(pos)) { b.random_tick(self, pos, &mut rng); }
+            }
+        }
+
+        let mut budget = MAX_SCHEDULED_PER_TICK;
+        while let Some(&Reverse((due, x, y, z))) = self.ticks.queue.peek() {
+            if due > self.ticks.tick || budget == 0 { break; }
+            self.ticks.queue.pop();
+            budget -= 1;
+            let pos = BlockPos { x, y, z };
+            self.ticks.scheduled.remove(&pos);
+            if let Some(b) = behaviour(self.get_block(pos)) { b.scheduled_tick(self, pos, &mut rng); }
+        }
+        self.ticks.rng = rng;
+    }
+
+    /// Sets a block from a tick and queues the meshes that can see it for rebuilding.
+    pub fn set_block_ticked(&mut self, pos: BlockPos, block: BlockType) {
+        self.set_block_world(pos, block);
+        self.ticks.note_change(pos);
+        let (cx, cy, cz) = (pos.x.div_euclid(16), pos.y.div_euclid(16), pos.z.div_euclid(16));
+        let (lx, ly, lz) = (pos.x.rem_euclid(16), pos.y.rem_euclid(16), pos.z.rem_euclid(16));
+        let mut keys = vec![(cx, cy, cz)];
+        if lx == 0 { keys.push((cx - 1, cy, cz)); } else if lx == 15 { keys.push((cx + 1, cy, cz)); }
+        if ly == 0 { keys.push((cx, cy - 1, cz)); } else if ly == 15 { keys.push((cx, cy + 1, cz)); }
+        if lz == 0 { keys.push((cx, cy, cz - 1)); } else if lz == 15 { keys.push((cx, cy, cz + 1)); }
+        for key in keys {
+            if let Some(chunk) = self.chunks.get_mut(&key) {
+                chunk.mesh_dirty = true;
+                self.dirty_chunks.insert(key);
+            }
+        }
+        self.mesh_dirty = true;
+    }
+}
+
+// --- BEHAVIOURS ---
+
+/// Wheat grows one stage at a time on farmland in light, faster when the farmland is wet.
+struct Crop;
+impl BlockBehaviour for Crop {
+    fn random_tick(&self, world: &mut World, pos: BlockPos, rng: &mut SimpleRng) {
+        let next = match world.get_block(pos) {
+            BlockType::Wheat0 => BlockType::Wheat1, BlockType::Wheat1 => BlockType::Wheat2, BlockType::Wheat2 => BlockType::Wheat3,
+            BlockType::Wheat3 => BlockType::Wheat4, BlockType::Wheat4 => BlockType::Wheat5, BlockType::Wheat5 => BlockType::Wheat6,
+            BlockType::Wheat6 => BlockType::Wheat7, _ => return,
+        };
+        if world.get_light_world(pos) < 9 { return; }
+        let odds = match world.get_block(offset(pos, 0, -1, 0)) { BlockType::FarmlandWet => 3, BlockType::FarmlandDry => 6, _ => return };
+        if chance(rng, odds) { world.set_block_ticked(pos, next); }
+    }
+    fn neighbour_changed(&self, world: &mut World, pos: BlockPos) {
+        if !matches!(world.get_block(offset(pos, 0, -1, 0)), BlockType::FarmlandDry | BlockType::FarmlandWet) {
+            world.set_block_ticked(pos, BlockType::Air); // Lost its soil
+        }
+    }
+}
+
+/// Saplings in light occasionally grow into the same oak shape worldgen places.
+struct Sapling;
+impl BlockBehaviour for Sapling {
+    fn random_tick(&self, world: &mut World, pos: BlockPos, rng: &mut SimpleRng) {
+        if world.get_light_world(pos) < 9 || !chance(rng, 7) { return; }
+        let tree = tree_blocks(TreeKind::Oak, pos, rng);
+        if tree.iter().any(|&(p, b)| b == BlockType::Wood && p.y > pos.y && !matches!(world.get_block(p), BlockType::Air | BlockType::Leaves)) { return; }
+        for (p, block) in tree {
+            if block == BlockType::Wood || world.get_block(p) == BlockType::Air { world.set_block_ticked(p, block); }
+        }
+    }
+}
+
+/// Grass dies under opaque blocks and spreads onto nearby lit dirt.
+struct GrassSpread;
+impl BlockBehaviour for GrassSpread {
+    fn random_tick(&self, world: &mut World, pos: BlockPos, rng: &mut SimpleRng) {
+        let above = world.get_block(offset(pos, 0, 1, 0));
+        if above.light_opacity() >= 15 || above.is_liquid() {
+            world.set_block_ticked(pos, BlockType::Dirt);
+            return;
+        }
+        if world.get_light_world(offset(pos, 0, 1, 0)) < 9 { return; }
+        let r = rng.next_u32();
+        let target = offset(pos, (r % 3) as i32 - 1, ((r >> 4) % 5) as i32 - 3, ((r >> 8) % 3) as i32 - 1);
+        let target_above = world.get_block(offset(target, 0, 1, 0));
+        if world.get_block(target) == BlockType::Dirt && target_above.light_opacity() < 15 && !target_above.is_liquid()
+            && world.get_light_world(offset(target, 0, 1, 0)) >= 4 {
+            world.set_block_ticked(target, BlockType::Grass);
+        }
+    }
+}
+
+/// Farmland is wet while water is within 4 blocks, dries out otherwise and reverts to dirt
+/// when dry with nothing planted.
+struct Farmland;
+impl BlockBehaviour for Farmland {
+    fn random_tick(&self, world: &mut World, pos: BlockPos, _rng: &mut SimpleRng) {
+        let block = world.get_block(pos);
+        let wet = (-4..=4).any(|dx| (0..=1).any(|dy| (-4..=4).any(|dz| world.get_block(offset(pos, dx, dy, dz)).is_water())));
+        let crop_above = matches!(world.get_block(offset(pos, 0, 1, 0)), BlockType::Wheat0 | BlockType::Wheat1 | BlockType::Wheat2 |
+            BlockType::Wheat3 | BlockType::Wheat4 | BlockType::Wheat5 | BlockType::Wheat6 | BlockType::Wheat7);
+        let next = match (block, wet) {
+            (BlockType::FarmlandDry, true) => BlockType::FarmlandWet,
+            (BlockType::FarmlandWet, false) => BlockType::FarmlandDry,
+            (BlockType::FarmlandDry, false) if !crop_above => BlockType::Dirt,
+            _ => return,
+        };
+        world.set_block_ticked(pos, next);
+    }
+    fn neighbour_changed(&self, world: &mut World, pos: BlockPos) {
+        if world.get_block(offset(pos, 0, 1, 0)).is_solid() { world.set_block_ticked(pos, BlockType::Dirt); } // Trampled under a block
+    }
+}
+
+/// Sand and gravel fall one block per update while there is air or fluid beneath them.
+struct Falling;
+impl BlockBehaviour for Falling {
+    fn scheduled_tick(&self, world: &mut World, pos: BlockPos, _rng: &mut SimpleRng) {
+        let block = world.get_block(pos);
+        let below = offset(pos, 0, -1, 0);
+        let below_block = world.get_block(below);
+        if pos.y <= 0 || !(below_block == BlockType::Air || below_block.is_liquid()) { return; }
+        world.set_block_ticked(pos, BlockType::Air);
+        world.set_block_ticked(below, block);
+    }
+    fn neighbour_changed(&self, world: &mut World, pos: BlockPos) {
+        let below = world.get_block(offset(pos, 0, -1, 0));
+        if below == BlockType::Air || below.is_liquid() { world.schedule_tick(pos, 2); }
+    }
+}
+
+/// Fire burns flammable neighbours, spreads into air next to fuel and dies without fuel.
+struct FireSpread;
+impl BlockBehaviour for FireSpread {
+    fn scheduled_tick(&self, world: &mut World, pos: BlockPos, rng: &mut SimpleRng) {
+        let fuel: Vec<BlockPos> = NEIGHBOURS.iter().map(|&(dx, dy, dz)| offset(pos, dx, dy, dz))
+            .filter(|&p| world.get_block(p).is_flammable()).collect();
+        let resting = world.get_block(offset(pos, 0, -1, 0)).is_solid();
+        if fuel.is_empty() && (!resting || chance(rng, 4)) { world.set_block_ticked(pos, BlockType::Air); return; }
+        for p in fuel {
+            if chance(rng, 4) { world.set_block_ticked(p, BlockType::Fire); }
+        }
+        for dx in -1..=1 { for dy in -1..=2 { for dz in -1..=1 {
+            let p = offset(pos, dx, dy, dz);
+            if world.get_block(p) != BlockType::Air || !chance(rng, 8) { continue; }
+            if NEIGHBOURS.iter().any(|&(ex, ey, ez)| world.get_block(offset(p, ex, ey, ez)).is_flammable()) {
+                world.set_block_ticked(p, BlockType::Fire);
+            }
+        }}}
+        if chance(rng, 12) { world.set_block_ticked(pos, BlockType::Air); } else { world.schedule_tick(pos, 30 + (rng.next_u32() % 10) as u64); }
+    }
+    fn neighbour_changed(&self, world: &mut World, pos: BlockPos) { world.schedule_tick(pos, 30); }
+}
+
+/// Sugarcane and cactus grow upward to three blocks tall.
+struct ColumnPlant;
+impl BlockBehaviour for ColumnPlant {
+    fn random_tick(&self, world: &mut World, pos: BlockPos, rng: &mut SimpleRng) {
+        let block = world.get_block(pos);
+        let above = offset(pos, 0, 1, 0);
+        if world.get_block(above) != BlockType::Air || !chance(rng, 16) { return; }
+        let height = 1 + (1..3).take_while(|&i| world.get_block(offset(pos, 0, -i, 0)) == block).count();
+        if height < 3 { world.set_block_ticked(above, block); }
+    }
+}
@@ -9,7 +9,8 @@
 //! - Seasonal variations
 
 use glam::Vec3;
-use crate::world::World;
+use crate::biome::{Biome, Precipitation};
+use crate::world::{BlockPos, World};
 
 /// DIABOLICAL Weather Types with realistic behaviors
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -415,6 +416,7 @@ pub struct WeatherSystem {
     pub lightning_strikes: Vec<LightningStrike>,
     pub ambient_light_color: [f32; 3],
     pub fog_density: f32,
+    pub local_biome: Biome, // Biome under the player; decides whether precipitation falls as rain or snow
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -449,6 +451,7 @@ impl WeatherSystem {
             lightning_strikes: Vec::new(),
             ambient_light_color: [1.0, 1.0, 1.0],
             fog_density: 0.0,
+            local_biome: Biome::default(),
         }
     }
 
@@ -465,6 +468,9 @@ impl WeatherSystem {
             _ => Season::Winter,
         };
 
+        let feet = BlockPos { x: player_position.x.floor() as i32, y: player_position.y.floor() as i32, z: player_position.z.floor() as i32 };
+        if let Some(biome) = world.get_biome(feet) { self.local_biome = biome; }
+
         // Update weather transitions
         self.time_until_change -= dt;
         if self.time_until_change <= 0.0 {
@@ -473,6 +479,7 @@ impl WeatherSystem {
 
         if self.current_weather != self.target_weather {
             self.transition_progress = (self.transition_progress + dt / 30.0).min(1.0);
+            if self.transition_progress >= 1.0 { self.current_weather = self.target_weather; }
         } else {
             self.transition_progress = 0.0;
         }
@@ -622,7 +629,19 @@ impl WeatherSystem {
             block_interaction_modifier: self.get_block_interaction_modifier(),
             ambient_light_color: self.ambient_light_color,
             fog_density: self.fog_density,
-            precipitation_type: self.current_weather.get_precipitation_type(),
+            precipitation_type: self.local_precipitation(),
+        }
+    }
+
+    /// The current weather's precipitation as it falls in the local biome: snow in cold biomes,
+    /// rain in temperate ones, nothing in dry ones. Dust and magic fall everywhere.
+    pub fn local_precipitation(&self) -> Option<PrecipitationType> {
+        let precipitation = self.current_weather.get_precipitation_type()?;
+        match (self.local_biome.def().precipitation, precipitation) {
+            (Precipitation::Dry, PrecipitationType::Rain { .. } | PrecipitationType::Snow { .. }) => None,
+            (Precipitation::Snow, PrecipitationType::Rain { intensity }) => Some(PrecipitationType::Snow { intensity }),
+            (Precipitation::Rain, PrecipitationType::Snow { intensity }) => Some(PrecipitationType::Rain { intensity }),
+            (_, p) => Some(p),
         }
     }
 
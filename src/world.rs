@@ -1,16 +1,24 @@
-use std::collections::{HashMap, VecDeque, HashSet};
+use std::collections::{HashMap, HashSet};
 use crate::noise_gen::NoiseGenerator;
+use crate::biome::Biome;
 use crate::player::Player;
+use crate::region::RegionStore;
+use crate::chunk_gen::ChunkGenPool;
+use crate::decoration::Placement;
+use crate::tick::TickScheduler;
 use glam::Vec3;
+use std::sync::Arc;
 use serde::{Serialize, Deserialize};
 
+#[derive(Clone)]
 pub struct SimpleRng { pub state: u64 }
 impl SimpleRng {
     pub fn new(seed: u64) -> Self { Self { state: seed } }
-    pub fn next_f32(&mut self) -> f32 {
+    pub fn next_u32(&mut self) -> u32 {
         self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1);
-        ((self.state >> 33) ^ self.state) as u32 as f32 / u32::MAX as f32
+        ((self.state >> 33) ^ self.state) as u32
     }
+    pub fn next_f32(&mut self) -> f32 { self.next_u32() as f32 / u32::MAX as f32 }
     fn gen_range(&mut self, min: f32, max: f32) -> f32 { min + (max - min) * self.next_f32() }
 }
 
@@ -19,6 +27,7 @@ pub const CHUNK_SIZE_Z: usize = 16;
 pub const CHUNK_SIZE_Y: usize = 16; // DIABOLICAL VERTICAL SUBDIVISION
 pub const WORLD_HEIGHT: i32 = 128;
 pub const WATER_LEVEL: i32 = 20;
+pub const UNLOAD_MARGIN: i32 = 2; // Hysteresis so walking back and forth over a border doesn't thrash
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BlockPos { pub x: i32, pub y: i32, pub z: i32 }
@@ -49,16 +58,33 @@ Wheat0 = 85, Wheat1 = 86, Wheat2 = 87, Wheat3 = 88, Wheat4 = 89, Wheat5 = 90, Wh
     CraftingTable = 100, Furnace = 101, FurnaceActive = 102, Chest = 103,
     ChestLeft = 104, ChestRight = 105,
     WheatSeeds = 110, Wheat = 111, Bread = 112, Apple = 113, Porkchop = 114, CookedPorkchop = 115,
+    // Saves and packets store a block as its position in this list, so new ones only go below
+    // Flowing fluids, numbered by level (7 = next to the source, 1 = thinnest edge)
+    WaterFlow1 = 130, WaterFlow2 = 131, WaterFlow3 = 132, WaterFlow4 = 133, WaterFlow5 = 134, WaterFlow6 = 135, WaterFlow7 = 136,
+    LavaFlow1 = 137, LavaFlow2 = 138, LavaFlow3 = 139, LavaFlow4 = 140, LavaFlow5 = 141, LavaFlow6 = 142, LavaFlow7 = 143,
 }
 
 impl BlockType {
-    pub fn get_water_level(&self) -> u8 { if *self == BlockType::Water { 8 } else { 0 } }
-    pub fn is_liquid(&self) -> bool { matches!(self, BlockType::Water | BlockType::Lava) }
+    pub fn get_water_level(&self) -> u8 { if self.is_water() { self.get_fluid_level() } else { 0 } }
+    /// 8 for source blocks, 1-7 for flowing water/lava, 0 for everything else.
+    pub fn get_fluid_level(&self) -> u8 {
+        match self {
+            BlockType::Water | BlockType::Lava => 8,
+            BlockType::WaterFlow7 | BlockType::LavaFlow7 => 7, BlockType::WaterFlow6 | BlockType::LavaFlow6 => 6,
+            BlockType::WaterFlow5 | BlockType::LavaFlow5 => 5, BlockType::WaterFlow4 | BlockType::LavaFlow4 => 4,
+            BlockType::WaterFlow3 | BlockType::LavaFlow3 => 3, BlockType::WaterFlow2 | BlockType::LavaFlow2 => 2,
+            BlockType::WaterFlow1 | BlockType::LavaFlow1 => 1,
+            _ => 0,
+        }
+    }
+    /// Height of the fluid surface inside its block, from the level.
+    pub fn get_fluid_height(&self) -> f32 { (self.get_fluid_level() as f32 + 1.0) / 10.0 }
+    pub fn is_liquid(&self) -> bool { self.is_water() || self.is_lava() }
     pub fn get_texture_top(&self) -> u32 { self.get_texture_indices().0 }
     pub fn get_texture_bottom(&self) -> u32 { self.get_texture_indices().1 }
     pub fn get_texture_side(&self) -> u32 { self.get_texture_indices().2 }
 pub fn is_transparent(&self) -> bool { 
-        matches!(self, BlockType::Air | BlockType::Water | BlockType::Lava | BlockType::Leaves | BlockType::SpruceLeaves | BlockType::BirchLeaves | 
+        self.is_liquid() || matches!(self, BlockType::Air | BlockType::Leaves | BlockType::SpruceLeaves | BlockType::BirchLeaves | 
                        BlockType::Torch | BlockType::Fire | BlockType::Glass | BlockType::Rose | BlockType::Dandelion | 
                        BlockType::DeadBush | BlockType::TallGrass | BlockType::OakSapling | BlockType::Sugarcane | 
                        BlockType::Ice | BlockType::LilyPad | BlockType::Vine |
@@ -73,10 +99,42 @@ pub fn is_transparent(&self) -> bool {
                        BlockType::Wheat5 | BlockType::Wheat6 | BlockType::Wheat7)
     }
 
-    pub fn is_water(&self) -> bool { matches!(self, BlockType::Water) }
+    pub fn is_water(&self) -> bool {
+        matches!(self, BlockType::Water | BlockType::WaterFlow1 | BlockType::WaterFlow2 | BlockType::WaterFlow3 |
+                       BlockType::WaterFlow4 | BlockType::WaterFlow5 | BlockType::WaterFlow6 | BlockType::WaterFlow7)
+    }
+
+    pub fn is_flammable(&self) -> bool {
+        matches!(self, BlockType::Wood | BlockType::Planks | BlockType::Leaves | BlockType::SpruceWood | BlockType::SpruceLeaves |
+                       BlockType::BirchWood | BlockType::BirchLeaves | BlockType::SprucePlanks | BlockType::BirchPlanks |
+                       BlockType::Bookshelf | BlockType::TNT | BlockType::CraftingTable | BlockType::TallGrass |
+                       BlockType::DeadBush | BlockType::Vine)
+    }
+
+    pub fn is_lava(&self) -> bool {
+        matches!(self, BlockType::Lava | BlockType::LavaFlow1 | BlockType::LavaFlow2 | BlockType::LavaFlow3 |
+                       BlockType::LavaFlow4 | BlockType::LavaFlow5 | BlockType::LavaFlow6 | BlockType::LavaFlow7)
+    }
+
+    /// Block light emitted by this block (0 = none).
+    pub fn light_emission(&self) -> u8 {
+        match self { b if b.is_lava() => 15, BlockType::Fire => 15, BlockType::Torch => 14, BlockType::FurnaceActive => 13, _ => 0 }
+    }
+
+    /// How much light is lost passing through this block; 15 blocks it entirely.
+    pub fn light_opacity(&self) -> u8 {
+        match self {
+            b if b.is_water() => 2,
+            BlockType::Ice => 2,
+            BlockType::Leaves | BlockType::SpruceLeaves | BlockType::BirchLeaves => 1,
+            b if b.is_lava() => 15,
+            b if b.is_transparent() || !b.is_solid() => 0,
+            _ => 15,
+        }
+    }
 
     pub fn is_solid(&self) -> bool {
-        !matches!(self, BlockType::Air | BlockType::Water | BlockType::Lava | BlockType::Fire | 
+        !self.is_liquid() && !matches!(self, BlockType::Air | BlockType::Fire | 
                        BlockType::Rose | BlockType::Dandelion | BlockType::DeadBush | BlockType::TallGrass | 
                        BlockType::OakSapling | BlockType::Sugarcane | BlockType::LilyPad | BlockType::Vine | 
                        BlockType::Wheat | BlockType::Wheat0 | BlockType::Wheat1 | BlockType::Wheat2 | 
@@ -118,6 +176,7 @@ BlockType::TNT => (50, 51, 50), BlockType::Pumpkin => (52, 53, 52), BlockType::M
             BlockType::Cloud => (228, 228, 228),
             BlockType::Wheat => (80, 80, 80), BlockType::Bread => (81, 81, 81), BlockType::Apple => (82, 82, 82),
             BlockType::Porkchop => (83, 83, 83), BlockType::CookedPorkchop => (84, 84, 84),
+            t if t.is_water() => (9, 9, 9), t if t.is_lava() => (200, 200, 200),
             t if t.is_tool() => { let i = *t as u32; (i, i, i) }
             _ => (0, 0, 0),
         }
@@ -150,6 +209,7 @@ pub fn get_display_name(&self) -> &str {
             BlockType::BucketEmpty => "Empty Bucket", BlockType::BucketWater => "Water Bucket",
             BlockType::FarmlandDry => "Farmland", BlockType::FarmlandWet => "Hydrated Farmland",
             BlockType::GoldBlock => "Block of Gold", BlockType::IronBlock => "Block of Iron", BlockType::DiamondBlock => "Block of Diamond",
+            t if t.is_water() => "Water", t if t.is_lava() => "Lava",
             t if t.is_tool() => match *t as u8 {
                 21..=25 => match *t as u8 % 5 { 1=>"Wood Pickaxe", 2=>"Stone Pickaxe", 3=>"Iron Pickaxe", 4=>"Gold Pickaxe", 0=>"Diamond Pickaxe", _=>"Pickaxe" },
                 26..=30 => match *t as u8 % 5 { 1=>"Wood Axe", 2=>"Stone Axe", 3=>"Iron Axe", 4=>"Gold Axe", 0=>"Diamond Axe", _=>"Axe" },
@@ -164,7 +224,8 @@ pub fn get_display_name(&self) -> &str {
 
     pub fn get_hardness(&self) -> f32 {
         match self {
-            BlockType::Bedrock | BlockType::Water | BlockType::Air => -1.0,
+            BlockType::Bedrock | BlockType::Air => -1.0,
+            t if t.is_liquid() => -1.0,
             BlockType::Leaves => 0.2, BlockType::Sand | BlockType::Dirt | BlockType::Grass => 0.5,
             BlockType::Wood | BlockType::Planks | BlockType::CraftingTable => 2.0,
 BlockType::Stone | BlockType::Cobblestone | BlockType::CoalOre => 3.0,
@@ -228,7 +289,7 @@ pub fn get_tool_class(&self) -> &'static str {
             BlockType::Glass => "glass",
             BlockType::Bedrock => "bedrock",
             BlockType::IronBlock | BlockType::GoldBlock | BlockType::DiamondBlock | BlockType::TNT => "metal",
-            BlockType::Water | BlockType::Lava => "water",
+            t if t.is_liquid() => "water",
             _ => "stone",
         }
     }
@@ -239,33 +300,174 @@ pub fn get_tool_class(&self) -> &'static str {
     }
 }
 
+const SECTION_VOLUME: usize = CHUNK_SIZE_X * CHUNK_SIZE_Y * CHUNK_SIZE_Z;
+
+/// Paletted block storage for one 16x16x16 section.
+/// `bits == 0` means the whole section is `palette[0]` and no index data is allocated at all;
+/// otherwise every block is a 1/2/4/8-bit index into `palette`, packed into u64 words.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PalettedBlocks {
+    palette: Vec<BlockType>,
+    bits: u8,
+    data: Vec<u64>,
+}
+
+impl PalettedBlocks {
+    pub fn filled(block: BlockType) -> Self { Self { palette: vec![block], bits: 0, data: Vec::new() } }
+
+    #[inline]
+    fn index(x: usize, y: usize, z: usize) -> usize { (y * CHUNK_SIZE_Z + z) * CHUNK_SIZE_X + x }
+
+    #[inline]
+    fn read(&self, i: usize) -> usize {
+        let per_word = 64 / self.bits as usize;
+        let shift = (i % per_word) * self.bits as usize;
+        ((self.data[i / per_word] >> shift) & ((1u64 << self.bits) - 1)) as usize
+    }
+
+    #[inline]
+    fn write(&mut self, i: usize, v: usize) {
+        let per_word = 64 / self.bits as usize;
+        let shift = (i % per_word) * self.bits as usize;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let word = &mut self.data[i / per_word];
+        *word = (*word & !mask) | ((v as u64) << shift);
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockType {
+        if self.bits == 0 { return self.palette[0]; }
+        self.palette[self.read(Self::index(x, y, z))]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
+        let i = Self::index(x, y, z);
+        let pal_idx = match self.palette.iter().position(|&b| b == block) {
+            Some(p) => p,
+            None => { self.palette.push(block); self.palette.len() - 1 }
+        };
+        if self.bits == 0 {
+            if pal_idx == 0 { return; } // Uniform section and the block didn't change
+            self.repack(1);
+        } else if pal_idx >= (1usize << self.bits) {
+            self.repack(self.bits * 2);
+        }
+        self.write(i, pal_idx);
+    }
+
+    /// Re-encodes the indices at a new bit width (palette order is preserved).
+    fn repack(&mut self, bits: u8) {
+        let mut old = std::mem::replace(self, Self { palette: Vec::new(), bits, data: vec![0; SECTION_VOLUME * bits as usize / 64] });
+        self.palette = std::mem::take(&mut old.palette);
+        if old.bits == 0 { return; } // All zeros already point at palette[0]
+        for i in 0..SECTION_VOLUME { self.write(i, old.read(i)); }
+    }
+
+    /// Drops palette entries nothing points at any more and shrinks to the smallest bit width.
+    /// Collapses back to the single-value form when the section became uniform.
+    pub fn compact(&mut self) {
+        if self.bits == 0 { return; }
+        let mut used = vec![false; self.palette.len()];
+        for i in 0..SECTION_VOLUME { used[self.read(i)] = true; }
+        let mut remap = vec![0usize; self.palette.len()];
+        let mut palette = Vec::new();
+        for (idx, &block) in self.palette.iter().enumerate() {
+            if used[idx] { remap[idx] = palette.len(); palette.push(block); }
+        }
+        if palette.len() == 1 { *self = Self::filled(palette[0]); return; }
+        let bits = match palette.len() { 0..=2 => 1, 3..=4 => 2, 5..=16 => 4, _ => 8 };
+        let mut packed = Self { palette, bits, data: vec![0; SECTION_VOLUME * bits as usize / 64] };
+        for i in 0..SECTION_VOLUME { packed.write(i, remap[self.read(i)]); }
+        *self = packed;
+    }
+
+    pub fn is_uniform(&self, block: BlockType) -> bool {
+        if self.bits == 0 { return self.palette[0] == block; }
+        self.palette.iter().position(|&b| b == block).is_some_and(|p| (0..SECTION_VOLUME).all(|i| self.read(i) == p))
+    }
+
+    /// Sanity check for data coming off disk/network before it gets indexed.
+    pub fn is_valid(&self) -> bool {
+        !self.palette.is_empty() && match self.bits {
+            0 => self.data.is_empty(),
+            1 | 2 | 4 | 8 => self.data.len() == SECTION_VOLUME * self.bits as usize / 64 && self.palette.len() <= (1usize << self.bits)
+                && (0..SECTION_VOLUME).all(|i| self.read(i) < self.palette.len()),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Chunk {
-    pub blocks: Box<[[[BlockType; CHUNK_SIZE_Z]; CHUNK_SIZE_Y]; CHUNK_SIZE_X]>,
-    pub light: Box<[[[u8; CHUNK_SIZE_Z]; CHUNK_SIZE_Y]; CHUNK_SIZE_X]>,
+    pub blocks: PalettedBlocks,
+    pub light: Option<Box<[u8; SECTION_VOLUME]>>, // Packed nibbles: sky << 4 | block. None = every cell is `light_fill`
+    pub light_fill: u8,
     pub is_empty: bool,
     pub mesh_dirty: bool,
+    pub modified: bool, // Edited since it was generated/loaded; needs writing back to its region
+    pub decorated: bool, // Features are already written (decoration pass ran, or it came off disk); leave it alone
+    pub biomes: [Biome; CHUNK_SIZE_X * CHUNK_SIZE_Z], // Per column at this section's mid height, indexed z * 16 + x
 }
 impl Chunk {
     pub fn new() -> Self { 
         Chunk { 
-            blocks: Box::new([[[BlockType::Air; CHUNK_SIZE_Z]; CHUNK_SIZE_Y]; CHUNK_SIZE_X]),
-            light: Box::new([[[15u8; CHUNK_SIZE_Z]; CHUNK_SIZE_Y]; CHUNK_SIZE_X]),
+            blocks: PalettedBlocks::filled(BlockType::Air),
+            light: None,
+            light_fill: 0xF0, // Unlit sections read as open sky until the lighting engine runs
             is_empty: true,
             mesh_dirty: true,
+            modified: false,
+            decorated: false,
+            biomes: [Biome::default(); CHUNK_SIZE_X * CHUNK_SIZE_Z],
         } 
     }
-    pub fn get_light(&self, x: usize, y: usize, z: usize) -> u8 { if x >= CHUNK_SIZE_X || y >= CHUNK_SIZE_Y || z >= CHUNK_SIZE_Z { return 15; } self.light[x][y][z] }
-#[allow(dead_code)]
-    pub fn set_light(&mut self, x: usize, y: usize, z: usize, val: u8) { if x < CHUNK_SIZE_X && y < CHUNK_SIZE_Y && z < CHUNK_SIZE_Z { self.light[x][y][z] = val; } }
-    pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockType { if x >= CHUNK_SIZE_X || y >= CHUNK_SIZE_Y || z >= CHUNK_SIZE_Z { return BlockType::Air; } self.blocks[x][y][z] }
-    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockType) { if x < CHUNK_SIZE_X && y < CHUNK_SIZE_Y && z < CHUNK_SIZE_Z { self.blocks[x][y][z] = block; } }
+    pub fn get_biome(&self, x: usize, z: usize) -> Biome { self.biomes[(z % CHUNK_SIZE_Z) * CHUNK_SIZE_X + x % CHUNK_SIZE_X] }
+    /// Fills the per-column biome IDs from worldgen noise; sections loaded from disk get them this way too.
+    pub fn fill_biomes(&mut self, cx: i32, cy: i32, cz: i32, noise_gen: &NoiseGenerator) {
+        for lz in 0..CHUNK_SIZE_Z { for lx in 0..CHUNK_SIZE_X {
+            let (wx, wz) = (cx * 16 + lx as i32, cz * 16 + lz as i32);
+            self.biomes[lz * CHUNK_SIZE_X + lx] = noise_gen.get_biome_at(wx, wz, cy * 16 + CHUNK_SIZE_Y as i32 / 2);
+        }}
+    }
+    #[inline]
+    fn packed_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        self.light.as_ref().map_or(self.light_fill, |l| l[PalettedBlocks::index(x, y, z)])
+    }
+    /// Brightest of the two channels, 0-15.
+    pub fn get_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        if x >= CHUNK_SIZE_X || y >= CHUNK_SIZE_Y || z >= CHUNK_SIZE_Z { return 15; }
+        let l = self.packed_light(x, y, z);
+        (l >> 4).max(l & 0xF)
+    }
+    pub fn get_sky_light(&self, x: usize, y: usize, z: usize) -> u8 { self.packed_light(x, y, z) >> 4 }
+    pub fn get_block_light(&self, x: usize, y: usize, z: usize) -> u8 { self.packed_light(x, y, z) & 0xF }
+    fn write_light(&mut self, x: usize, y: usize, z: usize, mask: u8, val: u8) {
+        let fill = self.light_fill;
+        let cell = &mut self.light.get_or_insert_with(|| Box::new([fill; SECTION_VOLUME]))[PalettedBlocks::index(x, y, z)];
+        *cell = (*cell & !mask) | (val & mask);
+    }
+    pub fn set_sky_light(&mut self, x: usize, y: usize, z: usize, val: u8) {
+        if self.light.is_none() && self.light_fill >> 4 == val { return; }
+        self.write_light(x, y, z, 0xF0, val << 4);
+    }
+    pub fn set_block_light(&mut self, x: usize, y: usize, z: usize, val: u8) {
+        if self.light.is_none() && self.light_fill & 0xF == val { return; }
+        self.write_light(x, y, z, 0x0F, val);
+    }
+    pub fn clear_light(&mut self) { self.light = None; self.light_fill = 0; }
+    /// Frees the light array again if every cell ended up with the same value (solid rock, open sky).
+    pub fn compact_light(&mut self) {
+        if let Some(l) = &self.light {
+            if l.iter().all(|&v| v == l[0]) { self.light_fill = l[0]; self.light = None; }
+        }
+    }
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockType { if x >= CHUNK_SIZE_X || y >= CHUNK_SIZE_Y || z >= CHUNK_SIZE_Z { return BlockType::Air; } self.blocks.get(x, y, z) }
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockType) { if x < CHUNK_SIZE_X && y < CHUNK_SIZE_Y && z < CHUNK_SIZE_Z { self.blocks.set(x, y, z, block); } }
 }
 #[allow(dead_code)]
 #[derive(Clone, Copy)]
 pub struct ItemEntity { pub position: Vec3, pub velocity: Vec3, pub item_type: BlockType, pub count: u8, pub pickup_delay: f32, pub lifetime: f32, pub rotation: f32, pub bob_offset: f32 }
 #[derive(Clone, Copy)]
-pub struct RemotePlayer { pub id: u32, pub position: Vec3, pub rotation: f32 }
+pub struct RemotePlayer { pub id: u32, pub position: Vec3, pub rotation: f32, pub pitch: f32 }
 
 #[derive(Clone)]
 pub struct World {
@@ -275,6 +477,28 @@ pub struct World {
     pub dirty_chunks: HashSet<(i32, i32, i32)>, // NEW: Priority mesh update queue
     pub remote_players: Vec<RemotePlayer>,
     pub seed: u32,
+    pub storage: Option<Arc<RegionStore>>, // None = in-memory only (e.g. clients of a remote host)
+    pub generator: Option<Arc<ChunkGenPool>>, // Started lazily on the first request
+    last_gen_request: Option<((i32, i32), i32)>,
+    pub lit_columns: HashSet<(i32, i32)>, // Columns the lighting engine has processed
+    pub(crate) lighting: HashMap<(i32, i32), bool>, // Columns the generation workers are lighting; true once a block in them changed meanwhile
+    pub decorated_columns: HashSet<(i32, i32)>, // Columns the decoration pass has processed
+    pub(crate) features: HashMap<(i32, i32), Vec<Placement>>, // Decoration features per source column, kept while neighbours still need them
+    pub view_distance: i32, // Columns kept loaded around the player; unloading starts at view_distance + UNLOAD_MARGIN
+    pub ticks: TickScheduler, // Game tick clock, random ticks and scheduled block updates
+}
+
+/// Per-chunk (or, with cy = -1, per-column) RNG seed. Every coordinate goes through a full 64-bit mix (splitmix64 finalizer) so
+/// neighbouring chunks, and chunks sharing an axis value, never start from the same stream.
+pub fn chunk_rng_seed(seed: u32, cx: i32, cy: i32, cz: i32) -> u64 {
+    let mut h = seed as u64 ^ 0x9E37_79B9_7F4A_7C15;
+    for v in [cx, cy, cz] {
+        h = (h ^ v as u32 as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        h ^= h >> 31;
+    }
+    h
 }
 
 impl World {
@@ -285,88 +509,278 @@ impl World {
             mesh_dirty: true,
             dirty_chunks: HashSet::new(),
             remote_players: Vec::new(), 
-            seed 
+            seed,
+            storage: None,
+            generator: None,
+            last_gen_request: None,
+            lit_columns: HashSet::new(),
+            lighting: HashMap::new(),
+            decorated_columns: HashSet::new(),
+            features: HashMap::new(),
+            view_distance: 8,
+            ticks: TickScheduler::new(seed),
         };
         // DIABOLICAL STARTUP: Do NOT generate terrain here.
         // The main loop will handle this during the Loading state to keep the OS responsive.
         world
     }
 
-pub fn generate_one_chunk_around(&mut self, cx: i32, _cy: i32, cz: i32, radius: i32) -> Option<(i32, i32, i32)> {
+    /// Same as `new`, but chunks are loaded from and saved to `saves/world_<seed>/region`.
+    pub fn new_persistent(seed: u32) -> Self {
+        let mut world = World::new(seed);
+        match RegionStore::open(format!("saves/world_{}/region", seed)) {
+            Ok(store) => world.storage = Some(Arc::new(store)),
+            Err(e) => log::warn!("[WORLD] Persistence disabled, could not open save dir: {}", e),
+        }
+        world
+    }
+
+    /// Writes every chunk edited since the last save back to its region file.
+    pub fn save_modified(&mut self) -> usize {
+        let Some(store) = self.storage.clone() else { return 0; };
+        let mut saved = 0;
+        for (&(cx, cy, cz), chunk) in self.chunks.iter_mut() {
+            if !chunk.modified { continue; }
+            match store.save_chunk(cx, cy, cz, chunk) {
+                Ok(()) => { chunk.modified = false; saved += 1; }
+                Err(e) => log::warn!("[WORLD] Failed to save chunk {:?}: {}", (cx, cy, cz), e),
+            }
+        }
+        if saved > 0 {
+            store.flush();
+            log::info!("[WORLD] Saved {} modified chunks", saved);
+        }
+        saved
+    }
+
+/// Queues every missing section within `radius` columns of (cx, cz) on the background generator.
+    /// Cheap to call every frame: the scan only reruns when the player column or radius changes.
+    pub fn request_chunks_around(&mut self, cx: i32, cz: i32, radius: i32) {
+        if self.last_gen_request == Some(((cx, cz), radius)) { return; }
+        self.last_gen_request = Some(((cx, cz), radius));
+        let seed = self.seed;
+        let storage = self.storage.clone();
+        let pool = self.generator.get_or_insert_with(|| Arc::new(ChunkGenPool::new(seed, storage))).clone();
+        // Keep a little slack so requests at the ring edge don't get dropped and re-queued constantly
+        pool.reprioritize((cx, cz), radius + 2);
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                if x * x + z * z > radius * radius { continue; }
+                for y in 0..(WORLD_HEIGHT / 16) {
+                    let key = (cx + x, y, cz + z);
+                    if !self.chunks.contains_key(&key) { pool.request(key); }
+                }
+            }
+        }
+    }
+
+    /// Moves finished chunks from the generator into the world and returns their keys.
+    pub fn collect_generated(&mut self) -> Vec<(i32, i32, i32)> {
+        let Some(pool) = self.generator.clone() else { return Vec::new(); };
+        let mut added = Vec::new();
+        for (key, chunk) in pool.drain() {
+            // A synchronous ensure_column() may have beaten the worker to it
+            if let std::collections::hash_map::Entry::Vacant(e) = self.chunks.entry(key) {
+                e.insert(chunk);
+                added.push(key);
+            }
+        }
+        if !added.is_empty() { self.mesh_dirty = true; }
+        for ((cx, cz), lit) in pool.drain_lit() {
+            match self.lighting.remove(&(cx, cz)) {
+                Some(false) => { self.merge_column_light(cx, cz, lit); }
+                Some(true) => { self.light_column(cx, cz); } // Edited while out; the copy is stale
+                None => {} // Unloaded in the meantime
+            }
+        }
+        let columns: HashSet<(i32, i32)> = added.iter().map(|k| (k.0, k.2)).collect();
+        self.finish_columns(columns, true);
+        added
+    }
+
+    pub fn pending_generation(&self) -> usize { self.generator.as_ref().map_or(0, |g| g.pending()) }
+
+    /// Columns further than this from the player (every player, on a server) get unloaded.
+    pub fn unload_distance(&self) -> i32 { self.view_distance + UNLOAD_MARGIN }
+
+    /// Columns within this distance are never evicted to stay under the chunk budget: the ones in
+    /// view, plus the 3x3 around each that decorating it needs (at most a diagonal step further).
+    pub fn needed_distance(&self) -> f32 { self.view_distance as f32 + std::f32::consts::SQRT_2 }
+
+    /// Largest view distance whose needed columns around one player fit in `max_chunks` sections,
+    /// so evicting what lies past them always brings the world back under the budget.
+    pub fn max_view_distance(max_chunks: usize) -> i32 {
+        let sections = (WORLD_HEIGHT / 16) as usize;
+        let fits = |view: &i32| {
+            let needed = *view as f32 + std::f32::consts::SQRT_2;
+            let (r, needed_sq) = (needed as i32, (needed * needed) as i32);
+            let columns = (-r..=r).flat_map(|x| (-r..=r).map(move |z| x * x + z * z)).filter(|&d| d <= needed_sq).count();
+            columns * sections <= max_chunks
+        };
+        (0..).take_while(fits).last().unwrap_or(0)
+    }
+
+    /// Drops chunks further than `unload_distance` columns from (cx, cz), then the farthest ones
+    /// outside `needed_distance` until at most `max_chunks` are loaded (always possible while the
+    /// view distance is within `max_view_distance(max_chunks)`). Modified chunks are written
+    /// to their region first (and kept if that fails); a world without a region store has nowhere
+    /// to keep them, so they go like the rest. Returns the removed keys so the renderer can free
+    /// their meshes.
+    pub fn unload_far_chunks(&mut self, cx: i32, cz: i32, max_chunks: usize) -> Vec<(i32, i32, i32)> {
+        self.unload_chunks_outside(&[(cx, cz)], max_chunks)
+    }
+
+    /// `unload_far_chunks` for several centres (e.g. every player on a server): a chunk stays while
+    /// it is in range of any of them, and distance means distance to the nearest one. Players far
+    /// apart can need more than the budget between them; what they need is kept anyway.
+    pub fn unload_chunks_outside(&mut self, centers: &[(i32, i32)], max_chunks: usize) -> Vec<(i32, i32, i32)> {
+        let (keep, needed) = (self.unload_distance(), self.needed_distance());
+        let needed_sq = (needed * needed) as i32; // Squared distances are whole numbers
+        let dist = |k: &(i32, i32, i32)| centers.iter().map(|&(cx, cz)| (k.0 - cx) * (k.0 - cx) + (k.2 - cz) * (k.2 - cz)).min().unwrap_or(i32::MAX);
+        let mut victims: Vec<_> = self.chunks.keys().filter(|k| dist(k) > keep * keep).cloned().collect();
+        let loaded = self.chunks.len() - victims.len();
+        if loaded > max_chunks {
+            // Evicting what's in view would only get it requested and loaded again right away
+            let mut spare: Vec<_> = self.chunks.keys().filter(|k| (needed_sq + 1..=keep * keep).contains(&dist(k))).cloned().collect();
+            spare.sort_unstable_by_key(|k| std::cmp::Reverse(dist(k)));
+            let excess = (loaded - max_chunks).min(spare.len());
+            log::warn!("[WORLD] Chunk budget exceeded ({} > {}), evicting {} chunks past the view distance", loaded, max_chunks, excess);
+            victims.extend(spare.into_iter().take(excess));
+        }
+
+        let mut removed = Vec::with_capacity(victims.len());
+        for key in victims {
+            let Some(chunk) = self.chunks.get(&key) else { continue; };
+            if let Some(store) = self.storage.as_ref().filter(|_| chunk.modified) {
+                if let Err(e) = store.save_chunk(key.0, key.1, key.2, chunk) {
+                    log::warn!("[WORLD] Keeping chunk {:?} loaded, save failed: {}", key, e);
+                    continue;
+                }
+            }
+            self.chunks.remove(&key);
+            self.dirty_chunks.remove(&key);
+            self.lit_columns.remove(&(key.0, key.2));
+            self.lighting.remove(&(key.0, key.2));
+            self.decorated_columns.remove(&(key.0, key.2));
+            self.features.remove(&(key.0, key.2));
+            removed.push(key);
+        }
+        if !removed.is_empty() {
+            log::info!("[WORLD] Unloaded {} chunks ({} loaded)", removed.len(), self.chunks.len());
+        }
+        removed
+    }
+
+    /// Blocking load/generate of one column on the calling thread. Only for code that can't wait,
+    /// like the spawn scout; everything else should go through `request_chunks_around`.
+    pub fn ensure_column(&mut self, cx: i32, cz: i32) {
+        let mut noise_gen = None;
+        for y in 0..(WORLD_HEIGHT / 16) {
+            if self.chunks.contains_key(&(cx, y, cz)) { continue; }
+            let noise_gen = noise_gen.get_or_insert_with(|| NoiseGenerator::new(self.seed));
+            let chunk = match self.storage.as_ref().and_then(|s| s.load_chunk(cx, y, cz)) {
+                Some(mut chunk) => { chunk.fill_biomes(cx, y, cz, noise_gen); chunk }
+                None => World::generate_single_chunk(cx, y, cz, noise_gen),
+            };
+            self.chunks.insert((cx, y, cz), chunk);
+        }
+        self.finish_columns(HashSet::from([(cx, cz)]), false);
+    }
+
+    /// Replaces column (cx, cz) with `sections` (bottom to top) as a server sent them, then lights it
+    /// once its neighbours are in too. Returns the sections to remesh.
+    pub fn insert_column(&mut self, cx: i32, cz: i32, sections: Vec<Chunk>) -> Vec<(i32, i32, i32)> {
         let noise_gen = NoiseGenerator::new(self.seed);
-        for r in 0..=radius {
-            for x in -r..=r {
-                for z in -r..=r {
-                    let tcx = cx + x;
-                    let tcz = cz + z;
-                    // Check all vertical chunks for this column
-                    for y in 0..(WORLD_HEIGHT / 16 as i32) {
-                        if !self.chunks.contains_key(&(tcx, y, tcz)) {
-                            self.generate_single_chunk(tcx, y, tcz, &noise_gen);
-                            return Some((tcx, y, tcz));
-                        }
-                    }
+        let mut keys = Vec::with_capacity(sections.len());
+        for (cy, mut chunk) in sections.into_iter().enumerate() {
+            let key = (cx, cy as i32, cz);
+            chunk.fill_biomes(cx, key.1, cz, &noise_gen);
+            chunk.mesh_dirty = true;
+            self.chunks.insert(key, chunk);
+            keys.push(key);
+        }
+        // Relight from scratch in case this replaces an older copy of the column
+        self.lit_columns.remove(&(cx, cz));
+        self.lighting.remove(&(cx, cz));
+        self.decorated_columns.remove(&(cx, cz));
+        self.features.remove(&(cx, cz));
+        self.mesh_dirty = true;
+        self.finish_columns(HashSet::from([(cx, cz)]), false);
+        keys
+    }
+
+    /// Drops column (cx, cz) without saving it, e.g. when a server stops streaming it to us.
+    /// Returns the removed sections so the renderer can free their meshes.
+    pub fn remove_column(&mut self, cx: i32, cz: i32) -> Vec<(i32, i32, i32)> {
+        let removed: Vec<_> = (0..WORLD_HEIGHT / 16).map(|cy| (cx, cy, cz)).filter(|key| self.chunks.remove(key).is_some()).collect();
+        for key in &removed { self.dirty_chunks.remove(key); }
+        self.lit_columns.remove(&(cx, cz));
+        self.lighting.remove(&(cx, cz));
+        self.decorated_columns.remove(&(cx, cz));
+        self.features.remove(&(cx, cz));
+        removed
+    }
+
+    /// Decorates, then lights, every column around `columns` whose 3x3 neighbourhood now has terrain.
+    /// Lighting waits for decoration so trees are lit correctly the first time. In the `background`
+    /// the generation workers light the column and `collect_generated` merges it in later.
+    fn finish_columns(&mut self, columns: HashSet<(i32, i32)>, background: bool) {
+        let mut ready: Vec<(i32, i32)> = columns.iter()
+            .flat_map(|&(cx, cz)| (-1..=1).flat_map(move |dx| (-1..=1).map(move |dz| (cx + dx, cz + dz))))
+            .collect();
+        ready.sort_unstable();
+        ready.dedup();
+        for (cx, cz) in ready {
+            if !self.decorate_column(cx, cz) || self.lit_columns.contains(&(cx, cz)) { continue; }
+            match self.generator.clone().filter(|_| background) {
+                Some(pool) => {
+                    let sections = (0..WORLD_HEIGHT / 16).filter_map(|cy| self.chunks.get(&(cx, cy, cz)).cloned()).collect();
+                    self.lighting.insert((cx, cz), false);
+                    pool.light((cx, cz), sections);
                 }
+                None => { self.light_column(cx, cz); }
             }
         }
-        None
     }
 
+    /// Synchronous generation of a square area, blocking the caller.
 pub fn generate_terrain_around(&mut self, cx: i32, cz: i32, radius: i32) -> Vec<(i32, i32, i32)> {
         let mut newly_generated = Vec::new();
-        let noise_gen = NoiseGenerator::new(self.seed);
         for x in -radius..=radius {
             for z in -radius..=radius {
                 for y in 0..(WORLD_HEIGHT / 16 as i32) {
-                    let (tcx, tcy, tcz) = (cx + x, y, cz + z);
-                    if !self.chunks.contains_key(&(tcx, tcy, tcz)) {
-                        self.generate_single_chunk(tcx, tcy, tcz, &noise_gen);
-                        newly_generated.push((tcx, tcy, tcz));
-                    }
+                    let key = (cx + x, y, cz + z);
+                    if !self.chunks.contains_key(&key) { newly_generated.push(key); }
                 }
+                self.ensure_column(cx + x, cz + z);
             }
         }
         newly_generated
     }
 
-    pub fn bootstrap_terrain_step(&mut self, step: i32) -> bool {
-        let radius = 6;
-        let side = radius * 2 + 1;
-        if step >= side * side { return true; }
-        
-        let x = (step % side) - radius;
-        let z = (step / side) - radius;
-        let noise_gen = NoiseGenerator::new(self.seed);
-        
-        log::info!("[WORLD] Generating Column: X:{:<3} Z:{:<3} (Step {}/{})", x, z, step, side * side);
-
-        for y in 0..(WORLD_HEIGHT / 16) {
-            if !self.chunks.contains_key(&(x, y, z)) {
-                self.generate_single_chunk(x, y, z, &noise_gen);
-            }
-        }
-        false
-    }
 pub fn _update_occlusion(&mut self, _px: i32, _py: i32, _pz: i32) {
         // ROOT FIX: Occlusion is now handled by the Frustum Culler in the Renderer.
         // We do nothing here to keep the World structure lightweight.
     }
 
-    fn generate_single_chunk(&mut self, cx: i32, cy: i32, cz: i32, noise_gen: &NoiseGenerator) {
+    /// Terrain phase only (stone, soil, surface blocks, water, bedrock); trees, ores and other
+    /// features are added later by the decoration pass once the neighbouring columns exist.
+    /// Pure function of (seed, coordinates): safe to run on any worker thread.
+    /// `worldgen_check` hashes its output against golden values, so any change here shows up in tests.
+    pub fn generate_single_chunk(cx: i32, cy: i32, cz: i32, noise_gen: &NoiseGenerator) -> Chunk {
         let mut chunk = Chunk::new();
         let chunk_x_world = cx * 16;
         let chunk_y_world = cy * 16;
         let chunk_z_world = cz * 16;
-        let mut rng = SimpleRng::new((cx as u64).wrapping_mul(self.seed as u64) ^ (cy as u64) ^ (cz as u64));
-        let mut tree_map: HashSet<(i32, i32)> = HashSet::new();
 
         for lx in 0..CHUNK_SIZE_X {
             for lz in 0..CHUNK_SIZE_Z {
                 let wx = chunk_x_world + lx as i32;
                 let wz = chunk_z_world + lz as i32;
                 let (cont, eros, weird, temp) = noise_gen.get_height_params(wx, wz);
-                let humid = noise_gen.get_noise_octaves(wx as f64 * 0.01, 123.0, wz as f64 * 0.01, 3) as f32;
-                
+                let humid = noise_gen.get_humidity(wx, wz);
+                chunk.biomes[lz * CHUNK_SIZE_X + lx] = noise_gen.get_biome(cont, eros, temp, humid, chunk_y_world + CHUNK_SIZE_Y as i32 / 2);
+
                 // DIABOLICAL VOLUMETRIC SAMPLING
                 for ly in 0..16 {
                     let y_world = chunk_y_world + ly as i32;
@@ -376,14 +790,12 @@ pub fn _update_occlusion(&mut self, _px: i32, _py: i32, _pz: i32) {
                     if density > 0.0 {
                         let density_above = noise_gen.get_density(wx, y_world + 1, wz, cont, eros, weird);
                         let is_surface = density_above <= 0.0 && y_world > WATER_LEVEL;
-                        let biome = noise_gen.get_biome(cont, eros, temp, humid, y_world);
-                        
+                        let biome = noise_gen.get_biome(cont, eros, temp, humid, y_world).def();
+
                         block = if is_surface {
-                            if biome == "desert" || biome == "ocean" { BlockType::Sand }
-                            else if biome == "ice_plains" { BlockType::Snow }
-                            else { BlockType::Grass }
+                            biome.surface
                         } else if density < 0.15 && y_world > WATER_LEVEL - 5 {
-                            if biome == "desert" { BlockType::Sand } else { BlockType::Dirt }
+                            biome.filler
                         } else {
                             BlockType::Stone
                         };
@@ -394,82 +806,14 @@ pub fn _update_occlusion(&mut self, _px: i32, _py: i32, _pz: i32) {
                     if y_world < 2 { block = BlockType::Bedrock; }
 
                     if block != BlockType::Air {
-                        if block == BlockType::Stone {
-                            let ore_rng = (wx as u64).wrapping_mul(31234) ^ (y_world as u64).wrapping_mul(7123) ^ (wz as u64).wrapping_mul(1234);
-                            let ore_chance = (ore_rng % 1000) as f32 / 10.0;
-                            if y_world < 16 && ore_chance < 0.2 { block = BlockType::DiamondOre; }
-                            else if y_world < 32 && ore_chance < 0.5 { block = BlockType::GoldOre; }
-                            else if y_world < 48 && ore_chance < 0.8 { block = BlockType::LapisOre; }
-                            else if y_world < 64 && ore_chance < 1.2 { block = BlockType::RedstoneOre; }
-                            else if y_world < 64 && ore_chance < 2.5 { block = BlockType::IronOre; }
-                            else if ore_chance < 4.0 { block = BlockType::CoalOre; }
-                        }
                         chunk.set_block(lx, ly, lz, block);
                         chunk.is_empty = false;
                     }
                 }
-
-                // DIABOLICAL SURFACE SCAN
-                for ly in 0..CHUNK_SIZE_Y {
-                    let y_world = chunk_y_world + ly as i32;
-                    let density = noise_gen.get_density(wx, y_world, wz, cont, eros, weird);
-                    let density_above = noise_gen.get_density(wx, y_world + 1, wz, cont, eros, weird);
-                    
-                    if density > 0.0 && density_above <= 0.0 && y_world > WATER_LEVEL {
-                        let biome = noise_gen.get_biome(cont, eros, temp, humid, y_world);
-                        let r = rng.next_f32();
-                        let ground_block = chunk.get_block(lx, ly, lz);
-
-                        if matches!(ground_block, BlockType::Grass | BlockType::Dirt | BlockType::Sand | BlockType::Snow) {
-                            if noise_gen.get_density(wx, y_world + 5, wz, cont, eros, weird) < 0.0 {
-                                let mut too_close = false;
-                                for dx in -4..=4 { 
-                                    for dz in -4..=4 { 
-                                        if tree_map.contains(&(lx as i32 + dx, lz as i32 + dz)) { too_close = true; break; } 
-                                    } 
-                                    if too_close { break; } 
-                                }
-
-                                if !too_close {
-                                    if (biome == "forest" || biome == "jungle") && r < 0.05 && y_world > 64 {
-                                        tree_map.insert((lx as i32, lz as i32));
-                                        let tree_h = 5 + (rng.next_f32() * 3.0) as i32;
-                                        for i in 1..=tree_h { 
-                                            let ty = y_world + i;
-                                            if ty >= chunk_y_world && ty < chunk_y_world + 16 {
-                                                chunk.set_block(lx, (ty - chunk_y_world) as usize, lz, BlockType::Wood); 
-                                            }
-                                        }
-                                        for dy in (tree_h - 2)..=(tree_h + 1) {
-                                            let radius: i32 = if dy > tree_h { 0 } else if dy == tree_h { 1 } else { 2 };
-                                            for dx in -radius..=radius {
-                                                for dz in -radius..=radius {
-                                                    if dx.abs() + dz.abs() > radius + 1 { continue; }
-                                                    let ty = y_world + dy;
-                                                    let tlx = lx as i32 + dx;
-                                                    let tlz = lz as i32 + dz;
-                                                    if ty >= chunk_y_world && ty < chunk_y_world + 16 && tlx >= 0 && tlx < 16 && tlz >= 0 && tlz < 16 {
-                                                        let cur = chunk.get_block(tlx as usize, (ty - chunk_y_world) as usize, tlz as usize);
-                                                        if cur == BlockType::Air {
-                                                            chunk.set_block(tlx as usize, (ty - chunk_y_world) as usize, tlz as usize, BlockType::Leaves);
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    } else if r < 0.02 {
-                                        if ly + 1 < 16 {
-                                            chunk.set_block(lx, ly + 1, lz, if biome == "desert" { BlockType::DeadBush } else { BlockType::Rose });
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
             }
         }
-        self.chunks.insert((cx, cy, cz), chunk);
+        chunk.blocks.compact();
+        chunk
     }
 
     fn _get_height_at_in_chunk(&self, chunk: &Chunk, lx: usize, lz: usize) -> i32 {
@@ -515,8 +859,8 @@ pub fn get_light_world(&self, pos: BlockPos) -> u8 {
             let local_x = (x as i32).rem_euclid(16) as usize;
             let local_z = (z as i32).rem_euclid(16) as usize;
             
-            for y in (0..256).rev() {
-                let block_type = chunk.blocks[local_x][y][local_z];
+            for y in (0..CHUNK_SIZE_Y).rev() {
+                let block_type = chunk.get_block(local_x, y, local_z);
                 if block_type.is_solid() {
                     return y as f32 + 1.0;
                 }
@@ -525,6 +869,13 @@ pub fn get_light_world(&self, pos: BlockPos) -> u8 {
         
         0.0 // Default ground height if no chunk found
     }
+    /// Biome stored for the column at `pos` in its section, if that section is loaded.
+    pub fn get_biome(&self, pos: BlockPos) -> Option<Biome> {
+        if pos.y < 0 || pos.y >= WORLD_HEIGHT { return None; }
+        self.chunks.get(&(pos.x.div_euclid(16), pos.y.div_euclid(16), pos.z.div_euclid(16)))
+            .map(|c| c.get_biome(pos.x.rem_euclid(16) as usize, pos.z.rem_euclid(16) as usize))
+    }
+
     pub fn get_block(&self, pos: BlockPos) -> BlockType {
         let cx = pos.x.div_euclid(16); let cy = pos.y.div_euclid(16); let cz = pos.z.div_euclid(16);
         let lx = pos.x.rem_euclid(16) as usize; let ly = pos.y.rem_euclid(16) as usize; let lz = pos.z.rem_euclid(16) as usize;
@@ -538,7 +889,10 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
         if let Some(chunk) = self.chunks.get_mut(&(cx, cy, cz)) { 
             chunk.set_block(lx, ly, lz, block); 
             chunk.mesh_dirty = true;
+            chunk.modified = true;
             if block != BlockType::Air { chunk.is_empty = false; }
+            self.relight_at(pos);
+            self.notify_neighbours(pos);
         }
     }
     pub fn raycast(&self, origin: Vec3, direction: Vec3, max_dist: f32) -> Option<(BlockPos, BlockPos)> {
@@ -559,44 +913,30 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
         None
     }
 // --- PHYSICS & LOGIC ---
-#[allow(dead_code)]
     pub fn update_block_physics(&mut self, pos: BlockPos) -> Vec<(i32, i32, i32)> {
         let mut affected = Vec::new();
         let b = self.get_block(pos);
         
-        // 1. Gravity (Sand/Gravel)
-        if matches!(b, BlockType::Sand | BlockType::Gravel) {
-            let below_pos = BlockPos { x: pos.x, y: pos.y - 1, z: pos.z };
-            let below = self.get_block(below_pos);
-            if below == BlockType::Air || below.is_water() || below == BlockType::Lava {
-                self.set_block_world(pos, BlockType::Air);
-                self.set_block_world(below_pos, b);
-                affected.extend(self.get_affected_chunks(pos));
-                affected.extend(self.get_affected_chunks(below_pos));
-                // Recursive update
-                affected.extend(self.update_block_physics(below_pos));
-                // Update neighbors of old pos
-                for (dx, dy, dz) in &[(0,1,0), (0,-1,0), (1,0,0), (-1,0,0), (0,0,1), (0,0,-1)] {
-                     affected.extend(self.update_block_physics(BlockPos{x:pos.x+dx, y:pos.y+dy, z:pos.z+dz}));
-                }
-            }
-        }
-
-        // 2. Fluid Interaction (Obsidian/Cobble Gen)
-        if b == BlockType::Lava {
+        // Gravity for sand/gravel lives in the tick system (tick::Falling)
+        // Fluid Interaction (Obsidian/Cobble Gen): lava sources harden to obsidian, flowing lava to cobblestone
+        let hardened = |lava: BlockType| if lava == BlockType::Lava { BlockType::Obsidian } else { BlockType::Cobblestone };
+        if b.is_lava() {
              for (dx, dy, dz) in &[(1,0,0), (-1,0,0), (0,0,1), (0,0,-1), (0,1,0)] {
                  let n_pos = BlockPos{x:pos.x+dx, y:pos.y+dy, z:pos.z+dz};
                  if self.get_block(n_pos).is_water() {
-                     self.set_block_world(pos, BlockType::Obsidian);
+                     self.set_block_world(pos, hardened(b));
+                     self.ticks.note_change(pos);
                      affected.extend(self.get_affected_chunks(pos));
                      break;
                  }
              }
         } else if b.is_water() {
-             for (dx, dy, dz) in &[(1,0,0), (-1,0,0), (0,0,1), (0,0,-1), (0,1,0)] {
+             for (dx, dy, dz) in &[(1,0,0), (-1,0,0), (0,0,1), (0,0,-1), (0,1,0), (0,-1,0)] {
                  let n_pos = BlockPos{x:pos.x+dx, y:pos.y+dy, z:pos.z+dz};
-                 if self.get_block(n_pos) == BlockType::Lava {
-                     self.set_block_world(n_pos, BlockType::Obsidian);
+                 let n = self.get_block(n_pos);
+                 if n.is_lava() {
+                     self.set_block_world(n_pos, hardened(n));
+                     self.ticks.note_change(n_pos);
                      affected.extend(self.get_affected_chunks(n_pos));
                  }
              }
@@ -658,19 +998,8 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
                 let ly = pos.y.rem_euclid(16) as usize;
                 let lz = pos.z.rem_euclid(16) as usize;
                 chunk.set_block(lx, ly, lz, BlockType::Air);
-                let mut is_empty = true;
-                for x in 0..16 {
-                    for y in 0..16 {
-                        for z in 0..16 {
-                            if chunk.blocks[x][y][z] != BlockType::Air {
-                                is_empty = false;
-                                break;
-                            }
-                        }
-                        if !is_empty { break; }
-                    }
-                    if !is_empty { break; }
-                }
+                chunk.blocks.compact();
+                let is_empty = chunk.blocks.is_uniform(BlockType::Air);
                 if is_empty {
                     chunk.is_empty = true;
                 }
@@ -707,8 +1036,7 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
         
         self.set_block_world(pos, BlockType::Air);
         
-        let mut affected = self.trigger_water_update(pos);
-        affected.extend(self.get_affected_chunks(pos));
+        let mut affected = self.get_affected_chunks(pos);
         affected.sort_unstable();
         affected.dedup();
         
@@ -723,8 +1051,7 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
     }
     pub fn place_block(&mut self, pos: BlockPos, block: BlockType) -> Vec<(i32, i32, i32)> { 
         self.set_block_world(pos, block); 
-        let mut affected = self.trigger_water_update(pos);
-        affected.extend(self.get_affected_chunks(pos));
+        let mut affected = self.get_affected_chunks(pos);
         affected.sort_unstable();
         affected.dedup();
         
@@ -737,37 +1064,24 @@ pub fn set_block_world(&mut self, pos: BlockPos, block: BlockType) {
         self.mesh_dirty = true;
         affected
     }
-    fn trigger_water_update(&mut self, start_pos: BlockPos) -> Vec<(i32, i32, i32)> {
-        let mut updates = Vec::new();
-        let mut queue = VecDeque::new();
-        queue.push_back(start_pos);
-        let mut visited = HashSet::new();
-        let mut steps = 0;
-
-        while let Some(pos) = queue.pop_front() {
-            if steps > 15 { break; } // Hard limit for performance
-            if !visited.insert(pos) { continue; }
-            steps += 1;
-
-            let current = self.get_block(pos);
-            let cx = pos.x.div_euclid(16);
-            let cy = pos.y.div_euclid(16);
-            let cz = pos.z.div_euclid(16);
-            updates.push((cx, cy, cz));
-
-            if current.is_water() {
-                let below = BlockPos { x: pos.x, y: pos.y - 1, z: pos.z };
-                if self.get_block(below) == BlockType::Air {
-                    self.set_block_world(below, BlockType::Water);
-                    queue.push_back(below);
-                }
+/// Item physics, with the local player pulling in and picking up items close to them.
+pub fn update_entities(&mut self, dt: f32, player: &mut Player) {
+    self.update_items(dt, |entity, dt| {
+        let dist_sq = entity.position.distance_squared(player.position);
+        if dist_sq < 9.0 && entity.pickup_delay <= 0.0 {
+            let dir = (player.position - entity.position).normalize(); entity.position += dir * 10.0 * dt;
+            if dist_sq < 2.25 && player.inventory.add_item(entity.item_type) {
+                log::info!("🎁 Picked up {:?}", entity.item_type);
+                return true;
             }
         }
-        updates.sort_unstable();
-        updates.dedup();
-        updates
-    }
-pub fn update_entities(&mut self, dt: f32, player: &mut Player) {
+        false
+    });
+}
+
+/// Ages, drops and collides every item entity. `collect` runs after the physics step and returns
+/// true when something picked the item up, which removes it.
+pub fn update_items(&mut self, dt: f32, mut collect: impl FnMut(&mut ItemEntity, f32) -> bool) {
     let entities = std::mem::take(&mut self.entities);
     let mut retained = Vec::new();
     for mut entity in entities {
@@ -804,18 +1118,9 @@ pub fn update_entities(&mut self, dt: f32, player: &mut Player) {
         }
 
         // Pickup Logic
-        let dist_sq = entity.position.distance_squared(player.position);
-        if dist_sq < 9.0 && entity.pickup_delay <= 0.0 {
-            let dir = (player.position - entity.position).normalize(); entity.position += dir * 10.0 * dt;
-if dist_sq < 2.25 { 
-                    if player.inventory.add_item(entity.item_type) { 
-                        log::info!("🎁 Picked up {:?}", entity.item_type);
-                        continue; 
-                    }
-                }
-            }
-            retained.push(entity);
-        }
-        self.entities = retained;
+        if collect(&mut entity, dt) { continue; }
+        retained.push(entity);
     }
+    self.entities = retained;
+}
 }
\ No newline at end of file
@@ -0,0 +1,80 @@
+//! Headless worldgen regression harness
+//!
+//! Generates a fixed block of chunks for a list of seeds and hashes every chunk's block IDs, so a
+//! change to terrain generation or decoration shows up as a diff against the golden hashes in
+//! `tests/golden/worldgen.txt`. Regenerate that file with `cargo run -- --worldgen-hashes` when a
+//! terrain change is intentional, and review the diff like any other change.
+
+use crate::noise_gen::NoiseGenerator;
+use crate::world::{Chunk, World, CHUNK_SIZE_X, CHUNK_SIZE_Y, CHUNK_SIZE_Z, WORLD_HEIGHT};
+
+/// Seeds covered by the golden file.
+pub const GOLDEN_SEEDS: [u32; 3] = [0, 12345, 0xDEAD_BEEF];
+/// Columns hashed around the origin, in chunks (so -1..=1 on both axes, every section).
+pub const GOLDEN_RADIUS: i32 = 1;
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// FNV-1a over the block IDs in (y, z, x) order; independent of how the section is paletted.
+pub fn chunk_hash(chunk: &Chunk) -> u64 {
+    let mut h = FNV_OFFSET;
+    for y in 0..CHUNK_SIZE_Y { for z in 0..CHUNK_SIZE_Z { for x in 0..CHUNK_SIZE_X {
+        for byte in (chunk.get_block(x, y, z) as u16).to_le_bytes() {
+            h ^= byte as u64;
+            h = h.wrapping_mul(FNV_PRIME);
+        }
+    }}}
+    h
+}
+
+/// Hashes of every chunk in the golden area for `seed`, sorted by chunk key. Terrain is generated one
+/// column further out so the decoration pass can finish every hashed column.
+pub fn hash_area(seed: u32) -> Vec<((i32, i32, i32), u64)> {
+    let noise_gen = NoiseGenerator::new(seed);
+    let mut world = World::new(seed);
+    let sections = WORLD_HEIGHT / CHUNK_SIZE_Y as i32;
+    let terrain = GOLDEN_RADIUS + 1;
+    for cx in -terrain..=terrain {
+        for cz in -terrain..=terrain {
+            for cy in 0..sections {
+                world.chunks.insert((cx, cy, cz), World::generate_single_chunk(cx, cy, cz, &noise_gen));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for cx in -GOLDEN_RADIUS..=GOLDEN_RADIUS {
+        for cz in -GOLDEN_RADIUS..=GOLDEN_RADIUS {
+            world.decorate_column(cx, cz);
+            for cy in 0..sections {
+                out.push(((cx, cy, cz), chunk_hash(&world.chunks[&(cx, cy, cz)])));
+            }
+        }
+    }
+    out.sort_unstable_by_key(|&(key, _)| key);
+    out
+}
+
+/// One line per chunk: `seed cx cy cz hash`, the format of the golden file.
+pub fn format_hashes(seeds: &[u32]) -> String {
+    let mut out = String::from("# seed cx cy cz fnv1a64 -- regenerate with `cargo run -- --worldgen-hashes`\n");
+    for &seed in seeds {
+        for ((cx, cy, cz), h) in hash_area(seed) {
+            out.push_str(&format!("{} {} {} {} {:016x}\n", seed, cx, cy, cz, h));
+        }
+    }
+    out
+}
+
+/// Parses a golden file back into `(seed, key, hash)` entries, skipping comments and blank lines.
+pub fn parse_hashes(text: &str) -> Vec<(u32, (i32, i32, i32), u64)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let f: Vec<&str> = l.split_whitespace().collect();
+            if f.len() != 5 { return None; }
+            Some((f[0].parse().ok()?, (f[1].parse().ok()?, f[2].parse().ok()?, f[3].parse().ok()?), u64::from_str_radix(f[4], 16).ok()?))
+        })
+        .collect()
+}
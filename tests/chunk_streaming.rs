@@ -0,0 +1,120 @@
+//! Chunk streaming test: a client joining a dedicated server is sent the real columns around it
+//! (edits included) and what the game tick changes in them afterwards, is told to drop them once it
+//! walks away, and a received column goes into a client world intact and gets lit once its
+//! neighbours are there.
+
+use std::collections::HashSet;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver};
+use minecraft_clone::config_system::NetworkConfig;
+use minecraft_clone::network::{decode_column, Packet};
+use minecraft_clone::prediction::{InputCommand, KEY_FORWARD, KEY_JUMP, KEY_SPRINT, KEY_UP};
+use minecraft_clone::protocol::{write_packet, PacketReader};
+use minecraft_clone::server::Server;
+use minecraft_clone::world::{BlockPos, BlockType, World};
+
+/// Heading (towards -x, +z) along which this world's terrain lets a running, jumping player get
+/// well away from spawn; the server only moves players by walking physics.
+const RUN_YAW: f32 = 3.0 * std::f32::consts::FRAC_PI_4;
+
+/// Ticks the server until `done` says so, feeding it whatever the client received.
+fn pump(server: &mut Server, inbox: &Receiver<Packet>, mut done: impl FnMut(Packet) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(60);
+    loop {
+        server.tick(0.05);
+        while let Ok(packet) = inbox.try_recv() {
+            if done(packet) { return; }
+        }
+        assert!(Instant::now() < deadline, "server never sent what the client was waiting for");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn columns_follow_the_player() {
+    let config = NetworkConfig { server_port: 0, view_distance: 1, ..Default::default() };
+    let mut server = Server::bind(World::new(4242), config).unwrap();
+    let edit = BlockPos { x: 3, y: 120, z: 4 };
+    server.world.ensure_column(0, 0);
+    server.world.place_block(edit, BlockType::Glass);
+
+    let mut stream = TcpStream::connect(("127.0.0.1", server.local_addr().unwrap().port())).unwrap();
+    let (tx, inbox) = unbounded();
+    let mut reader = PacketReader::new(stream.try_clone().unwrap());
+    std::thread::spawn(move || {
+        while let Ok(Some(packet)) = reader.read_packet() {
+            if tx.send(packet).is_err() { break; }
+        }
+    });
+
+    // Nobody has moved yet, so the client gets the view circle around spawn and nothing else
+    let mut client = World::new(4242);
+    let circle: HashSet<_> = [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].into();
+    let mut received = HashSet::new();
+    pump(&mut server, &inbox, |packet| {
+        if let Packet::ChunkColumn { cx, cz, sections } = packet {
+            assert!(received.insert((cx, cz)), "column {:?} sent twice", (cx, cz));
+            client.insert_column(cx, cz, decode_column(&sections).unwrap());
+        }
+        received == circle
+    });
+    assert_eq!(client.get_block(edit), BlockType::Glass);
+
+    // Sand dropped in by the server falls onto the glass, and the client hears about it
+    let sand = BlockPos { x: edit.x, y: edit.y + 3, z: edit.z };
+    server.world.place_block(sand, BlockType::Sand);
+    let landed = BlockPos { x: edit.x, y: edit.y + 1, z: edit.z };
+    pump(&mut server, &inbox, |packet| {
+        if let Packet::BlockUpdate { pos, block } = packet { client.place_block(pos, block); }
+        client.get_block(landed) == BlockType::Sand
+    });
+    assert_eq!(client.get_block(sand), BlockType::Air);
+
+    // Running far away unloads every column sent around spawn (and sends others on the way)
+    let around_spawn = received.clone();
+    let deadline = Instant::now() + Duration::from_secs(60);
+    for seq in 1.. {
+        let run = InputCommand { seq, dt: 0.05, keys: KEY_FORWARD | KEY_SPRINT | KEY_JUMP | KEY_UP, yaw: RUN_YAW, pitch: 0.0, admin_speed: 1.0, respawn: false };
+        write_packet(&mut stream, &Packet::PlayerInput { commands: vec![run] }).unwrap();
+        std::thread::sleep(Duration::from_millis(50)); // No faster than real time, or the server skips it
+        server.tick(0.05);
+        while let Ok(packet) = inbox.try_recv() {
+            match packet {
+                Packet::ChunkColumn { cx, cz, .. } => assert!(received.insert((cx, cz)), "column {:?} sent twice", (cx, cz)),
+                Packet::UnloadColumn { cx, cz } => assert!(received.remove(&(cx, cz)), "told to unload {:?}, which was never sent", (cx, cz)),
+                _ => {}
+            }
+        }
+        if received.is_disjoint(&around_spawn) { break; }
+        assert!(Instant::now() < deadline, "still holding {:?}", received);
+    }
+}
+
+#[test]
+fn columns_survive_the_wire() {
+    let mut source = World::new(7);
+    for cx in -1..=1 {
+        for cz in -1..=1 { source.ensure_column(cx, cz); }
+    }
+    let mut client = World::new(7);
+    for cx in -1..=1 {
+        for cz in -1..=1 {
+            let packet = Packet::chunk_column(&source, cx, cz).unwrap();
+            packet.validate().unwrap();
+            let Packet::ChunkColumn { sections, .. } = packet else { unreachable!() };
+            client.insert_column(cx, cz, decode_column(&sections).unwrap());
+        }
+    }
+    for y in 0..128 {
+        let pos = BlockPos { x: 5, y, z: -9 };
+        assert_eq!(client.get_block(pos), source.get_block(pos), "block at {:?}", pos);
+    }
+    // Only the middle column has all its neighbours
+    assert!(client.lit_columns.contains(&(0, 0)) && !client.lit_columns.contains(&(1, 1)));
+
+    let truncated = Packet::ChunkColumn { cx: 0, cz: 0, sections: vec![Vec::new(); 3] };
+    assert!(truncated.validate().is_err());
+    assert!(Packet::chunk_column(&source, 5, 5).is_err());
+}
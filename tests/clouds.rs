@@ -0,0 +1,55 @@
+//! Cloud layer test: fancy clouds are closed boxes without inner walls, fast clouds are flat, and
+//! stormier weather makes the same cloud thicker and darker.
+
+use glam::Vec3;
+use minecraft_clone::clouds::{cloud_cells, CloudMode, CELL};
+use minecraft_clone::weather_system::{Cloud, WeatherSystem, WeatherType};
+
+fn sky_with_one_cloud() -> WeatherSystem {
+    let mut weather = WeatherSystem::new();
+    weather.clouds.push(Cloud {
+        position: Vec3::new(40.0, 120.0, -25.0), size: Vec3::new(96.0, 20.0, 72.0), density: 0.4, height: 120.0,
+        movement_speed: Vec3::ZERO, precipitation: None, color: [0.6, 0.6, 0.7, 0.5], particles: Vec::new(),
+    });
+    weather
+}
+
+#[test]
+fn fancy_cells_hide_shared_walls_and_fast_cells_are_flat() {
+    let weather = sky_with_one_cloud();
+    assert!(cloud_cells(&weather, CloudMode::Off).is_empty());
+
+    let cells = cloud_cells(&weather, CloudMode::Fancy);
+    assert!(cells.len() > 20, "only {} cells", cells.len());
+    let at = |x: f32, z: f32| cells.iter().find(|c| (c.min[0] - x).abs() < 0.01 && (c.min[2] - z).abs() < 0.01);
+    for c in &cells {
+        assert!(c.size[1] > 0.0 && c.faces & 0b11 == 0b11);
+        for (bit, (dx, dz)) in [(-CELL, 0.0), (CELL, 0.0), (0.0, CELL), (0.0, -CELL)].into_iter().enumerate() {
+            let open = c.faces & (1 << (bit + 2)) != 0;
+            assert_eq!(open, at(c.min[0] + dx, c.min[2] + dz).is_none(), "wall {} of the cell at {:?}", bit + 2, c.min);
+        }
+        assert!((c.min[0] + CELL * 0.5 - 40.0).abs() < 48.0 && (c.min[2] + CELL * 0.5 + 25.0).abs() < 36.0);
+    }
+
+    let flat = cloud_cells(&weather, CloudMode::Fast);
+    assert_eq!(flat.len(), cells.len());
+    assert!(flat.iter().all(|c| c.size[1] == 0.0 && c.faces == 0b11));
+}
+
+#[test]
+fn storms_thicken_and_darken_clouds() {
+    let mut weather = sky_with_one_cloud();
+    let clear = cloud_cells(&weather, CloudMode::Fancy);
+    weather.current_weather = WeatherType::Thunderstorm;
+    weather.target_weather = WeatherType::Thunderstorm;
+    let storm = cloud_cells(&weather, CloudMode::Fancy);
+    assert!(storm.len() >= clear.len());
+    assert!(storm[0].size[1] > clear[0].size[1] * 2.0);
+    assert!(storm[0].color[0] < clear[0].color[0] * 0.5);
+
+    // Halfway through clearing up, the look is halfway between
+    weather.target_weather = WeatherType::Clear;
+    weather.transition_progress = 0.5;
+    let half = cloud_cells(&weather, CloudMode::Fancy)[0];
+    assert!((half.size[1] - (clear[0].size[1] + storm[0].size[1]) * 0.5).abs() < 1e-3);
+}
@@ -0,0 +1,44 @@
+//! Entity model test: the walk cycle follows how far an entity actually moves (remote players arrive
+//! in 20 Hz steps, mobs every frame) and winds down once it stops.
+#![cfg(feature = "client")] // Needs wgpu
+
+use glam::Vec3;
+use minecraft_clone::entity_models::{bake_models, Animator, ModelInstance, ModelKind, SKIN_PLAYER};
+
+fn player_at(position: Vec3) -> ModelInstance {
+    ModelInstance { kind: ModelKind::Humanoid, id: 7, position, yaw: 0.0, look_yaw: 0.0, look_pitch: 0.0, gait: None, arm_pitch: 0.0, skin: SKIN_PLAYER, scale: 0.9, light: 15.0 }
+}
+
+#[test]
+fn walk_cycle_follows_stepped_movement_and_settles() {
+    let mut animator = Animator::default();
+    let mut position = Vec3::new(0.5, 64.0, 0.5);
+    let mut peak: f32 = 0.0;
+    // 60 fps frames, with the position only updating every third frame at 4 blocks per second.
+    for frame in 0..120 {
+        if frame % 3 == 0 { position.x += 4.0 / 20.0; }
+        let pose = animator.pose(&player_at(position), frame as f32 / 60.0);
+        peak = peak.max(pose.pose[0].abs());
+    }
+    assert!(peak > 0.4, "walking never swung the legs (peak {})", peak);
+    let mut last = 0.0;
+    for frame in 120..240 { last = animator.pose(&player_at(position), frame as f32 / 60.0).pose[0]; }
+    assert!(last.abs() < 1e-3, "legs still swinging {} after stopping", last);
+
+    // Head yaw is relative to the body and wraps the short way round.
+    let mut looking = player_at(position);
+    looking.yaw = 3.0;
+    looking.look_yaw = -3.0;
+    assert!((animator.pose(&looking, 5.0).pose[2] - (std::f32::consts::TAU - 6.0)).abs() < 1e-4);
+}
+
+#[test]
+fn baked_models_are_closed_boxes() {
+    let (vertices, indices, ranges) = bake_models();
+    assert_eq!(ranges.len(), ModelKind::ALL.len());
+    assert_eq!(ranges.last().unwrap().end as usize, indices.len());
+    for (kind, range) in ModelKind::ALL.iter().zip(&ranges) {
+        assert_eq!((range.end - range.start) as usize, kind.parts().len() * 36, "{:?}", kind);
+    }
+    assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+}
@@ -0,0 +1,78 @@
+//! Fluid test: a source on a wide flat floor spreads one level less per block (two for lava) and
+//! stops where the level runs out, pours straight down over an edge, and everything it fed dries
+//! up again once the source is gone.
+
+use minecraft_clone::world::{BlockPos, BlockType, World};
+
+const FLOOR: i32 = 110;
+const CENTRE: (i32, i32) = (8, 8);
+
+/// A stone floor 31 blocks across at `FLOOR` with open air above, so nothing nearby is a drop.
+/// Written straight into the sections: the fluids don't care about light.
+fn flat_world() -> World {
+    let mut world = World::new(777);
+    for cx in -2..=2 { for cz in -2..=2 { world.ensure_column(cx, cz); } }
+    for x in CENTRE.0 - 15..=CENTRE.0 + 15 {
+        for z in CENTRE.1 - 15..=CENTRE.1 + 15 {
+            for y in FLOOR..=FLOOR + 3 {
+                let chunk = world.chunks.get_mut(&(x.div_euclid(16), y / 16, z.div_euclid(16))).unwrap();
+                chunk.set_block(x.rem_euclid(16) as usize, y as usize % 16, z.rem_euclid(16) as usize, if y == FLOOR { BlockType::Stone } else { BlockType::Air });
+            }
+        }
+    }
+    world
+}
+
+fn run_ticks(world: &mut World, ticks: u32) {
+    for _ in 0..ticks { world.update_ticks(0.05); }
+}
+
+/// Level of the fluid `steps` blocks along +x from the centre, on the floor.
+fn level_at(world: &World, steps: i32) -> u8 {
+    world.get_block(BlockPos { x: CENTRE.0 + steps, y: FLOOR + 1, z: CENTRE.1 }).get_fluid_level()
+}
+
+#[test]
+fn water_spreads_seven_blocks_and_dries_up() {
+    let mut world = flat_world();
+    let source = BlockPos { x: CENTRE.0, y: FLOOR + 1, z: CENTRE.1 };
+    world.place_block(source, BlockType::Water);
+    run_ticks(&mut world, 200);
+
+    for steps in 0..=8 { assert_eq!(level_at(&world, steps), 8u8.saturating_sub(steps as u8), "{} blocks out", steps); }
+    // Diagonally it's the walking distance that counts
+    assert_eq!(world.get_block(BlockPos { x: CENTRE.0 + 3, y: FLOOR + 1, z: CENTRE.1 + 3 }), BlockType::WaterFlow2);
+    assert!(!world.get_block(BlockPos { x: source.x, y: FLOOR + 2, z: source.z }).is_water(), "water never climbs");
+
+    world.place_block(source, BlockType::Air);
+    run_ticks(&mut world, 200);
+    for x in CENTRE.0 - 8..=CENTRE.0 + 8 {
+        for z in CENTRE.1 - 8..=CENTRE.1 + 8 {
+            assert!(!world.get_block(BlockPos { x, y: FLOOR + 1, z }).is_water(), "water left at {:?}", (x, z));
+        }
+    }
+}
+
+#[test]
+fn lava_spreads_three_blocks() {
+    let mut world = flat_world();
+    world.place_block(BlockPos { x: CENTRE.0, y: FLOOR + 1, z: CENTRE.1 }, BlockType::Lava);
+    run_ticks(&mut world, 400);
+    assert_eq!((0..=4).map(|steps| level_at(&world, steps)).collect::<Vec<_>>(), vec![8, 6, 4, 2, 0]);
+}
+
+#[test]
+fn water_pours_over_an_edge() {
+    let mut world = flat_world();
+    // A pit two blocks out: the flow heads for it and falls in
+    let pit = BlockPos { x: CENTRE.0 + 2, y: FLOOR, z: CENTRE.1 };
+    world.place_block(pit, BlockType::Air);
+    world.place_block(BlockPos { x: pit.x, y: FLOOR - 1, z: pit.z }, BlockType::Stone);
+    world.place_block(BlockPos { x: CENTRE.0, y: FLOOR + 1, z: CENTRE.1 }, BlockType::Water);
+    run_ticks(&mut world, 100);
+
+    assert!(world.get_block(pit).is_water(), "nothing fell into the pit");
+    assert_eq!(world.get_block(pit).get_fluid_level(), 7, "poured water stands almost full");
+    // Only the way toward the drop is taken
+    assert_eq!(level_at(&world, -1), 0);
+}
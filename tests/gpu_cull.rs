@@ -0,0 +1,65 @@
+//! GPU culling test: the compute pass must make the same visible/hidden call for every chunk as the
+//! CPU fallback. Runs on whatever adapter is available, software ones (lavapipe, llvmpipe)
+//! included, and is skipped with a note when there is none.
+#![cfg(feature = "client")] // Needs wgpu
+
+use minecraft_clone::gpu_cull::{sphere_visible, ChunkCullData, DrawIndexedIndirect, GpuCuller, CHUNK_RADIUS};
+use minecraft_clone::Player;
+use wgpu::util::DeviceExt;
+
+fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor { backends: wgpu::Backends::all(), ..Default::default() });
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions { power_preference: wgpu::PowerPreference::LowPower, compatible_surface: None, force_fallback_adapter: false }))?;
+    let flags = adapter.get_downlevel_capabilities().flags;
+    if !flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS) { return None; }
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()
+}
+
+#[test]
+fn gpu_cull_matches_cpu_cull() {
+    let Some((device, queue)) = device() else {
+        eprintln!("no adapter with compute shaders, skipping");
+        return;
+    };
+
+    let mut player = Player::new();
+    player.position = glam::Vec3::new(3.0, 70.0, -5.0);
+    player.rotation = glam::Vec3::new(-0.3, 0.8, 0.0);
+    let aspect = 16.0 / 9.0;
+    let view_proj = player.build_view_projection_matrix(aspect);
+    let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: None, contents: bytemuck::cast_slice(&view_proj), usage: wgpu::BufferUsages::UNIFORM });
+
+    // A block of sections all around the player, each with a distinct draw range
+    let mut rows = Vec::new();
+    for cx in -12..12 { for cy in 0..8 { for cz in -12..12 {
+        let n = rows.len() as u32;
+        rows.push(ChunkCullData { pos: [cx as f32 * 16.0 + 8.0, cy as f32 * 16.0 + 8.0, cz as f32 * 16.0 + 8.0, CHUNK_RADIUS], index_count: 6 + n % 7, base_vertex: n as i32 * 4, base_index: n * 12, _pad: 0 });
+    }}}
+
+    let mut culler = GpuCuller::new(&device, &camera_buffer);
+    culler.upload(&device, &queue, &rows);
+    let size = (rows.len() * std::mem::size_of::<DrawIndexedIndirect>()) as u64;
+    let readback = device.create_buffer(&wgpu::BufferDescriptor { label: None, size, usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    culler.dispatch(&mut encoder);
+    encoder.copy_buffer_to_buffer(&culler.indirect_draw_buffer, 0, &readback, 0, size);
+    queue.submit(Some(encoder.finish()));
+
+    readback.slice(..).map_async(wgpu::MapMode::Read, |r| r.expect("map readback"));
+    device.poll(wgpu::Maintain::Wait);
+    let draws: Vec<DrawIndexedIndirect> = bytemuck::cast_slice(&readback.slice(..).get_mapped_range()).to_vec();
+
+    let planes = player.get_frustum_planes(aspect);
+    let mut visible = 0;
+    for (row, draw) in rows.iter().zip(&draws) {
+        assert_eq!((draw.index_count, draw.first_index, draw.base_vertex, draw.first_instance), (row.index_count, row.base_index, row.base_vertex, 0));
+        let center = glam::Vec3::from_slice(&row.pos[0..3]);
+        // Spheres grazing a plane may land either side of it depending on float rounding
+        let margin = planes.iter().map(|p| (glam::Vec3::from_slice(&p[0..3]).dot(center) + p[3] + CHUNK_RADIUS).abs()).fold(f32::INFINITY, f32::min);
+        if margin < 1e-3 { continue; }
+        let expected = sphere_visible(&planes, center, CHUNK_RADIUS) as u32;
+        assert_eq!(draw.instance_count, expected, "chunk at {:?}", row.pos);
+        visible += expected;
+    }
+    assert!(visible > 0 && (visible as usize) < rows.len(), "camera should see some but not all chunks ({} of {})", visible, rows.len());
+}
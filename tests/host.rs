@@ -0,0 +1,85 @@
+//! Hosting test: with the host and three clients over loopback, every client learns its own id and
+//! who else is playing, moves are relayed to everyone but the mover under its real id, broadcasts
+//! reach every client, targeted sends only one, and a client leaving is announced to the rest.
+
+use std::time::{Duration, Instant};
+
+use minecraft_clone::network::{NetworkManager, Packet, Target, HOST_ID};
+use minecraft_clone::world::{BlockPos, BlockType};
+
+fn next(net: &mut NetworkManager) -> Packet {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(packet) = net.try_recv() { return packet; }
+        assert!(Instant::now() < deadline, "nothing arrived");
+        std::thread::sleep(Duration::from_millis(2));
+    }
+}
+
+/// Joins and checks the greeting: the handshake with the expected id, then everyone already there.
+fn join(port: u16, id: u32) -> NetworkManager {
+    let mut net = NetworkManager::join(format!("127.0.0.1:{}", port));
+    assert!(matches!(next(&mut net), Packet::Handshake { seed: 99, id: given, .. } if given == id));
+    for other in HOST_ID..id {
+        assert!(matches!(next(&mut net), Packet::PlayerJoin { id } if id == other));
+    }
+    net
+}
+
+/// Hosts on a free port, returning the host and that port.
+fn host() -> (NetworkManager, u16) {
+    let host = NetworkManager::host("0".to_string(), 99);
+    let port = host.local_addr().expect("a host has a listening address").port();
+    (host, port)
+}
+
+fn block(block: BlockType) -> Packet {
+    Packet::BlockUpdate { pos: BlockPos { x: 1, y: 70, z: 1 }, block }
+}
+
+#[test]
+fn packets_reach_the_right_players() {
+    let (mut host, port) = host();
+    let mut clients = Vec::new();
+    for id in 2..=4 {
+        clients.push(join(port, id));
+        assert!(matches!(next(&mut host), Packet::PlayerJoin { id: joined } if joined == id));
+        for (earlier, client) in (2..id).zip(&mut clients) {
+            assert!(matches!(next(client), Packet::PlayerJoin { id: joined } if joined == id), "player {} wasn't told", earlier);
+        }
+    }
+    let [a, b, c] = &mut clients[..] else { unreachable!() };
+
+    // Whatever id a client claims, everyone else sees the one the host gave it
+    a.send_packet(Packet::PlayerMove { id: 77, x: 5.0, y: 70.0, z: 5.0, rx: 0.0, ry: 1.0 });
+    for net in [&mut host, &mut *b, &mut *c] {
+        assert!(matches!(next(net), Packet::PlayerMove { id: 2, x, .. } if x == 5.0));
+    }
+
+    host.send_to(Target::Player(3), block(BlockType::Glass));
+    host.send_packet(block(BlockType::Stone));
+    assert!(matches!(next(b), Packet::BlockUpdate { block: BlockType::Glass, .. }));
+    // A didn't get its own move back, and neither A nor C got B's packet
+    for net in [a, b, c] {
+        assert!(matches!(next(net), Packet::BlockUpdate { block: BlockType::Stone, .. }));
+    }
+}
+
+#[test]
+fn leaving_players_are_announced() {
+    let (mut host, port) = host();
+    let mut a = join(port, 2);
+    let mut b = join(port, 3);
+    assert!(matches!(next(&mut a), Packet::PlayerJoin { id: 3 }));
+
+    b.send_packet(Packet::Disconnect);
+    assert!(matches!(next(&mut a), Packet::PlayerLeave { id: 3 }));
+    assert!(matches!(next(&mut host), Packet::PlayerJoin { id: 2 }));
+    assert!(matches!(next(&mut host), Packet::PlayerJoin { id: 3 }));
+    assert!(matches!(next(&mut host), Packet::PlayerLeave { id: 3 }));
+
+    // Nothing from B is relayed any more, but the host still reaches whoever is left
+    b.send_packet(block(BlockType::Glass));
+    host.send_packet(block(BlockType::Stone));
+    assert!(matches!(next(&mut a), Packet::BlockUpdate { block: BlockType::Stone, .. }));
+}
@@ -0,0 +1,78 @@
+//! Lighting tests: a column the generation workers light on their own and the main thread then
+//! joins to its neighbours ends up exactly as lit as one lit in place, and single edits spread
+//! light out (a step at a time, or straight down for sunlight) and take all of it away again.
+
+use std::time::{Duration, Instant};
+
+use minecraft_clone::lighting::LightChannel;
+use minecraft_clone::world::{BlockPos, BlockType, World, WORLD_HEIGHT};
+
+#[test]
+fn background_lighting_matches_lighting_in_place() {
+    let mut in_place = World::new(777);
+    for cx in -2..=2 { for cz in -2..=2 { in_place.ensure_column(cx, cz); } }
+
+    let mut background = World::new(777);
+    let started = Instant::now();
+    while !(-1..=1).all(|cx| (-1..=1).all(|cz| background.lit_columns.contains(&(cx, cz)))) {
+        assert!(started.elapsed() < Duration::from_secs(120), "columns never got lit");
+        background.request_chunks_around(0, 0, 3);
+        background.collect_generated();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    for x in 0..16 { for z in 0..16 { for y in 0..WORLD_HEIGHT {
+        let pos = BlockPos { x, y, z };
+        assert_eq!(background.get_block(pos), in_place.get_block(pos), "block at {:?}", pos);
+        for ch in [LightChannel::Sky, LightChannel::Block] {
+            assert_eq!(background.light_channel_at(pos, ch), in_place.light_channel_at(pos, ch), "{:?} light at {:?}", ch, pos);
+        }
+    }}}
+}
+
+/// Every cell inside a closed stone box spanning `lo`..=`hi` in column (0, 0), built high in the air.
+fn build_box(world: &mut World, lo: BlockPos, hi: BlockPos) -> Vec<BlockPos> {
+    let mut inside = Vec::new();
+    for x in lo.x..=hi.x { for y in lo.y..=hi.y { for z in lo.z..=hi.z {
+        let pos = BlockPos { x, y, z };
+        let shell = x == lo.x || x == hi.x || y == lo.y || y == hi.y || z == lo.z || z == hi.z;
+        world.place_block(pos, if shell { BlockType::Stone } else { BlockType::Air });
+        if !shell { inside.push(pos); }
+    }}}
+    inside
+}
+
+#[test]
+fn edits_add_and_remove_light() {
+    let mut world = World::new(777);
+    for cx in -2..=2 { for cz in -2..=2 { world.ensure_column(cx, cz); } }
+    assert!(world.lit_columns.contains(&(0, 0)));
+    let light = |world: &World, pos: BlockPos, ch: LightChannel| world.light_channel_at(pos, ch).unwrap();
+
+    let (lo, hi) = (BlockPos { x: 4, y: 110, z: 4 }, BlockPos { x: 12, y: 118, z: 12 });
+    let inside = build_box(&mut world, lo, hi);
+    assert!(inside.iter().all(|&p| light(&world, p, LightChannel::Sky) == 0 && light(&world, p, LightChannel::Block) == 0));
+
+    // A torch lights the box one level less per step
+    let centre = BlockPos { x: 8, y: 114, z: 8 };
+    world.place_block(centre, BlockType::Torch);
+    for &p in &inside {
+        let steps = (p.x - centre.x).abs() + (p.y - centre.y).abs() + (p.z - centre.z).abs();
+        assert_eq!(light(&world, p, LightChannel::Block) as i32, 14 - steps, "at {:?}", p);
+    }
+    world.place_block(centre, BlockType::Air);
+    assert!(inside.iter().all(|&p| light(&world, p, LightChannel::Block) == 0), "torch light left behind");
+
+    // A hole in the roof lets full sunlight straight down, fading sideways
+    let hole = BlockPos { x: 8, y: hi.y, z: 8 };
+    world.place_block(hole, BlockType::Air);
+    for y in lo.y + 1..hi.y {
+        assert_eq!(light(&world, BlockPos { x: 8, y, z: 8 }, LightChannel::Sky), 15);
+        assert_eq!(light(&world, BlockPos { x: 9, y, z: 8 }, LightChannel::Sky), 14);
+        assert_eq!(light(&world, BlockPos { x: 10, y, z: 10 }, LightChannel::Sky), 11);
+    }
+    world.place_block(hole, BlockType::Glass);
+    assert_eq!(light(&world, BlockPos { x: 8, y: lo.y + 1, z: 8 }, LightChannel::Sky), 15, "glass lets sunlight through");
+    world.place_block(hole, BlockType::Stone);
+    assert!(inside.iter().all(|&p| light(&world, p, LightChannel::Sky) == 0), "sunlight left behind");
+}
@@ -0,0 +1,52 @@
+//! LOD ring test: a fresh mesh takes the level of the ring it's in, and a mesh already at some level
+//! keeps it until it's `LOD_HYSTERESIS` (1.5) chunks past either edge of its ring, at every ring.
+#![cfg(feature = "client")] // The renderer picks the levels
+
+use minecraft_clone::renderer::{lod_for_distance, wanted_lod, LOD_RINGS};
+
+#[test]
+fn fresh_meshes_take_their_ring() {
+    assert_eq!(lod_for_distance(0.0, None), 0);
+    for (i, &ring) in LOD_RINGS.iter().enumerate() {
+        assert_eq!(lod_for_distance(ring, None), i as u32, "the edge belongs to the inner ring");
+        assert_eq!(lod_for_distance(ring + 0.01, None), i as u32 + 1);
+    }
+    assert_eq!(lod_for_distance(1000.0, None), LOD_RINGS.len() as u32);
+}
+
+#[test]
+fn meshes_hold_their_level_across_ring_edges() {
+    for (i, &ring) in LOD_RINGS.iter().enumerate() {
+        let (inner, outer) = (i as u32, i as u32 + 1);
+        // Moving out: the inner level holds up to 1.5 chunks past the edge
+        assert_eq!(lod_for_distance(ring + 1.5, Some(inner)), inner);
+        assert_eq!(lod_for_distance(ring + 1.51, Some(inner)), outer);
+        // Moving in: the outer level holds up to 1.5 chunks inside it
+        assert_eq!(lod_for_distance(ring - 1.5, Some(outer)), outer);
+        assert_eq!(lod_for_distance(ring - 1.51, Some(outer)), inner);
+    }
+    // A mesh far from its ring jumps straight to the right level
+    assert_eq!(lod_for_distance(0.0, Some(2)), 0);
+    assert_eq!(lod_for_distance(40.0, Some(0)), 2);
+}
+
+#[test]
+fn wanted_lod_goes_by_horizontal_distance() {
+    let player = (100, 3, -20);
+    // Straight above or below the player is full detail, however far
+    assert_eq!(wanted_lod(player, (100, 7, -20), None), 0);
+    // 9 chunks along x is past the first ring; 6 by 6 (8.49) is too
+    assert_eq!(wanted_lod(player, (109, 3, -20), None), 1);
+    assert_eq!(wanted_lod(player, (106, 0, -14), None), 1);
+    // ... but a full-detail mesh there stays until 9.5
+    assert_eq!(wanted_lod(player, (109, 3, -20), Some(0)), 0);
+    assert_eq!(wanted_lod(player, (110, 3, -20), Some(0)), 1);
+    // Coming back in, a half-detail mesh at 7 chunks stays, at 6 it's remeshed
+    assert_eq!(wanted_lod(player, (93, 3, -20), Some(1)), 1);
+    assert_eq!(wanted_lod(player, (94, 3, -20), Some(1)), 0);
+    // Same at the second ring: half detail holds at 17, drops to quarter at 18; back in at 14
+    assert_eq!(wanted_lod(player, (100, 3, -37), Some(1)), 1);
+    assert_eq!(wanted_lod(player, (100, 3, -38), Some(1)), 2);
+    assert_eq!(wanted_lod(player, (100, 3, -35), Some(2)), 2);
+    assert_eq!(wanted_lod(player, (100, 3, -34), Some(2)), 1);
+}
@@ -0,0 +1,121 @@
+//! Mesher test: a section snapshot sees exactly what the world does around the section, so meshes
+//! don't depend on how much else is loaded; faces land in the pass their block draws in, with
+//! translucent blocks hiding only their own kind; and ambient occlusion darkens the corners next
+//! to occluders and turns each quad to split along its darker diagonal.
+#![cfg(feature = "client")] // The mesher lives in the renderer
+
+use minecraft_clone::chunk_snapshot::ChunkSnapshot;
+use minecraft_clone::renderer::{mesh_chunk, sorted_translucent_indices, MeshTask, RenderLayer, TranslucentQuad, Vertex};
+use minecraft_clone::world::{BlockPos, BlockType, World};
+
+/// The section everything is built in: the top one of column (0, 0), y 112 to 127.
+const SECTION: (i32, i32, i32) = (0, 7, 0);
+
+fn loaded(radius: i32) -> World {
+    let mut world = World::new(2024);
+    for cx in -radius..=radius { for cz in -radius..=radius { world.ensure_column(cx, cz); } }
+    world
+}
+
+/// `SECTION` emptied out and refilled with `blocks`, then lit.
+fn scene(blocks: &[((i32, i32, i32), BlockType)]) -> MeshTask {
+    let mut world = loaded(1);
+    let chunk = world.chunks.get_mut(&SECTION).unwrap();
+    for x in 0..16 { for y in 0..16 { for z in 0..16 { chunk.set_block(x, y, z, BlockType::Air); } } }
+    for &((x, y, z), block) in blocks { chunk.set_block(x as usize, (y - 112) as usize, z as usize, block); }
+    chunk.is_empty = false;
+    world.light_column(0, 0);
+    mesh_chunk(&ChunkSnapshot::capture(&world, SECTION), 0)
+}
+
+/// The four vertices of each quad an index list draws.
+fn quads<'a>(task: &'a MeshTask, indices: &[u32]) -> Vec<&'a [Vertex]> {
+    indices.chunks(6).map(|q| &task.vertices[q[0] as usize..q[0] as usize + 4]).collect()
+}
+
+fn centre(quad: &[Vertex]) -> [f32; 3] {
+    std::array::from_fn(|i| quad.iter().map(|v| v.position[i]).sum::<f32>() / 4.0)
+}
+
+#[test]
+fn snapshots_see_what_the_world_sees() {
+    let world = loaded(2);
+    for cy in 0..8 {
+        let snap = ChunkSnapshot::capture(&world, (0, cy, 0));
+        if snap.is_empty { continue; }
+        for x in -1..=16 { for y in -1..=16 { for z in -1..=16 {
+            let pos = BlockPos { x, y: cy * 16 + y, z };
+            assert_eq!(snap.block(x, y, z), world.get_block(pos), "block at {:?}", pos);
+            assert_eq!(snap.light(x, y, z), world.get_light_world(pos), "light at {:?}", pos);
+        }}}
+    }
+
+    // Loading more of the world further out doesn't change a thing
+    let bigger = loaded(3);
+    for cy in 0..8 {
+        let (a, b) = (mesh_chunk(&ChunkSnapshot::capture(&world, (0, cy, 0)), 0), mesh_chunk(&ChunkSnapshot::capture(&bigger, (0, cy, 0)), 0));
+        assert_eq!(bytemuck::cast_slice::<Vertex, u8>(&a.vertices), bytemuck::cast_slice::<Vertex, u8>(&b.vertices), "section {}", cy);
+        assert_eq!((a.opaque, a.cutout, a.translucent.len()), (b.opaque, b.cutout, b.translucent.len()));
+    }
+}
+
+#[test]
+fn faces_draw_in_their_blocks_layer() {
+    assert_eq!(RenderLayer::of(BlockType::Stone), RenderLayer::Opaque);
+    for block in [BlockType::Leaves, BlockType::TallGrass, BlockType::Torch] { assert_eq!(RenderLayer::of(block), RenderLayer::Cutout, "{:?}", block); }
+    for block in [BlockType::Water, BlockType::Lava, BlockType::Glass, BlockType::Ice] { assert_eq!(RenderLayer::of(block), RenderLayer::Translucent, "{:?}", block); }
+
+    let task = scene(&[((4, 120, 4), BlockType::Glass), ((5, 120, 4), BlockType::Glass),
+                       ((4, 120, 10), BlockType::Leaves), ((5, 120, 10), BlockType::Leaves),
+                       ((8, 120, 7), BlockType::Water), ((9, 120, 7), BlockType::Glass),
+                       ((12, 120, 12), BlockType::TallGrass), ((12, 115, 4), BlockType::Stone)]);
+    let translucent: Vec<&[Vertex]> = task.translucent.iter().map(|q| &task.vertices[q.first_vertex as usize..q.first_vertex as usize + 4]).collect();
+    let (cutout, opaque) = (quads(&task, &task.cutout), quads(&task, &task.opaque));
+    // Faces standing in the plane `x`, centred on `z`
+    let walls = |faces: &[&[Vertex]], x: f32, z: f32| faces.iter().filter(|q| q.iter().all(|v| v.position[0] == x) && centre(q)[2] == z).count();
+
+    // Panes of glass show no walls between them, but leaves do, and so does water against glass
+    assert_eq!(walls(&translucent, 5.0, 4.5), 0);
+    assert_eq!(walls(&cutout, 5.0, 10.5), 2);
+    assert_eq!(walls(&translucent, 9.0, 7.5), 2);
+    assert!(cutout.iter().any(|q| centre(q)[0] == 12.5 && centre(q)[2] == 12.5), "plants are cutout");
+    assert!(!opaque.is_empty() && opaque.iter().all(|q| centre(q)[1] < 117.0), "only the stone is opaque");
+}
+
+#[test]
+fn translucent_faces_sort_back_to_front() {
+    let quads = [TranslucentQuad { center: [0.0, 0.0, 1.0], first_vertex: 0 },
+                 TranslucentQuad { center: [0.0, 0.0, 9.0], first_vertex: 4 },
+                 TranslucentQuad { center: [0.0, 0.0, 5.0], first_vertex: 8 }];
+    let firsts = |eye: glam::Vec3| sorted_translucent_indices(&quads, eye).chunks(6).map(|q| q[0]).collect::<Vec<_>>();
+    assert_eq!(firsts(glam::Vec3::ZERO), vec![4, 8, 0]);
+    assert_eq!(firsts(glam::Vec3::new(0.0, 0.0, 10.0)), vec![0, 8, 4]);
+}
+
+#[test]
+fn occlusion_darkens_corners_and_turns_quads() {
+    // A stone floor with two blocks touching at a corner standing on it
+    let mut blocks: Vec<_> = (6..=12).flat_map(|x| (6..=12).map(move |z| ((x, 116, z), BlockType::Stone))).collect();
+    blocks.extend([((8, 117, 8), BlockType::Stone), ((9, 117, 9), BlockType::Stone)]);
+    let task = scene(&blocks);
+    let floor: Vec<&[Vertex]> = quads(&task, &task.opaque).into_iter().filter(|q| q.iter().all(|v| v.position[1] == 117.0)).collect();
+    let top_of = |x: f32, z: f32| floor.iter().copied().find(|q| q.iter().all(|v| (x..=x + 1.0).contains(&v.position[0]) && (z..=z + 1.0).contains(&v.position[2]))).unwrap();
+    let ao_at = |quad: &[Vertex], x: f32, z: f32| quad.iter().find(|v| v.position[0] == x && v.position[2] == z).unwrap().ao;
+
+    // Squeezed between both blocks the corner is fully closed off; beside one it loses a level
+    let between = top_of(9.0, 8.0);
+    assert_eq!(ao_at(between, 9.0, 9.0), 0.45);
+    assert_eq!(ao_at(between, 10.0, 9.0), 0.82);
+    assert_eq!(ao_at(between, 10.0, 8.0), 1.0);
+    // Faces that shade differently don't merge, but open floor does
+    assert_eq!(between.iter().map(|v| v.position[0]).fold(0.0, f32::max), 10.0);
+    assert!(floor.iter().any(|q| q.iter().any(|v| (v.position[0] - q[0].position[0]).abs() > 1.0)));
+
+    // Only the diagonal corner of this one is dark, so the split runs through it
+    let diagonal = top_of(10.0, 10.0);
+    let dark: Vec<usize> = (0..4).filter(|&k| diagonal[k].ao < 1.0).collect();
+    assert_eq!(dark.len(), 1);
+    assert!(dark[0] % 2 == 0, "split along the bright diagonal");
+    let shade = |v: &Vertex| v.ao * v.light;
+    for quad in quads(&task, &task.opaque) { assert!(shade(&quad[0]) + shade(&quad[2]) <= shade(&quad[1]) + shade(&quad[3])); }
+}
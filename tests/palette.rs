@@ -0,0 +1,76 @@
+//! Paletted section test: indices widen as blocks are added without disturbing the ones already
+//! there, and compacting drops unused palette entries, narrows back down and returns to a single
+//! block once the section is uniform again.
+
+use minecraft_clone::world::{BlockType, PalettedBlocks};
+
+/// Bits per block index as the section is saved: bincode writes the palette (a u64 length, then
+/// a u32 per block) and then the width.
+fn width(blocks: &PalettedBlocks) -> u8 {
+    let saved = bincode::serialize(blocks).unwrap();
+    let palette_len = u64::from_le_bytes(saved[..8].try_into().unwrap()) as usize;
+    saved[8 + 4 * palette_len]
+}
+
+/// Cell `i` of a section, in the order the test fills them.
+fn cell(i: usize) -> (usize, usize, usize) { (i % 16, (i / 16) % 16, i / 256) }
+
+/// A block type for every palette entry the widest index can address.
+fn kinds() -> Vec<BlockType> {
+    let all = [BlockType::Stone, BlockType::Dirt, BlockType::Grass, BlockType::Sand, BlockType::Glass, BlockType::Planks,
+               BlockType::Cobblestone, BlockType::CoalOre, BlockType::IronOre, BlockType::GoldOre, BlockType::DiamondOre,
+               BlockType::Wood, BlockType::Leaves, BlockType::Snow, BlockType::Gravel, BlockType::Clay, BlockType::Sandstone];
+    all.to_vec()
+}
+
+#[test]
+fn palette_grows_with_the_blocks_in_it() {
+    let mut blocks = PalettedBlocks::filled(BlockType::Air);
+    assert_eq!(width(&blocks), 0);
+    blocks.set(3, 3, 3, BlockType::Air);
+    assert_eq!(width(&blocks), 0, "writing the fill block allocates nothing");
+
+    let kinds = kinds();
+    // Air plus n kinds: 2 entries fit in 1 bit, 4 in 2, 16 in 4, 17 need 8
+    for (n, bits) in [(1, 1), (3, 2), (15, 4), (16, 8)] {
+        for (i, &kind) in kinds.iter().enumerate().take(n) {
+            let (x, y, z) = cell(i);
+            blocks.set(x, y, z, kind);
+        }
+        assert_eq!(width(&blocks), bits, "{} kinds", n);
+        for (i, &kind) in kinds.iter().enumerate().take(n) {
+            let (x, y, z) = cell(i);
+            assert_eq!(blocks.get(x, y, z), kind);
+        }
+        assert_eq!(blocks.get(15, 15, 15), BlockType::Air);
+        assert!(blocks.is_valid());
+    }
+}
+
+#[test]
+fn compacting_shrinks_the_palette() {
+    let mut blocks = PalettedBlocks::filled(BlockType::Stone);
+    let kinds = kinds();
+    for (i, &kind) in kinds.iter().enumerate() {
+        let (x, y, z) = cell(i);
+        blocks.set(x, y, z, kind);
+    }
+    assert_eq!(width(&blocks), 8);
+
+    // Back down to stone and two others: 3 entries fit in 2 bits
+    for i in 2..kinds.len() {
+        let (x, y, z) = cell(i);
+        blocks.set(x, y, z, BlockType::Stone);
+    }
+    blocks.set(9, 9, 9, BlockType::Glass);
+    blocks.compact();
+    assert_eq!(width(&blocks), 2);
+    assert!(blocks.is_valid());
+    assert_eq!((blocks.get(0, 0, 0), blocks.get(1, 0, 0), blocks.get(9, 9, 9), blocks.get(2, 0, 0)), (kinds[0], kinds[1], BlockType::Glass, BlockType::Stone));
+
+    // All stone again: no index data at all
+    for (x, y, z) in [(0, 0, 0), (1, 0, 0), (9, 9, 9)] { blocks.set(x, y, z, BlockType::Stone); }
+    blocks.compact();
+    assert_eq!(width(&blocks), 0);
+    assert!(blocks.is_uniform(BlockType::Stone));
+}
@@ -0,0 +1,63 @@
+//! Post-processing test: the passes run on the world but not the HUD, grading follows the settings,
+//! and a shader that doesn't compile is rejected without breaking the frame. Only the FXAA setting
+//! turns on the FXAA pass; the anti-aliasing modes the renderer lacks don't pretend to be it.
+//! Like the golden tests, this passes with a note when there's no wgpu adapter at all.
+#![cfg(feature = "client")] // Needs wgpu
+
+use minecraft_clone::config_system::AntiAliasing;
+use minecraft_clone::player::Player;
+use minecraft_clone::post::PostSettings;
+use minecraft_clone::renderer::Renderer;
+use minecraft_clone::world::World;
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 90;
+
+fn headless() -> Option<Renderer<'static>> {
+    let renderer = pollster::block_on(Renderer::new_headless(WIDTH, HEIGHT));
+    if renderer.is_none() { eprintln!("no wgpu adapter available, skipping post-processing test"); }
+    renderer.map(|mut r| { r.freeze_time(120.0); r.set_day_time(0.2); r })
+}
+
+/// Nothing generated, so the frame is sky plus the HUD; returns the sky above the crosshair.
+fn sky(renderer: &mut Renderer) -> [u8; 4] {
+    let (world, mut player) = (World::new(1), Player::new());
+    player.rotation.x = 0.4;
+    player.prev_rotation = player.rotation;
+    renderer.update_camera(&player, WIDTH as f32 / HEIGHT as f32, 1.0);
+    renderer.render_game(&world, &player, false, (0.0, 0.0), WIDTH, HEIGHT).unwrap();
+    renderer.capture_frame().get_pixel(WIDTH / 2, HEIGHT / 4).0
+}
+
+#[test]
+fn exposure_grades_the_world() {
+    let Some(mut renderer) = headless() else { return };
+    let graded = sky(&mut renderer);
+    renderer.set_post_processing(PostSettings { exposure: 0.0, ..Default::default() });
+    assert_eq!(&sky(&mut renderer)[..3], &[0, 0, 0]);
+
+    // Without tonemapping the sky keeps its plain colour, which ACES would have darkened
+    renderer.set_post_processing(PostSettings { tonemap: false, fxaa: false, bloom: false, ..Default::default() });
+    let plain = sky(&mut renderer);
+    assert!(plain[2] > graded[2], "{:?} vs {:?}", plain, graded);
+}
+
+#[test]
+fn broken_post_shader_keeps_the_old_passes() {
+    let Some(mut renderer) = headless() else { return };
+    let before = sky(&mut renderer);
+    let source = include_str!("../src/post.wgsl");
+    let error = renderer.switch_shader(&source.replace("fn fs_fxaa", "fn fs_fxaa_renamed")).unwrap_err();
+    assert!(!error.is_empty());
+    assert_eq!(sky(&mut renderer), before);
+    renderer.switch_shader(source).unwrap();
+    assert_eq!(sky(&mut renderer), before);
+}
+
+#[test]
+fn only_fxaa_runs_the_fxaa_pass() {
+    assert!(AntiAliasing::FXAA.fxaa());
+    for mode in [AntiAliasing::Off, AntiAliasing::MSAA2x, AntiAliasing::MSAA4x, AntiAliasing::MSAA8x, AntiAliasing::TAA] {
+        assert!(!mode.fxaa(), "{:?}", mode);
+    }
+}
@@ -0,0 +1,100 @@
+//! Prediction test: replaying unacknowledged input on a snapshot lands where local prediction did
+//! when client and server agree and carries a correction forward when they don't, and a respawn is
+//! left to the server; remote players are blended between updates; the link simulator delays, keeps
+//! order and only drops movement.
+
+use std::time::{Duration, Instant};
+
+use glam::Vec3;
+use minecraft_clone::netsim::{LinkConditioner, LinkConditions};
+use minecraft_clone::player::Player;
+use minecraft_clone::prediction::{PlayerSnapshot, Prediction, RemoteEntities, INTERPOLATION_DELAY};
+use minecraft_clone::world::{RemotePlayer, World};
+use minecraft_clone::AudioSystem;
+
+const DT: f32 = 1.0 / 120.0;
+
+#[test]
+fn reconciliation_replays_unacknowledged_input() {
+    // Nothing is loaded, so the player walks forward while falling through empty air
+    let (world, audio) = (World::new(5), AudioSystem::silent());
+    let mut client = Player::new();
+    client.keys.forward = true;
+    client.rotation.y = 0.7;
+    let mut prediction = Prediction::new();
+    for _ in 0..30 { prediction.step(&mut client, &world, DT, &audio, false); }
+    let commands = prediction.outgoing();
+    assert_eq!(commands.len(), 30);
+    assert!(commands.iter().all(|c| !c.respawn));
+    assert!(prediction.outgoing().is_empty(), "nothing new to send");
+
+    // The server has applied the first ten commands to its copy
+    let mut server = Player::new();
+    for command in &commands[..10] { command.simulate(&mut server, &world, &audio); }
+    let predicted = client.position;
+    prediction.reconcile(&mut client, &PlayerSnapshot::of(2, 10, &server), &world, &audio);
+    assert!(client.position.distance(predicted) < 1e-4, "{} vs {}", client.position, predicted);
+    assert!(client.keys.forward && client.rotation.y == 0.7, "the replay leaves the current input alone");
+
+    // The server saw it a block further along x: the correction carries through the replay
+    server.position.x += 1.0;
+    prediction.reconcile(&mut client, &PlayerSnapshot::of(2, 10, &server), &world, &audio);
+    assert!((client.position - predicted - Vec3::X).length() < 1e-4, "{} vs {}", client.position, predicted);
+
+    // Acknowledged commands aren't sent again; coming back from dying is asked of the server once
+    prediction.respawn();
+    prediction.step(&mut client, &world, DT, &audio, false);
+    prediction.step(&mut client, &world, DT, &audio, false);
+    let commands = prediction.outgoing();
+    assert_eq!(commands.iter().map(|c| c.seq).collect::<Vec<_>>(), (11..=32).collect::<Vec<_>>());
+    assert_eq!(commands.iter().filter(|c| c.respawn).map(|c| c.seq).collect::<Vec<_>>(), vec![31]);
+
+    // Held in place, the player just goes wherever the server put it
+    client.stasis = true;
+    server.position = Vec3::new(40.0, 90.0, 40.0);
+    prediction.reconcile(&mut client, &PlayerSnapshot::of(2, 32, &server), &world, &audio);
+    assert_eq!(client.position, Vec3::new(40.0, 90.0, 40.0));
+}
+
+#[test]
+fn remote_players_are_interpolated() {
+    let mut remotes = RemoteEntities::new();
+    let mut players: Vec<RemotePlayer> = Vec::new();
+    remotes.apply(1.0, &mut players);
+    assert!(players.is_empty());
+
+    remotes.push(7, 0.0, Vec3::ZERO, 3.0, 0.0);
+    remotes.push(7, 0.1, Vec3::new(1.0, 0.0, 2.0), -3.0, 0.4);
+    remotes.apply(0.05 + INTERPOLATION_DELAY, &mut players);
+    assert_eq!(players.len(), 1);
+    assert!((players[0].position - Vec3::new(0.5, 0.0, 1.0)).length() < 1e-5);
+    // Yaw turns the short way round, through pi rather than through zero
+    assert!((players[0].rotation - std::f32::consts::PI).abs() < 1e-3, "{}", players[0].rotation);
+    assert!((players[0].pitch - 0.2).abs() < 1e-5);
+
+    // Past the newest update it holds still
+    remotes.apply(5.0, &mut players);
+    assert_eq!(players[0].position, Vec3::new(1.0, 0.0, 2.0));
+    remotes.remove(7);
+    remotes.apply(5.0, &mut players);
+    assert!(players.is_empty());
+}
+
+#[test]
+fn link_simulator_delays_and_drops_movement() {
+    let conditions = LinkConditions::parse("100,50,50").unwrap();
+    assert_eq!(conditions, LinkConditions { latency: Duration::from_millis(100), jitter: Duration::from_millis(50), loss: 0.5 });
+    assert!(LinkConditions::parse("100,x").is_none());
+
+    let start = Instant::now();
+    let mut link = LinkConditioner::new(conditions, 9);
+    let delivered = (0..1000).filter(|&i| link.push(start, i, i % 2 == 0)).count();
+    // Half of the droppable half is lost, none of the rest
+    assert!((700..800).contains(&delivered), "{} delivered", delivered);
+    assert_eq!(link.pop(start + Duration::from_millis(99)), None);
+    let late = start + Duration::from_millis(151);
+    let arrived: Vec<_> = std::iter::from_fn(|| link.pop(late)).collect();
+    assert_eq!(arrived.len(), delivered);
+    assert!(arrived.windows(2).all(|w| w[0] < w[1]), "packets came out of order");
+    assert!((1..1000).step_by(2).all(|i| arrived.contains(&i)));
+}
@@ -0,0 +1,102 @@
+//! Region storage test: sections come back from disk exactly as saved (across region borders, after
+//! growing in place, and through a fresh store), a world's saved edits win over regeneration and
+//! survive unloading, and blocks are saved as their position in `BlockType`, so the blocks older
+//! saves contain must keep the positions they had.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use minecraft_clone::region::RegionStore;
+use minecraft_clone::world::{BlockPos, BlockType, Chunk, World, CHUNK_SIZE_X, CHUNK_SIZE_Y, CHUNK_SIZE_Z};
+
+/// An empty save directory of its own for each test.
+fn save_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("region-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A section with `kinds` different blocks scattered through it.
+fn section(kinds: usize) -> Chunk {
+    let blocks = [BlockType::Stone, BlockType::Dirt, BlockType::Glass, BlockType::CoalOre, BlockType::Planks, BlockType::WaterFlow3];
+    let mut chunk = Chunk::new();
+    for x in 0..CHUNK_SIZE_X { for y in 0..CHUNK_SIZE_Y { for z in 0..CHUNK_SIZE_Z {
+        chunk.set_block(x, y, z, blocks[(x * 7 + y * 3 + z) % kinds]);
+    }}}
+    chunk.is_empty = false;
+    chunk
+}
+
+fn same_blocks(a: &Chunk, b: &Chunk) -> bool {
+    (0..CHUNK_SIZE_X).all(|x| (0..CHUNK_SIZE_Y).all(|y| (0..CHUNK_SIZE_Z).all(|z| a.get_block(x, y, z) == b.get_block(x, y, z))))
+}
+
+#[test]
+fn sections_round_trip() {
+    let dir = save_dir("round-trip");
+    let store = RegionStore::open(&dir).unwrap();
+    let keys = [(0, 0, 0), (31, 7, 31), (32, 3, 0), (-1, 2, -33)];
+    for (i, &(cx, cy, cz)) in keys.iter().enumerate() { store.save_chunk(cx, cy, cz, &section(i + 1)).unwrap(); }
+    // A bigger payload than the slot was first written with
+    store.save_chunk(0, 0, 0, &section(6)).unwrap();
+    store.flush();
+    drop(store);
+
+    let store = RegionStore::open(&dir).unwrap();
+    for (i, &(cx, cy, cz)) in keys.iter().enumerate() {
+        let loaded = store.load_chunk(cx, cy, cz).unwrap();
+        let kinds = if i == 0 { 6 } else { i + 1 };
+        assert!(same_blocks(&loaded, &section(kinds)), "section {:?}", (cx, cy, cz));
+        assert!(loaded.decorated && !loaded.is_empty);
+    }
+    assert!(store.load_chunk(1, 0, 0).is_none(), "never saved");
+    assert!(store.load_chunk(0, 8, 0).is_none(), "above the world");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn saved_edits_win_over_regeneration() {
+    let dir = save_dir("edits");
+    let store = Arc::new(RegionStore::open(&dir).unwrap());
+    let mut world = World::new(5);
+    world.storage = Some(store.clone());
+    world.generate_terrain_around(40, 40, 0);
+    let pos = BlockPos { x: 40 * 16 + 5, y: 125, z: 40 * 16 + 9 };
+    world.place_block(pos, BlockType::Bookshelf);
+    assert_eq!(world.save_modified(), 1);
+    assert_eq!(world.save_modified(), 0, "nothing left to save");
+
+    let mut reloaded = World::new(5);
+    reloaded.storage = Some(store);
+    reloaded.generate_terrain_around(40, 40, 0);
+    assert_eq!(reloaded.get_block(pos), BlockType::Bookshelf);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn edits_survive_unloading() {
+    let dir = save_dir("unloading");
+    let mut world = World::new(5);
+    world.storage = Some(Arc::new(RegionStore::open(&dir).unwrap()));
+    world.view_distance = 0;
+    world.ensure_column(40, 40);
+    let pos = BlockPos { x: 40 * 16 + 5, y: 125, z: 40 * 16 + 9 };
+    world.place_block(pos, BlockType::Bookshelf);
+
+    world.unload_far_chunks(0, 0, usize::MAX);
+    assert!(!world.chunks.contains_key(&(40, 7, 40)));
+    world.ensure_column(40, 40);
+    assert_eq!(world.get_block(pos), BlockType::Bookshelf);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn blocks_keep_their_saved_ids() {
+    let id = |block: BlockType| u32::from_le_bytes(bincode::serialize(&block).unwrap()[..4].try_into().unwrap());
+    for (block, saved) in [(BlockType::Air, 0), (BlockType::Water, 9), (BlockType::Lava, 83), (BlockType::Cloud, 97),
+                           (BlockType::CraftingTable, 98), (BlockType::CookedPorkchop, 109)] {
+        assert_eq!(id(block), saved, "{:?}", block);
+    }
+    // Blocks added since come after all of them
+    assert!(id(BlockType::WaterFlow1) > 109 && id(BlockType::LavaFlow7) > 109);
+}
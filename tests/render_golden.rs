@@ -0,0 +1,205 @@
+//! Screenshot regression test: fixed scenes are rendered headless and compared with the PNGs in
+//! tests/golden/. Adapters rasterise slightly differently, so frames only have to match within a
+//! tolerance. If a visual change is intentional, regenerate with
+//! `UPDATE_GOLDEN=1 cargo test --test render_golden` and commit the new images.
+//! Without any wgpu adapter (not even a software one) the tests pass with a note instead.
+#![cfg(feature = "client")] // Needs wgpu
+
+use std::path::PathBuf;
+
+use image::RgbaImage;
+use minecraft_clone::combat_system::{AnimationState, Mob, MobType};
+use minecraft_clone::clouds::CloudMode;
+use minecraft_clone::player::Player;
+use minecraft_clone::renderer::Renderer;
+use minecraft_clone::world::{BlockPos, BlockType, ItemEntity, RemotePlayer, World, WORLD_HEIGHT};
+use minecraft_clone::weather_system::{Cloud, WeatherSystem, WeatherType};
+use minecraft_clone::worldgen_check::GOLDEN_SEEDS;
+use minecraft_clone::MainMenu;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+/// Where the water and item animations are pinned.
+const FROZEN_TIME: f32 = 120.0;
+/// Mid-morning, so the terrain shots have a low sun and visible shadows.
+const DAY_TIME: f32 = 0.2;
+const CURSOR: (f64, f64) = (4.0, 4.0);
+/// In chunks; one more ring is generated so every rendered column is decorated and lit.
+const RENDER_DISTANCE: u32 = 3;
+
+/// A channel may drift this far before its pixel counts as changed...
+const CHANNEL_TOLERANCE: u8 = 24;
+/// ...and this share of the frame may change: enough for rasteriser and filtering differences
+/// between adapters, far too little to hide a missing HUD element or a wrong texture.
+const MAX_CHANGED: f64 = 0.01;
+
+fn headless() -> Option<Renderer<'static>> {
+    let renderer = pollster::block_on(Renderer::new_headless(WIDTH, HEIGHT));
+    if renderer.is_none() { eprintln!("no wgpu adapter available, skipping golden frame test"); }
+    renderer.map(|mut r| { r.freeze_time(FROZEN_TIME); r.set_day_time(DAY_TIME); r })
+}
+
+fn check_golden(name: &str, frame: &RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        frame.save(&path).unwrap();
+        return;
+    }
+    let golden = image::open(&path).unwrap_or_else(|e| panic!("{}: {} (regenerate with UPDATE_GOLDEN=1)", path.display(), e)).to_rgba8();
+    assert_eq!(golden.dimensions(), frame.dimensions(), "{} has the wrong size", path.display());
+    let changed = golden.pixels().zip(frame.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0).any(|(x, y)| x.abs_diff(y) > CHANNEL_TOLERANCE))
+        .count();
+    let share = changed as f64 / (WIDTH * HEIGHT) as f64;
+    if share > MAX_CHANGED {
+        let out = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden-failures");
+        std::fs::create_dir_all(&out).unwrap();
+        frame.save(out.join(format!("{}.png", name))).unwrap();
+        panic!("{}: {:.2}% of pixels differ from {} (frame saved to {})", name, share * 100.0, path.display(), out.display());
+    }
+}
+
+/// Terrain around the origin for one of the worldgen golden seeds, and a player hovering over it
+/// looking out across the hills.
+fn terrain_scene() -> (World, Player) {
+    let mut world = World::new(GOLDEN_SEEDS[1]);
+    world.generate_terrain_around(0, 0, RENDER_DISTANCE as i32 + 1);
+    world.dirty_chunks.clear();
+    let ground = (0..WORLD_HEIGHT).rev().find(|&y| world.get_block(BlockPos { x: 8, y, z: 8 }) != BlockType::Air).unwrap();
+    let mut player = Player::new();
+    player.position = glam::Vec3::new(8.5, ground as f32 + 12.0, 8.5);
+    player.prev_position = player.position;
+    player.rotation = glam::Vec3::new(-0.35, 0.6, 0.0);
+    player.prev_rotation = player.rotation;
+    (world, player)
+}
+
+/// Top of the first block in the column that isn't air or part of a tree.
+fn ground(world: &World, x: i32, z: i32) -> f32 {
+    (0..WORLD_HEIGHT).rev()
+        .find(|&y| !matches!(world.get_block(BlockPos { x, y, z }), BlockType::Air | BlockType::Leaves | BlockType::Wood))
+        .unwrap() as f32 + 1.0
+}
+
+/// A flat meadow with its trees cleared, three remote players looking different ways and a line of
+/// mobs walking towards the camera past a dropped item.
+fn entity_scene() -> (World, Player, Vec<Mob>) {
+    let mut world = World::new(0xDEAD_BEEF);
+    world.generate_terrain_around(-2, 0, RENDER_DISTANCE as i32 + 1);
+    for x in -42..-20 { for z in -12..10 { for y in 50..WORLD_HEIGHT {
+        let pos = BlockPos { x, y, z };
+        if matches!(world.get_block(pos), BlockType::Leaves | BlockType::Wood) { world.set_block_world(pos, BlockType::Air); }
+    }}}
+    world.dirty_chunks.clear();
+    for (id, z) in [-3, 0, 3].into_iter().enumerate() {
+        let position = glam::Vec3::new(-31.5, ground(&world, -32, z), z as f32 + 0.5);
+        world.remote_players.push(RemotePlayer { id: id as u32, position, rotation: std::f32::consts::PI + id as f32 * 0.6, pitch: 0.5 - id as f32 * 0.5 });
+    }
+    world.entities.push(ItemEntity {
+        position: glam::Vec3::new(-34.5, ground(&world, -35, -1), -0.5), velocity: glam::Vec3::ZERO,
+        item_type: BlockType::Wood, count: 1, pickup_delay: 0.0, lifetime: 0.0, rotation: 0.0, bob_offset: 0.3,
+    });
+    let mobs = [MobType::Zombie, MobType::Skeleton, MobType::Creeper, MobType::Spider, MobType::Wolf].into_iter().enumerate().map(|(id, kind)| {
+        let z = -5 + id as i32 * 2;
+        let mut mob = Mob::new(kind, glam::Vec3::new(-28.5, ground(&world, -29, z), z as f32 + 0.5));
+        mob.id = id as u32;
+        mob.animation_state = AnimationState::Walking;
+        mob.rotation.y = -std::f32::consts::FRAC_PI_2;
+        mob
+    }).collect();
+    let mut player = Player::new();
+    player.position = glam::Vec3::new(-37.5, ground(&world, -38, 1) + 3.5, 0.5);
+    player.prev_position = player.position;
+    player.rotation = glam::Vec3::new(-0.35, 0.0, 0.0);
+    player.prev_rotation = player.rotation;
+    (world, player, mobs)
+}
+
+/// Renders until every section in range has been meshed by the workers, then returns that frame.
+fn render_settled(renderer: &mut Renderer, world: &World, player: &Player) -> RgbaImage {
+    renderer.set_render_distance(RENDER_DISTANCE);
+    renderer.update_camera(player, WIDTH as f32 / HEIGHT as f32, 1.0);
+    for _ in 0..2000 {
+        renderer.render_game(world, player, false, CURSOR, WIDTH, HEIGHT).unwrap();
+        if !renderer.chunk_meshes.is_empty() && renderer.pending_chunks.is_empty() { return renderer.capture_frame(); }
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    panic!("meshing never settled ({} sections pending)", renderer.pending_chunks.len());
+}
+
+#[test]
+fn main_menu_matches_golden() {
+    let Some(mut renderer) = headless() else { return };
+    renderer.render_main_menu(&MainMenu::new_main(), WIDTH, HEIGHT).unwrap();
+    check_golden("main_menu", &renderer.capture_frame());
+}
+
+#[test]
+fn loading_screen_matches_golden() {
+    let Some(mut renderer) = headless() else { return };
+    renderer.loading_progress = 0.6;
+    renderer.loading_message = "Generating terrain".to_string();
+    renderer.render_loading_screen().unwrap();
+    check_golden("loading_screen", &renderer.capture_frame());
+}
+
+#[test]
+fn terrain_view_matches_golden() {
+    let Some(mut renderer) = headless() else { return };
+    let (world, player) = terrain_scene();
+    check_golden("terrain_view", &render_settled(&mut renderer, &world, &player));
+}
+
+#[test]
+fn inventory_matches_golden() {
+    let Some(mut renderer) = headless() else { return };
+    let (world, mut player) = terrain_scene();
+    for (item, count) in [(BlockType::Stone, 64), (BlockType::Dirt, 12), (BlockType::Wood, 5), (BlockType::Glass, 1)] {
+        for _ in 0..count { player.inventory.add_item(item); }
+    }
+    player.inventory_open = true;
+    check_golden("inventory", &render_settled(&mut renderer, &world, &player));
+}
+
+#[test]
+fn entities_match_golden() {
+    let Some(mut renderer) = headless() else { return };
+    let (world, player, mut mobs) = entity_scene();
+    render_settled(&mut renderer, &world, &player);
+    // Step the clock while the mobs walk so their limbs are caught mid-stride.
+    for frame in 1..=12 {
+        renderer.freeze_time(FROZEN_TIME + frame as f32 * 0.05);
+        for mob in &mut mobs { mob.position.x -= 0.1; }
+        renderer.submit_models(mobs.iter().map(|m| m.model_instance(&world)));
+        renderer.render_game(&world, &player, false, CURSOR, WIDTH, HEIGHT).unwrap();
+    }
+    check_golden("entities", &renderer.capture_frame());
+}
+
+#[test]
+fn clouds_match_golden() {
+    let Some(mut renderer) = headless() else { return };
+    let (world, mut player) = terrain_scene();
+    player.rotation.x = 0.3;
+    player.prev_rotation = player.rotation;
+    // Two clouds ahead of the player, drawn as fancy boxes in an overcast sky
+    let mut weather = WeatherSystem::new();
+    weather.current_weather = WeatherType::Cloudy;
+    weather.target_weather = WeatherType::Cloudy;
+    for (offset, size) in [(glam::Vec3::new(70.0, 35.0, 45.0), glam::Vec3::new(64.0, 14.0, 48.0)), (glam::Vec3::new(40.0, 45.0, -10.0), glam::Vec3::new(40.0, 12.0, 56.0))] {
+        weather.clouds.push(Cloud { position: player.position + offset, size, density: 0.4, height: 120.0, movement_speed: glam::Vec3::ZERO, precipitation: None, color: [0.6, 0.6, 0.7, 0.5], particles: Vec::new() });
+    }
+    renderer.set_cloud_mode(CloudMode::Fancy);
+    renderer.update_camera(&player, WIDTH as f32 / HEIGHT as f32, 1.0);
+    renderer.update_clouds(&weather);
+    check_golden("clouds", &render_settled(&mut renderer, &world, &player));
+}
+
+#[test]
+fn frozen_frames_repeat_exactly() {
+    let Some(mut renderer) = headless() else { return };
+    renderer.render_main_menu(&MainMenu::new_main(), WIDTH, HEIGHT).unwrap();
+    let first = renderer.capture_frame();
+    renderer.render_main_menu(&MainMenu::new_main(), WIDTH, HEIGHT).unwrap();
+    assert!(first == renderer.capture_frame());
+}
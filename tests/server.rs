@@ -0,0 +1,177 @@
+//! Dedicated server test: real clients over loopback get the seed, their id and the MOTD, the
+//! server puts them at spawn and ignores positions they claim, their input is simulated once per
+//! command (no faster than real time) and everyone sees the result under the id the server
+//! assigned, block edits land in the server's world and reach the other players, players hear
+//! about each other joining and leaving, and a full server turns newcomers away. A client can't
+//! give itself noclip, flight or speed: claiming them in its input still leaves it walking into walls.
+
+use std::net::TcpStream;
+use std::time::Duration;
+
+use glam::Vec3;
+use minecraft_clone::config_system::NetworkConfig;
+use minecraft_clone::network::Packet;
+use minecraft_clone::player::Player;
+use minecraft_clone::prediction::{InputCommand, PlayerSnapshot, KEY_FLYING, KEY_FORWARD, KEY_NOCLIP};
+use minecraft_clone::protocol::{write_packet, PacketReader};
+use minecraft_clone::server::Server;
+use minecraft_clone::world::{BlockPos, BlockType, World};
+use minecraft_clone::AudioSystem;
+
+/// A server whose spawn is ready, so players are placed as soon as they join.
+fn server(max_players: u32) -> Server {
+    let config = NetworkConfig { server_port: 0, max_players, view_distance: 1, motd: "Test world".to_string(), ..Default::default() };
+    let mut server = Server::bind(World::new(4242), config).unwrap();
+    while !server.world.decorated_columns.contains(&(0, 0)) { server.tick(0.05); }
+    server
+}
+
+struct Client { stream: TcpStream, reader: PacketReader<TcpStream> }
+
+impl Client {
+    fn connect(server: &mut Server) -> Self {
+        let stream = TcpStream::connect(("127.0.0.1", server.local_addr().unwrap().port())).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let reader = PacketReader::new(stream.try_clone().unwrap());
+        server.tick(0.05);
+        Client { stream, reader }
+    }
+    fn send(&mut self, server: &mut Server, packet: Packet) {
+        write_packet(&mut self.stream, &packet).unwrap();
+        // The reader thread needs a moment to hand the packet to the server
+        for _ in 0..20 { std::thread::sleep(Duration::from_millis(5)); server.tick(0.05); }
+    }
+    /// Next packet that isn't terrain streaming, a block update or a player snapshot.
+    fn recv(&mut self) -> Option<Packet> {
+        loop {
+            match self.reader.read_packet().unwrap() {
+                Some(Packet::ChunkColumn { .. } | Packet::UnloadColumn { .. } | Packet::BlockUpdate { .. } | Packet::PlayerState(_)) => continue,
+                other => return other,
+            }
+        }
+    }
+    /// What the next update of the block at `pos` sets it to (the game tick changes others).
+    fn block(&mut self, pos: BlockPos) -> BlockType {
+        loop {
+            if let Some(Packet::BlockUpdate { pos: at, block }) = self.reader.read_packet().unwrap() {
+                if at == pos { return block; }
+            }
+        }
+    }
+    /// Next snapshot of player `id` that includes its command `ack`.
+    fn state(&mut self, id: u32, ack: u32) -> PlayerSnapshot {
+        loop {
+            if let Some(Packet::PlayerState(s)) = self.reader.read_packet().unwrap() {
+                if s.id == id && s.ack >= ack { return s; }
+            }
+        }
+    }
+}
+
+/// A command that walks along +x for `dt` seconds.
+fn walk(seq: u32, dt: f32) -> InputCommand {
+    InputCommand { seq, dt, keys: KEY_FORWARD, yaw: 0.0, pitch: 0.0, admin_speed: 1.0, respawn: false }
+}
+
+/// Where `commands` take a player standing still at `from`, by the server's own physics.
+fn simulated(server: &Server, from: Vec3, commands: &[InputCommand]) -> Vec3 {
+    let mut player = Player::new();
+    player.position = from;
+    for command in commands { command.simulate(&mut player, &server.world, &AudioSystem::silent()); }
+    player.position
+}
+
+#[test]
+fn clients_are_greeted_and_relayed() {
+    let mut server = server(4);
+    let mut a = Client::connect(&mut server);
+    let mut b = Client::connect(&mut server);
+    for (client, id) in [(&mut a, 1), (&mut b, 2)] {
+        assert!(matches!(client.recv(), Some(Packet::Handshake { seed: 4242, id: given, .. }) if given == id));
+        assert!(matches!(client.recv(), Some(Packet::ServerInfo { ref motd, max_players: 4, .. }) if motd == "Test world"));
+    }
+    // Each hears about the other, whichever joined first
+    assert!(matches!(b.recv(), Some(Packet::PlayerJoin { id: 1 })));
+    assert!(matches!(a.recv(), Some(Packet::PlayerJoin { id: 2 })));
+    assert_eq!(server.players().len(), 2);
+
+    // Positions are the server's to decide: both start at spawn, and claiming one does nothing
+    let spawn = a.state(2, 0).position;
+    assert_eq!(b.state(1, 0).position, spawn, "a newcomer sees players who haven't moved");
+    a.send(&mut server, Packet::PlayerMove { id: 1, x: 900.0, y: 70.0, z: 900.0, rx: 0.0, ry: 1.0 });
+    a.send(&mut server, Packet::PlayerInput { commands: vec![walk(1, 0.05)] });
+    let expected = simulated(&server, spawn, &[walk(1, 0.05)]);
+    assert!(b.state(1, 1).position.distance(expected) < 1e-4);
+    // The mover hears which command its own snapshot got to
+    assert_eq!(a.state(1, 1).ack, 1);
+
+    // Repeated commands are applied once
+    let steps: Vec<_> = (1..=3).map(|seq| walk(seq, 0.05)).collect();
+    b.send(&mut server, Packet::PlayerInput { commands: steps[..2].to_vec() });
+    b.send(&mut server, Packet::PlayerInput { commands: steps[1..].to_vec() });
+    let seen = a.state(2, 3);
+    assert_eq!(seen.ack, 3);
+    assert!(seen.position.distance(simulated(&server, spawn, &steps)) < 1e-4);
+
+    // Four seconds of input at once: all acknowledged, but only what real time allows is run
+    let burst: Vec<_> = (4..44).map(|seq| walk(seq, 0.1)).collect();
+    b.send(&mut server, Packet::PlayerInput { commands: burst.clone() });
+    let rushed = a.state(2, 43);
+    assert_eq!(rushed.ack, 43);
+    let allowed = simulated(&server, seen.position, &burst[..6]);
+    assert!(rushed.position.x > seen.position.x && rushed.position.x <= allowed.x + 1e-4, "{} vs at most {}", rushed.position, allowed);
+
+    // Coming back from dying puts the player at spawn, wherever it was
+    let respawn = InputCommand { respawn: true, ..walk(44, 0.05) };
+    b.send(&mut server, Packet::PlayerInput { commands: vec![respawn] });
+    let back = a.state(2, 44).position;
+    let expected = simulated(&server, spawn, &[respawn]);
+    assert_eq!((back.x, back.z), (expected.x, expected.z));
+
+    let pos = BlockPos { x: 3, y: 120, z: 4 };
+    b.send(&mut server, Packet::BlockUpdate { pos, block: BlockType::Glass });
+    assert_eq!(server.world.get_block(pos), BlockType::Glass);
+    assert_eq!(a.block(pos), BlockType::Glass);
+
+    drop(a);
+    for _ in 0..100 {
+        if server.players().len() == 1 { break; }
+        std::thread::sleep(Duration::from_millis(10));
+        server.tick(0.05);
+    }
+    assert_eq!(server.players().iter().map(|p| p.0).collect::<Vec<_>>(), vec![2]);
+    assert!(matches!(b.recv(), Some(Packet::PlayerLeave { id: 1 })));
+}
+
+#[test]
+fn claimed_noclip_still_collides() {
+    let mut server = server(1);
+    let mut client = Client::connect(&mut server);
+    let spawn = client.state(1, 0).position;
+
+    // A wall two blocks ahead along +x, from below the feet to well over the head
+    let (wx, y, z) = (spawn.x.floor() as i32 + 2, spawn.y.floor() as i32, spawn.z.floor() as i32);
+    while !server.world.chunks.contains_key(&(wx.div_euclid(16), 0, z.div_euclid(16))) { server.tick(0.05); }
+    for dy in -3..=4 {
+        for dz in -2..=2 { server.world.place_block(BlockPos { x: wx, y: y + dy, z: z + dz }, BlockType::Stone); }
+    }
+
+    // A second of flying through anything at top speed, by the client's say-so
+    let cheat = |seq| InputCommand { keys: KEY_FLYING | KEY_NOCLIP | KEY_FORWARD, admin_speed: 10.0, ..walk(seq, 0.05) };
+    for seq in (1..=20).step_by(2) {
+        client.send(&mut server, Packet::PlayerInput { commands: vec![cheat(seq), cheat(seq + 1)] });
+    }
+    let stopped = client.state(1, 20).position;
+    assert!(stopped.x > spawn.x && stopped.x < wx as f32, "{} went through the wall at x = {}", stopped, wx);
+}
+
+#[test]
+fn full_server_turns_players_away() {
+    let mut server = server(1);
+    let mut first = Client::connect(&mut server);
+    assert!(matches!(first.recv(), Some(Packet::Handshake { .. })));
+    let mut second = Client::connect(&mut server);
+    assert!(matches!(second.recv(), Some(Packet::Disconnect)));
+    assert!(second.recv().is_none());
+    assert_eq!(server.players().len(), 1);
+}
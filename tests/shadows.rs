@@ -0,0 +1,69 @@
+//! Cascade fitting test: each cascade's light-space box must contain the whole slice of the camera
+//! frustum it's responsible for, and the slices must tile the shadow distance in order. The
+//! configured shadow quality decides how many depth passes a frame runs, none when it's off, and
+//! the light follows the day time the game sets.
+#![cfg(feature = "client")] // Needs wgpu
+
+use glam::Vec3;
+use minecraft_clone::config_system::{GameConfig, ShadowQuality};
+use minecraft_clone::player::Player;
+use minecraft_clone::renderer::Renderer;
+use minecraft_clone::shadows::{cascade_matrix, cascade_splits, sun_direction, ViewFrustum, SHADOW_DISTANCE};
+use minecraft_clone::world::World;
+
+#[test]
+fn cascades_cover_their_frustum_slice() {
+    let view = ViewFrustum { eye: Vec3::new(13.5, 71.2, -40.3), forward: Vec3::new(0.6, -0.3, 0.74).normalize(), fov_y: 75.0f32.to_radians(), aspect: 16.0 / 9.0 };
+    let right = view.forward.cross(Vec3::Y).normalize();
+    let up = right.cross(view.forward);
+    let near = 0.1;
+    for count in 2..=4 {
+        let splits = cascade_splits(near, count);
+        assert_eq!(splits[count - 1], SHADOW_DISTANCE);
+        for i in 0..count {
+            let start = if i == 0 { near } else { splits[i - 1] };
+            assert!(start < splits[i], "cascade {} of {} is empty", i, count);
+            let (light, _) = sun_direction(0.15);
+            let m = cascade_matrix(&view, start, splits[i], light, 2048);
+            for d in [start, splits[i]] {
+                let (h, w) = (d * (view.fov_y * 0.5).tan(), d * (view.fov_y * 0.5).tan() * view.aspect);
+                for (sx, sy) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                    let p = m.project_point3(view.eye + view.forward * d + right * (w * sx) + up * (h * sy));
+                    assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0 && (0.0..=1.0).contains(&p.z), "cascade {} of {} misses {:?}", i, count, p);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn shadows_fade_at_the_horizon() {
+    assert_eq!(sun_direction(0.0).1, 0.0);
+    assert_eq!(sun_direction(0.25).1, 1.0);
+    assert!(sun_direction(0.25).0.abs_diff_eq(Vec3::Y, 1e-5));
+    assert!((sun_direction(0.75).1 - 0.3).abs() < 1e-5);
+}
+
+#[test]
+fn shadow_quality_and_day_time_set_the_depth_passes() {
+    let Some(mut renderer) = pollster::block_on(Renderer::new_headless(64, 64)) else {
+        eprintln!("no wgpu adapter available, skipping shadow pass test");
+        return;
+    };
+    let (world, player) = (World::new(1), Player::new());
+    let mut config = GameConfig::default();
+    renderer.set_day_time(0.2);
+    for (quality, passes) in [(ShadowQuality::Medium, 3), (ShadowQuality::Off, 0), (ShadowQuality::Ultra, 4)] {
+        config.graphics.shadow_quality = quality;
+        config.apply_graphics_settings(&mut renderer);
+        renderer.render_game(&world, &player, false, (0.0, 0.0), 64, 64).unwrap();
+        assert_eq!(renderer.shadow_passes(), passes);
+    }
+    // The sun is setting and the moon not up yet: nothing casts shadows
+    renderer.set_day_time(0.5);
+    renderer.render_game(&world, &player, false, (0.0, 0.0), 64, 64).unwrap();
+    assert_eq!(renderer.shadow_passes(), 0);
+    renderer.set_day_time(0.75);
+    renderer.render_game(&world, &player, false, (0.0, 0.0), 64, 64).unwrap();
+    assert_eq!(renderer.shadow_passes(), 4, "the moon casts them at night");
+}
@@ -0,0 +1,70 @@
+//! World tick test: scheduled updates run on the tick they come due and not before, in position
+//! order within a tick whatever order they were queued in, a position already waiting keeps its
+//! slot, a tick runs at most `MAX_SCHEDULED_PER_TICK` of them and leaves the rest for the next,
+//! and the same world with the same edits always ticks the same way.
+
+use minecraft_clone::world::{BlockPos, BlockType, World};
+
+/// Columns (0, 0) to (1, 1), with everything happening high up in the air above them.
+fn world() -> World {
+    let mut world = World::new(2024);
+    for cx in 0..=1 { for cz in 0..=1 { world.ensure_column(cx, cz); } }
+    world.ticks.record_changes();
+    world
+}
+
+/// What ticks changed since last asked, up in the air where the test builds.
+fn changes(world: &mut World) -> Vec<BlockPos> {
+    world.ticks.take_changes().into_iter().filter(|p| p.y >= 118).collect()
+}
+
+fn at(x: i32, y: i32, z: i32) -> BlockPos { BlockPos { x, y, z } }
+
+#[test]
+fn scheduled_updates_run_when_due_in_position_order() {
+    let mut world = world();
+    // Floating sand waits two ticks, then falls one block
+    for x in [10, 5, 7] { world.place_block(at(x, 124, 3), BlockType::Sand); }
+    world.schedule_tick(at(7, 124, 3), 50); // Already queued: keeps its earlier slot
+    world.tick();
+    assert!(changes(&mut world).is_empty(), "nothing is due yet");
+    world.tick();
+    assert_eq!(changes(&mut world), vec![at(5, 124, 3), at(5, 123, 3), at(7, 124, 3), at(7, 123, 3), at(10, 124, 3), at(10, 123, 3)]);
+}
+
+#[test]
+fn a_tick_runs_a_bounded_number_of_updates() {
+    let mut world = world();
+    // 1100 sand blocks written without notifying anyone, then all queued for the next tick
+    let sand: Vec<_> = (0..1100).map(|i| at(i % 32, 124 + 2 * (i / 1024), (i / 32) % 32)).collect();
+    for &p in &sand {
+        let chunk = world.chunks.get_mut(&(p.x / 16, p.y / 16, p.z / 16)).unwrap();
+        chunk.set_block((p.x % 16) as usize, (p.y % 16) as usize, (p.z % 16) as usize, BlockType::Sand);
+    }
+    for &p in &sand { world.schedule_tick(p, 1); }
+
+    world.tick();
+    assert_eq!(changes(&mut world).len(), 2 * 1024);
+    world.tick();
+    // The leftovers are the last in position order, and run on the next tick
+    let mut last = sand.clone();
+    last.sort_by_key(|p| (p.x, p.y, p.z));
+    let fell: Vec<_> = changes(&mut world).into_iter().step_by(2).collect();
+    assert_eq!(fell, last[1024..]);
+}
+
+#[test]
+fn ticking_is_deterministic() {
+    let run = || {
+        let mut world = world();
+        // A floating planks floor set alight: fire spreads at random, driven by the world's RNG
+        for x in 4..12 { for z in 4..12 { world.place_block(at(x, 120, z), BlockType::Planks); } }
+        world.place_block(at(8, 121, 8), BlockType::Fire);
+        for _ in 0..600 { world.tick(); }
+        let blocks: Vec<_> = (3..13).flat_map(|x| (3..13).flat_map(move |z| (119..124).map(move |y| at(x, y, z)))).map(|p| world.get_block(p)).collect();
+        (changes(&mut world), blocks)
+    };
+    let (first, blocks) = run();
+    assert!(first.len() > 10, "the fire should have done something");
+    assert_eq!(run(), (first, blocks));
+}
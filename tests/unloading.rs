@@ -0,0 +1,57 @@
+//! Unloading test: columns past the unload distance go, and a chunk budget too small for the view
+//! distance only costs what lies past the view and its decoration ring, never what's in view
+//! (that would just be requested and loaded again right away), so view distances are capped to
+//! what the budget can hold. Without a region store, edited columns unload like any other.
+
+use minecraft_clone::world::{BlockPos, BlockType, World, WORLD_HEIGHT};
+
+#[test]
+fn budget_never_evicts_what_is_in_view() {
+    let mut world = World::new(99);
+    world.view_distance = 2;
+    for cx in -5..=5 { for cz in -5..=5 { world.ensure_column(cx, cz); } }
+    let sections = WORLD_HEIGHT / 16;
+    let column = |world: &World, cx: i32, cz: i32| (0..sections).all(|cy| world.chunks.contains_key(&(cx, cy, cz)));
+
+    world.unload_far_chunks(0, 0, 0);
+    let dist = |cx: i32, cz: i32| cx * cx + cz * cz;
+    for cx in -5..=5 {
+        for cz in -5..=5 {
+            if dist(cx, cz) as f32 <= world.needed_distance() * world.needed_distance() {
+                assert!(column(&world, cx, cz), "column {:?} is needed", (cx, cz));
+            } else {
+                assert!(!(0..sections).any(|cy| world.chunks.contains_key(&(cx, cy, cz))), "column {:?} should have gone", (cx, cz));
+            }
+        }
+    }
+    assert!(world.unload_distance() as f32 > world.needed_distance());
+}
+
+#[test]
+fn edited_chunks_unload_without_storage() {
+    let mut world = World::new(99);
+    world.view_distance = 0;
+    world.ensure_column(10, 0);
+    let pos = BlockPos { x: 10 * 16 + 4, y: 125, z: 4 };
+    world.place_block(pos, BlockType::Glass);
+    assert!(world.chunks[&(10, 7, 0)].modified);
+
+    let removed = world.unload_far_chunks(0, 0, usize::MAX);
+    assert!(removed.contains(&(10, 7, 0)));
+    assert!(!(0..WORLD_HEIGHT / 16).any(|cy| world.chunks.contains_key(&(10, cy, 0))));
+}
+
+#[test]
+fn capped_view_distance_stays_within_budget() {
+    assert_eq!(World::max_view_distance(30000), 33, "the default budget's cap, as ResourceLimits says");
+    let budget = 200;
+    let mut world = World::new(99);
+    world.view_distance = World::max_view_distance(budget);
+    assert_eq!(world.view_distance, 1);
+    let keep = world.unload_distance();
+    for cx in -keep..=keep { for cz in -keep..=keep { world.ensure_column(cx, cz); } }
+    assert!(world.chunks.len() > budget);
+
+    world.unload_far_chunks(0, 0, budget);
+    assert!(world.chunks.len() <= budget, "{} sections loaded", world.chunks.len());
+}
@@ -0,0 +1,38 @@
+//! Worldgen regression test: every chunk in the golden area must hash to the checked-in value.
+//! If a terrain change is intentional, regenerate with
+//! `cargo run -- --worldgen-hashes > tests/golden/worldgen.txt` and commit the diff.
+
+use minecraft_clone::worldgen_check::{hash_area, parse_hashes, GOLDEN_SEEDS};
+
+const GOLDEN: &str = include_str!("golden/worldgen.txt");
+
+#[test]
+fn golden_file_covers_every_seed() {
+    let golden = parse_hashes(GOLDEN);
+    for seed in GOLDEN_SEEDS {
+        let expected = hash_area(seed).len();
+        let found = golden.iter().filter(|(s, ..)| *s == seed).count();
+        assert_eq!(found, expected, "golden file has {} chunks for seed {}, expected {}", found, seed, expected);
+    }
+}
+
+#[test]
+fn worldgen_matches_golden_hashes() {
+    let golden = parse_hashes(GOLDEN);
+    let mut mismatches = Vec::new();
+    for seed in GOLDEN_SEEDS {
+        for (key, hash) in hash_area(seed) {
+            match golden.iter().find(|(s, k, _)| *s == seed && *k == key) {
+                Some(&(_, _, want)) if want == hash => {}
+                Some(&(_, _, want)) => mismatches.push(format!("seed {} chunk {:?}: {:016x} != golden {:016x}", seed, key, hash, want)),
+                None => mismatches.push(format!("seed {} chunk {:?}: missing from golden file", seed, key)),
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{} chunks differ from tests/golden/worldgen.txt:\n{}", mismatches.len(), mismatches.join("\n"));
+}
+
+#[test]
+fn worldgen_is_repeatable() {
+    assert_eq!(hash_area(GOLDEN_SEEDS[1]), hash_area(GOLDEN_SEEDS[1]));
+}